@@ -85,6 +85,23 @@ export type SwarmProtocol<
     machineName: MachineName,
   ) => Machine<SwarmProtocolName, MachineName, MachineEventFactories>
   tagWithEntityId: (id: string) => Tags<MachineEvents>
+  /**
+   * Make an adapted machine from the projection information of a role, e.g. as computed by
+   * projectionInformation in machine-core or included in a generated machine-runner skeleton.
+   * Its states are designed like those of a machine made with makeMachine, using the
+   * names of the states of the projection.
+   * @param machineName - The role implemented by the machine.
+   * @param projectionInfo - The projection and branch tracking information of the role.
+   * @param minimize - Should be true if projectionInfo contains a minimized projection, false otherwise.
+   * @param verbose - A verbose machine prints information on event emission and reception and on state changes.
+   * @returns an {@link AdaptedMachine} to be passed to createMachineRunnerBT.
+   */
+  makeAdaptedMachine: <MachineName extends string>(
+    machineName: MachineName,
+    projectionInfo: ProjectionInfo,
+    minimize: boolean,
+    verbose?: boolean
+  ) => AdaptedMachine<SwarmProtocolName, MachineName, MachineEventFactories>
   /**
    * Adapt a machine.
    * @param role - The role implemented by the machine.
@@ -155,6 +172,8 @@ export namespace SwarmProtocol {
     return {
       tagWithEntityId: (id) => tag.withId(id),
       makeMachine: (machineName) => ImplMachine.make(swarmName, machineName, eventFactories),
+      makeAdaptedMachine: (machineName, projectionInfo, minimize, verbose?) =>
+        ImplMachine.makeAdapted(swarmName, machineName, eventFactories, projectionInfo, minimize, verbose),
       adaptMachine: (role, protocols, k, subscriptions, oldMachine, verbose?) => {
         const minimize = true
         const [mOld, mOldInitial] = oldMachine
@@ -162,7 +181,7 @@ export namespace SwarmProtocol {
         if (projectionInfo.type == 'ERROR') {
           return {data: undefined, ... projectionInfo}
         }
-        return MachineAdaptation.adaptMachine(ImplMachine.makeAdapted(swarmName, role, eventFactories, projectionInfo.data, minimize, verbose), eventFactories, mOldInitial, verbose)
+        return MachineAdaptation.adaptMachine(ImplMachine.makeAdapted(swarmName, role, eventFactories, projectionInfo.data, minimize, verbose), eventFactories, mOldInitial, k, verbose)
       }
     }
   }
@@ -683,13 +702,15 @@ namespace MachineAdaptation {
     MachineName extends string,
     MachineEventFactories extends MachineEvent.Factory.Any,
   >(
-    m: AdaptedMachine<SwarmProtocolName, MachineName, MachineEventFactories>
+    m: AdaptedMachine<SwarmProtocolName, MachineName, MachineEventFactories>,
+    k: number
   ): Map<string, ProjectionStateInfo> => {
     const projStateInfoMap: Map<string, ProjectionStateInfo> = new Map()
 
     for (let t of m.projectionInfo.projection.transitions) {
-        const sourceOriginalName = m.projectionInfo.projToMachineStates[t.source][0]
-        const targetOriginalName = m.projectionInfo.projToMachineStates[t.target][0]
+        // the entries are positional per protocol, the original machine was implemented for protocol k
+        const sourceOriginalName = m.projectionInfo.projToMachineStates[t.source][k]!
+        const targetOriginalName = m.projectionInfo.projToMachineStates[t.target][k]!
 
         if (!projStateInfoMap.has(t.source)) {
           projStateInfoMap.set(t.source, {projStateName: t.source, originalMStateName: sourceOriginalName, reactionLabels: [], commandLabels: []})
@@ -782,6 +803,7 @@ namespace MachineAdaptation {
    * @see MachineEvent.design to get started on creating MachineEventFactories
    * for the registeredEventFactories parameter.
    * @param mOldInitial - the initial state of the original machine to adapt.
+   * @param k - the index of the protocol the original machine was implemented for.
    * @param verbose - flag determining whether the generated machine
    * should print information event emission, event reception and state changes.
    */
@@ -797,10 +819,11 @@ namespace MachineAdaptation {
     mNew: AdaptedMachine<SwarmProtocolName, ProjectionName, MachineEventFactories>,
     events: readonly MachineEventFactories[],
     mOldInitial: StateFactory<SwarmProtocolName, MachineName, MachineEventFactories, any, any, any>,
+    k: number,
     verbose?: boolean,
   ): MachineResult<[AdaptedMachine<SwarmProtocolName, ProjectionName, MachineEventFactories>, StateFactory<SwarmProtocolName, ProjectionName, MachineEventFactories, StateName, StatePayload, Commands>]> => {
     // information about projection states, such as their labels incoming and outgoing and what state in old machine they may correspond to
-    const projStateInfoMap: Map<string, ProjectionStateInfo> = projStateInfo(mNew)
+    const projStateInfoMap: Map<string, ProjectionStateInfo> = projStateInfo(mNew, k)
 
     // map projection states to states in machine under constructions
     const projStateToMachineState: Map<string, StateFactory<SwarmProtocolName, ProjectionName, MachineEventFactories, StateName, StatePayload, Commands>> = new Map()
@@ -3,5 +3,9 @@ import type { JestConfigWithTsJest } from 'ts-jest'
 const jestConfig: JestConfigWithTsJest = {
   preset: 'ts-jest',
   testEnvironment: 'node',
+  moduleNameMapper: {
+    // generated code imports machine-runner by its package name
+    '^@actyx/machine-runner$': '<rootDir>/../../lib/cjs/index.js',
+  },
 }
 export default jestConfig
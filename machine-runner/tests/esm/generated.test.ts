@@ -0,0 +1,20 @@
+import { describe, expect, it } from '@jest/globals'
+import { createMockMachineRunner } from '../../lib/esm/test-utils/index.js'
+import { Events, S0, S1, S3_4, W, projectionInfo } from './protocol-generated.js'
+
+// protocol-generated.ts is the skeleton generated by machine-core for role W of a warehouse
+// protocol, a test in machine-core/src/codegen.rs checks that it is up to date.
+describe('generated machine-runner skeleton', () => {
+  it('makes an adapted machine from the projection information', () => {
+    expect(W.machineName).toBe('W')
+    expect(W.projectionInfo).toEqual(projectionInfo)
+  })
+
+  it('reacts to the events of the projection', () => {
+    const runner = createMockMachineRunner(S0, undefined)
+    runner.test.feed([Events.partID.make({})])
+    runner.test.assertAs(S1)
+    runner.test.feed([Events.part.make({}), Events.stored.make({})])
+    runner.test.assertAs(S3_4)
+  })
+})
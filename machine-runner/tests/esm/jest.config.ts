@@ -5,6 +5,8 @@ const jestConfig: JestConfigWithTsJest = {
   extensionsToTreatAsEsm: ['.ts'],
   moduleNameMapper: {
     '^(\\.{1,2}/.*)\\.js$': '$1',
+    // generated code imports machine-runner by its package name
+    '^@actyx/machine-runner$': '<rootDir>/../../lib/esm/index.js',
   },
   transform: {
     // '^.+\\.[tj]sx?$' to process js/ts with `ts-jest`
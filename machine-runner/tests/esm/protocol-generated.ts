@@ -0,0 +1,37 @@
+// Generated from the projection of role W on swarm protocol warehouse.
+import { MachineEvent, SwarmProtocol, utils } from '@actyx/machine-runner'
+import type { ProjectionInfo, Subscriptions } from 'machine-core'
+
+export namespace Events {
+  export const cancelled = MachineEvent.design('cancelled').withPayload<utils.types.SerializableObject>()
+  export const part = MachineEvent.design('part').withPayload<utils.types.SerializableObject>()
+  export const partID = MachineEvent.design('partID').withPayload<utils.types.SerializableObject>()
+  export const stored = MachineEvent.design('stored').withPayload<utils.types.SerializableObject>()
+  export const allEvents = [cancelled, part, partID, stored] as const
+}
+
+export const warehouse = SwarmProtocol.make('warehouse', Events.allEvents)
+export const subscriptions: Subscriptions = {"T":["cancelled","part","partID"],"W":["cancelled","part","partID","stored"]}
+export const projectionInfo: ProjectionInfo = {"projection":{"initial":"{ { 0 } }","transitions":[{"label":{"tag":"Input","eventType":"partID"},"source":"{ { 0 } }","target":"{ { 1 } }"},{"label":{"tag":"Execute","cmd":"request","logType":["partID"]},"source":"{ { 0 } }","target":"{ { 0 } }"},{"label":{"tag":"Input","eventType":"part"},"source":"{ { 1 } }","target":"{ { 2 } }"},{"label":{"tag":"Input","eventType":"cancelled"},"source":"{ { 1 } }","target":"{ { 3 }, { 4 } }"},{"label":{"tag":"Execute","cmd":"cancel","logType":["cancelled"],"timeout":300000},"source":"{ { 1 } }","target":"{ { 1 } }"},{"label":{"tag":"Input","eventType":"stored"},"source":"{ { 2 } }","target":"{ { 3 }, { 4 } }"},{"label":{"tag":"Execute","cmd":"store","logType":["stored"]},"source":"{ { 2 } }","target":"{ { 2 } }"}]},"branches":{"cancelled":[],"part":["stored"],"partID":["cancelled","part"],"stored":[]},"specialEventTypes":["cancelled","part"],"projToMachineStates":{"{ { 0 } }":["{ { 0 } }"],"{ { 1 } }":["{ { 1 } }"],"{ { 2 } }":["{ { 2 } }"],"{ { 3 }, { 4 } }":["{ { 3 }, { 4 } }"]}}
+
+export const W = warehouse.makeAdaptedMachine('W', projectionInfo, true)
+
+export const S0 = W.designEmpty('{ { 0 } }')
+  .command('request', [Events.partID], (_ctx) => [/* TODO */ {} as MachineEvent.Payload.Of<typeof Events.partID>])
+  .finish()
+export const S1 = W.designEmpty('{ { 1 } }')
+  // on timeout: execute 300000ms after entering the state, the runner starts no timer
+  .command('cancel', [Events.cancelled], (_ctx) => [/* TODO */ {} as MachineEvent.Payload.Of<typeof Events.cancelled>])
+  .finish()
+export const S2 = W.designEmpty('{ { 2 } }')
+  .command('store', [Events.stored], (_ctx) => [/* TODO */ {} as MachineEvent.Payload.Of<typeof Events.stored>])
+  .finish()
+export const S3_4 = W.designEmpty('{ { 3 }, { 4 } }')
+  .finish()
+
+S0.react([Events.partID], S1, (_ctx, _event) => {})
+S1.react([Events.part], S2, (_ctx, _event) => {})
+S1.react([Events.cancelled], S3_4, (_ctx, _event) => {})
+S2.react([Events.stored], S3_4, (_ctx, _event) => {})
+
+// const runner = createMachineRunnerBT(app, warehouse.tagWithEntityId('id'), S0, undefined, W)
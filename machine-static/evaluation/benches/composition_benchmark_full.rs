@@ -108,6 +108,7 @@ fn full_bench_general(c: &mut Criterion) {
                     machine_core::exact_well_formed_sub(
                         input.clone(),
                         SubscriptionsWrapped(subs.clone()),
+                        None,
                     )
                 })
             },
@@ -109,6 +109,7 @@ fn short_bench_general(c: &mut Criterion) {
                     machine_core::exact_well_formed_sub(
                         input.clone(),
                         SubscriptionsWrapped(subs.clone()),
+                        None,
                     )
                 })
             },
@@ -109,6 +109,7 @@ fn short_bench_general(c: &mut Criterion) {
                     machine_core::exact_well_formed_sub(
                         input.clone(),
                         SubscriptionsWrapped(subs.clone()),
+                        None,
                     )
                 })
             },
@@ -119,5 +120,43 @@ fn short_bench_general(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, short_bench_general);
+// The subscription fixpoints of the finer overapproximations, which do not compose the protocols.
+fn short_bench_general_granularities(c: &mut Criterion) {
+    setup_logger();
+    let mut group = c.benchmark_group("General-pattern-overapproximation-granularities-short-run");
+    group.sample_size(10);
+    let input_dir = format!("{BENCHMARK_DIR}/benchmarks/general_pattern/");
+    let mut interfacing_swarms_general = prepare_files_in_directory(input_dir);
+    interfacing_swarms_general.sort_by_key(|(size, _)| *size);
+
+    let subs = BTreeMap::<Role, BTreeSet<EventType>>::new();
+    let step: usize = 120;
+
+    for (size, interfacing_swarms) in interfacing_swarms_general.iter().step_by(step) {
+        for (name, granularity) in [("Fine", Granularity::Fine), ("Coarse", Granularity::Coarse)] {
+            group.bench_with_input(
+                BenchmarkId::new(name, size),
+                interfacing_swarms,
+                |b, input| {
+                    b.iter(|| {
+                        machine_core::overapproximated_well_formed_sub(
+                            input.clone(),
+                            SubscriptionsWrapped(subs.clone()),
+                            granularity.clone(),
+                        )
+                    })
+                },
+            );
+        }
+
+        println!("{}", SPECIAL_SYMBOL);
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    short_bench_general,
+    short_bench_general_granularities
+);
 criterion_main!(benches);
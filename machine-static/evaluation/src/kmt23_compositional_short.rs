@@ -3,6 +3,7 @@ use std::collections::{BTreeMap, BTreeSet};
 use clap::Parser;
 use evaluation::{Cli, SPECIAL_SYMBOL, Version, create_directory, prepare_simple_inputs_in_directory, wrap_and_write_sub_out_simple};
 use machine_check::{CheckResult, check_composed_swarm, check_swarm, well_formed_sub};
+use machine_core::parallel;
 use machine_core::types::typescript_types::{DataResult, EventType, Granularity, InterfacingProtocols, Role, SubscriptionsWrapped};
 
 fn main() {
@@ -15,7 +16,8 @@ fn main() {
     let subs = BTreeMap::<Role, BTreeSet<EventType>>::new();
     let two_step_granularity = Granularity::TwoStep;
     let step: usize = 120;
-    for input in inputs.iter().step_by(step) {
+    // inputs are processed independently, outputs are written in order afterwards
+    let results = parallel::map(inputs.iter().step_by(step).collect(), |input| {
         let subscriptions_wf_kmt =
             match well_formed_sub(input.proto.clone(), SubscriptionsWrapped(subs.clone())) {
                 DataResult::OK {
@@ -38,16 +40,11 @@ fn main() {
                 panic!("Not ok compositional")
             }
         }
-        wrap_and_write_sub_out_simple(
-            &input,
-            subscriptions_wf_kmt.unwrap(),
-            Version::KMT23,
-            &output_dir,
-        );
 
         let subscriptions_compositional_exact = match machine_core::exact_well_formed_sub(
             InterfacingProtocols(vec![input.proto.clone()]),
             SubscriptionsWrapped(subs.clone()),
+            None,
         ) {
             DataResult::OK {
                 data: subscriptions,
@@ -57,6 +54,7 @@ fn main() {
         match check_composed_swarm(
             InterfacingProtocols(vec![input.proto.clone()]),
             SubscriptionsWrapped(subscriptions_compositional_exact.clone().unwrap()),
+            Some(usize::MAX),
         ) {
             CheckResult::OK => (),
             CheckResult::ERROR { errors } => {
@@ -70,12 +68,6 @@ fn main() {
                 panic!("Not ok compositional")
             }
         }
-        wrap_and_write_sub_out_simple(
-            &input,
-            subscriptions_compositional_exact.unwrap(),
-            Version::CompositionalExact,
-            &output_dir,
-        );
 
         let subscriptions_compositional_approx =
             match machine_core::overapproximated_well_formed_sub(
@@ -91,6 +83,7 @@ fn main() {
         match check_composed_swarm(
             InterfacingProtocols(vec![input.proto.clone()]),
             SubscriptionsWrapped(subscriptions_compositional_approx.clone().unwrap()),
+            Some(usize::MAX),
         ) {
             CheckResult::OK => (),
             CheckResult::ERROR { errors } => {
@@ -104,13 +97,24 @@ fn main() {
                 panic!("Not ok compositional")
             }
         }
+
+        (
+            input,
+            subscriptions_wf_kmt.unwrap(),
+            subscriptions_compositional_exact.unwrap(),
+            subscriptions_compositional_approx.unwrap(),
+        )
+    });
+
+    for (input, kmt, exact, approx) in results {
+        wrap_and_write_sub_out_simple(&input, kmt, Version::KMT23, &output_dir);
+        wrap_and_write_sub_out_simple(&input, exact, Version::CompositionalExact, &output_dir);
         wrap_and_write_sub_out_simple(
             &input,
-            subscriptions_compositional_approx.unwrap(),
+            approx,
             Version::CompositionalOverapprox,
             &output_dir,
         );
-
         println!("{}", SPECIAL_SYMBOL);
     }
 }
\ No newline at end of file
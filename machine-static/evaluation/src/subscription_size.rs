@@ -2,6 +2,7 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use clap::Parser;
 use evaluation::{Cli, SPECIAL_SYMBOL, Version, create_directory, prepare_files_in_directory, wrap_and_write_sub_out};
+use machine_core::parallel;
 use machine_core::types::typescript_types::{DataResult, EventType, Granularity, Role, SubscriptionsWrapped};
 
 fn main() {
@@ -15,9 +16,10 @@ fn main() {
     let subs = BTreeMap::<Role, BTreeSet<EventType>>::new();
     let two_step_granularity = Granularity::TwoStep;
 
-    for (_, bi) in interfacing_swarms_general.iter() {
+    // benchmarks are processed independently, outputs are written in order afterwards
+    let results = parallel::map(interfacing_swarms_general.iter().collect(), |(_, bi)| {
         let swarms = &bi.interfacing_swarms;
-        let subscriptions = match machine_core::overapproximated_well_formed_sub(
+        let overapproximated = match machine_core::overapproximated_well_formed_sub(
             swarms.clone(),
             SubscriptionsWrapped(subs.clone()),
             two_step_granularity.clone(),
@@ -27,25 +29,29 @@ fn main() {
             } => Some(subscriptions),
             DataResult::ERROR { .. } => None,
         };
-        wrap_and_write_sub_out(
-            &bi,
-            subscriptions.unwrap(),
-            Version::CompositionalOverapprox,
-            &output_dir,
-        );
-
-        let subscriptions = match machine_core::exact_well_formed_sub(
+        let exact = match machine_core::exact_well_formed_sub(
             swarms.clone(),
             SubscriptionsWrapped(subs.clone()),
+            None,
         ) {
             DataResult::OK {
                 data: subscriptions,
             } => Some(subscriptions),
             DataResult::ERROR { .. } => None,
         };
+        (bi, overapproximated, exact)
+    });
+
+    for (bi, overapproximated, exact) in results {
+        wrap_and_write_sub_out(
+            &bi,
+            overapproximated.unwrap(),
+            Version::CompositionalOverapprox,
+            &output_dir,
+        );
         wrap_and_write_sub_out(
             &bi,
-            subscriptions.unwrap(),
+            exact.unwrap(),
             Version::CompositionalExact,
             &output_dir,
         );
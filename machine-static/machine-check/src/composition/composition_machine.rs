@@ -2,12 +2,16 @@ use crate::machine::{Error, Side};
 use machine_core::types::{
     projection::OptionGraph,
     proto_graph::NodeId,
-    typescript_types::{Command, EventType, MachineLabel},
+    typescript_types::{
+        Command, DataResult, EventType, InterfacingProtocols, MachineLabel, MachineType, Role,
+        SubscriptionsWrapped,
+    },
 };
 use petgraph::{visit::EdgeRef, Direction::Outgoing};
 use std::{
     cmp::Ordering,
     collections::{BTreeMap, BTreeSet},
+    iter::once,
 };
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
@@ -124,6 +128,45 @@ pub fn equivalent(left: &OptionGraph, li: NodeId, right: &OptionGraph, ri: NodeI
     errors
 }
 
+/// check an adapted machine against the projection of the expanded composition,
+/// returning a description of each discrepancy found
+pub fn verify_adaptation(
+    protos: InterfacingProtocols,
+    subs: SubscriptionsWrapped,
+    role: Role,
+    adapted: MachineType,
+) -> Vec<String> {
+    let _span = tracing::info_span!("verify_adaptation", %role).entered();
+
+    let reference = match machine_core::project(protos, subs, role.clone(), true, true, None) {
+        DataResult::OK { data } => data,
+        DataResult::ERROR { errors } => return errors,
+    };
+    let (reference, reference_initial, _) = crate::machine::from_json(reference);
+    let (adapted, adapted_initial, _) = crate::machine::from_json(adapted);
+    let (Some(reference_initial), Some(adapted_initial)) = (reference_initial, adapted_initial)
+    else {
+        return vec![format!(
+            "adapted machine for role {role} or the projection of the composition has no transitions"
+        )];
+    };
+
+    let errors = equivalent(&reference, reference_initial, &adapted, adapted_initial);
+    if errors.is_empty() {
+        return vec![];
+    }
+
+    once(format!(
+        "adapted machine for role {role} is not equivalent to the projection of the composition"
+    ))
+    .chain(
+        errors
+            .into_iter()
+            .map(crate::machine::Error::convert(&reference, &adapted)),
+    )
+    .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -539,6 +582,7 @@ mod tests {
             role,
             false,
             false,
+            None,
         ) {
             DataResult::ERROR { errors: _ } => panic!(),
             DataResult::OK { data } => crate::machine::from_json(data),
@@ -564,6 +608,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("build"),
                         log_type: vec![EventType::new("car")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("3"),
                     target: State::new("3"),
@@ -576,6 +622,7 @@ mod tests {
                     target: State::new("4"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let (expected, expected_initial, errors) = crate::machine::from_json(expected_m);
         assert!(errors.is_empty());
@@ -597,6 +644,7 @@ mod tests {
         let subs = match machine_core::exact_well_formed_sub(
             InterfacingProtocols(vec![proto.clone()]),
             SubscriptionsWrapped(BTreeMap::new()),
+            None,
         ) {
             DataResult::ERROR { errors: _ } => panic!(),
             DataResult::OK { data } => data,
@@ -608,6 +656,7 @@ mod tests {
             role,
             false,
             false,
+            None,
         ) {
             DataResult::ERROR { errors: _ } => panic!(),
             DataResult::OK { data } => crate::machine::from_json(data),
@@ -626,6 +675,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("1"),
                     target: State::new("1"),
@@ -659,6 +710,7 @@ mod tests {
                     target: State::new("3"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let (right, right_initial, errors) = crate::machine::from_json(right_m);
         let right = from_option_machine(&right);
@@ -677,6 +729,7 @@ mod tests {
         let subs = match machine_core::exact_well_formed_sub(
             InterfacingProtocols(vec![proto.clone()]),
             SubscriptionsWrapped(BTreeMap::new()),
+            None,
         ) {
             DataResult::ERROR { errors: _ } => panic!(),
             DataResult::OK { data } => data,
@@ -688,6 +741,7 @@ mod tests {
             role,
             false,
             false,
+            None,
         ) {
             DataResult::ERROR { errors: _ } => panic!(),
             DataResult::OK { data } => crate::machine::from_json(data),
@@ -706,6 +760,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("build"),
                         log_type: vec![EventType::new("car")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("2"),
                     target: State::new("2"),
@@ -718,6 +774,7 @@ mod tests {
                     target: State::new("3"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let (expected, expected_initial, errors) = crate::machine::from_json(expected_m);
 
@@ -750,11 +807,17 @@ mod tests {
         };
 
         let role = Role::new("T");
-        let (proj, proj_initial, _) =
-            match machine_core::project(protos, SubscriptionsWrapped(subs), role, false, false) {
-                DataResult::ERROR { errors: _ } => panic!(),
-                DataResult::OK { data } => crate::machine::from_json(data),
-            };
+        let (proj, proj_initial, _) = match machine_core::project(
+            protos,
+            SubscriptionsWrapped(subs),
+            role,
+            false,
+            false,
+            None,
+        ) {
+            DataResult::ERROR { errors: _ } => panic!(),
+            DataResult::OK { data } => crate::machine::from_json(data),
+        };
         let expected_m = MachineType {
             initial: State::new("0"),
             transitions: vec![
@@ -762,6 +825,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("request"),
                         log_type: vec![EventType::new("partID")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("0"),
                     target: State::new("0"),
@@ -791,6 +856,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("deliver"),
                         log_type: vec![EventType::new("part")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("3"),
                     target: State::new("3"),
@@ -831,6 +898,7 @@ mod tests {
                     target: State::new("7"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let (expected, expected_initial, errors) = crate::machine::from_json(expected_m);
 
@@ -854,6 +922,7 @@ mod tests {
         let subs = match machine_core::exact_well_formed_sub(
             InterfacingProtocols(vec![proto.clone()]),
             SubscriptionsWrapped(BTreeMap::new()),
+            None,
         ) {
             DataResult::ERROR { errors: _ } => panic!(),
             DataResult::OK { data } => data,
@@ -865,6 +934,7 @@ mod tests {
             role,
             false,
             false,
+            None,
         ) {
             DataResult::ERROR { errors: _ } => panic!(),
             DataResult::OK { data } => crate::machine::from_json(data),
@@ -883,6 +953,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("1"),
                     target: State::new("1"),
@@ -916,6 +988,7 @@ mod tests {
                     target: State::new("3"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let (right, right_initial, errors) = crate::machine::from_json(right_m);
         let right = from_option_machine(&right);
@@ -935,6 +1008,7 @@ mod tests {
         let subs = match machine_core::exact_well_formed_sub(
             InterfacingProtocols(vec![proto.clone()]),
             SubscriptionsWrapped(BTreeMap::new()),
+            None,
         ) {
             DataResult::ERROR { errors: _ } => panic!(),
             DataResult::OK { data } => data,
@@ -946,6 +1020,7 @@ mod tests {
             role,
             false,
             false,
+            None,
         ) {
             DataResult::ERROR { errors: _ } => panic!(),
             DataResult::OK { data } => crate::machine::from_json(data),
@@ -964,6 +1039,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("1"),
                     target: State::new("1"),
@@ -997,6 +1074,7 @@ mod tests {
                     target: State::new("3"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let (right, right_initial, errors) = crate::machine::from_json(right_m);
         let right = from_option_machine(&right);
@@ -1016,6 +1094,7 @@ mod tests {
         let subs = match machine_core::exact_well_formed_sub(
             InterfacingProtocols(vec![proto.clone()]),
             SubscriptionsWrapped(BTreeMap::new()),
+            None,
         ) {
             DataResult::ERROR { errors: _ } => panic!(),
             DataResult::OK { data } => data,
@@ -1027,6 +1106,7 @@ mod tests {
             role,
             false,
             false,
+            None,
         ) {
             DataResult::ERROR { errors: _ } => panic!(),
             DataResult::OK { data } => crate::machine::from_json(data),
@@ -1045,6 +1125,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("1"),
                     target: State::new("1"),
@@ -1060,6 +1142,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("2"),
                     target: State::new("2"),
@@ -1079,6 +1163,7 @@ mod tests {
                     target: State::new("3"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let (right, right_initial, errors) = crate::machine::from_json(right_m);
         let right = from_option_machine(&right);
@@ -1098,6 +1183,7 @@ mod tests {
         let subs = match machine_core::exact_well_formed_sub(
             InterfacingProtocols(vec![proto.clone()]),
             SubscriptionsWrapped(BTreeMap::new()),
+            None,
         ) {
             DataResult::ERROR { errors: _ } => panic!(),
             DataResult::OK { data } => data,
@@ -1109,6 +1195,7 @@ mod tests {
             role,
             false,
             false,
+            None,
         ) {
             DataResult::ERROR { errors: _ } => panic!(),
             DataResult::OK { data } => crate::machine::from_json(data),
@@ -1127,6 +1214,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("1"),
                     target: State::new("1"),
@@ -1153,6 +1242,7 @@ mod tests {
                     target: State::new("3"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let (right, right_initial, errors) = crate::machine::from_json(right_m);
         let right = from_option_machine(&right);
@@ -1187,6 +1277,7 @@ mod tests {
             role.clone(),
             false,
             false,
+            None,
         ) {
             DataResult::OK { data } => crate::machine::from_json(data),
             DataResult::ERROR { errors } => {
@@ -1213,6 +1304,7 @@ mod tests {
             role.clone(),
             true,
             false,
+            None,
         ) {
             DataResult::OK { data } => crate::machine::from_json(data),
             DataResult::ERROR { errors } => {
@@ -1227,6 +1319,7 @@ mod tests {
             role.clone(),
             true,
             true,
+            None,
         ) {
             DataResult::OK { data } => crate::machine::from_json(data),
             DataResult::ERROR { errors } => {
@@ -1297,6 +1390,7 @@ mod tests {
                 role.clone(),
                 false,
                 false,
+                None,
             ) {
                 DataResult::OK { data } => crate::machine::from_json(data),
                 DataResult::ERROR { errors } => {
@@ -1310,6 +1404,7 @@ mod tests {
                 role.clone(),
                 true,
                 false,
+                None,
             ) {
                 DataResult::OK { data } => crate::machine::from_json(data),
                 DataResult::ERROR { errors } => {
@@ -1332,6 +1427,7 @@ mod tests {
                 role.clone(),
                 true,
                 true,
+                None,
             ) {
                 DataResult::OK { data } => crate::machine::from_json(data),
                 DataResult::ERROR { errors } => {
@@ -1378,6 +1474,7 @@ mod tests {
                 role.clone(),
                 true,
                 true,
+                None,
             ) {
                 DataResult::OK { data } => crate::machine::from_json(data),
                 DataResult::ERROR { errors } => {
@@ -1391,6 +1488,7 @@ mod tests {
                 role.clone(),
                 false,
                 false,
+                None,
             ) {
                 DataResult::OK { data } => crate::machine::from_json(data),
                 DataResult::ERROR { errors } => {
@@ -1418,4 +1516,117 @@ mod tests {
             .is_empty());
         }
     }
+
+    #[test]
+    fn test_verify_adaptation_1() {
+        setup_logger();
+        let subs = match machine_core::overapproximated_well_formed_sub(
+            get_interfacing_swarms_1(),
+            SubscriptionsWrapped(BTreeMap::new()),
+            Granularity::TwoStep,
+        ) {
+            DataResult::OK { data } => data,
+            DataResult::ERROR { errors } => {
+                println!("{}", errors.join(", "));
+                panic!()
+            }
+        };
+        for role in [
+            Role::new("T"),
+            Role::new("FL"),
+            Role::new("D"),
+            Role::new("F"),
+        ] {
+            let machine = match machine_core::project(
+                InterfacingProtocols(vec![get_proto1()]),
+                SubscriptionsWrapped(subs.clone()),
+                role.clone(),
+                true,
+                false,
+                None,
+            ) {
+                DataResult::OK { data } => data,
+                DataResult::ERROR { errors } => {
+                    println!("{}", errors.join(", "));
+                    panic!()
+                }
+            };
+            let result = crate::projection_information(
+                role.clone(),
+                get_interfacing_swarms_1(),
+                0,
+                SubscriptionsWrapped(subs.clone()),
+                machine,
+                true,
+                true,
+            );
+            match result {
+                DataResult::OK { .. } => (),
+                DataResult::ERROR { errors } => panic!("{role}: {}", errors.join(", ")),
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_adaptation_fail_1() {
+        setup_logger();
+        let subs = match machine_core::overapproximated_well_formed_sub(
+            get_interfacing_swarms_1(),
+            SubscriptionsWrapped(BTreeMap::new()),
+            Granularity::TwoStep,
+        ) {
+            DataResult::OK { data } => data,
+            DataResult::ERROR { errors } => {
+                println!("{}", errors.join(", "));
+                panic!()
+            }
+        };
+        // transport machine for proto1 that never delivers
+        let machine = serde_json::from_str::<MachineType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "label": { "tag": "Execute", "cmd": "request", "logType": ["partID"] }, "source": "0", "target": "0" },
+                    { "label": { "tag": "Input", "eventType": "partID" }, "source": "0", "target": "1" },
+                    { "label": { "tag": "Input", "eventType": "pos" }, "source": "1", "target": "2" },
+                    { "label": { "tag": "Input", "eventType": "part" }, "source": "2", "target": "0" },
+                    { "label": { "tag": "Input", "eventType": "time" }, "source": "0", "target": "3" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let unverified = crate::projection_information(
+            Role::new("T"),
+            get_interfacing_swarms_1(),
+            0,
+            SubscriptionsWrapped(subs.clone()),
+            machine.clone(),
+            true,
+            false,
+        );
+        assert!(matches!(unverified, DataResult::OK { .. }));
+
+        let verified = crate::projection_information(
+            Role::new("T"),
+            get_interfacing_swarms_1(),
+            0,
+            SubscriptionsWrapped(subs),
+            machine,
+            true,
+            true,
+        );
+        match verified {
+            DataResult::OK { .. } => panic!("expected verification to fail"),
+            DataResult::ERROR { errors } => {
+                assert_eq!(
+                    errors[0],
+                    "adapted machine for role T is not equivalent to the projection of the composition"
+                );
+                assert!(errors[1..]
+                    .iter()
+                    .any(|e| e.starts_with("missing transition deliver/part")));
+            }
+        }
+    }
 }
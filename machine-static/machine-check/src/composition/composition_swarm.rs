@@ -1,7 +1,9 @@
 use machine_core::errors::{Error, ErrorReport};
+use machine_core::parallel;
 use machine_core::types::unordered_event_pair::UnordEventPair;
 use machine_core::types::{proto_graph, proto_info};
 use machine_core::types::{
+    proto_graph::NodeId,
     proto_info::{ProtoInfo, ProtoStruct},
     typescript_types::{EventLabel, EventType, InterfacingProtocols, Role, Subscriptions},
 };
@@ -32,8 +34,8 @@ macro_rules! get_ith_or_error {
     };
 }
 
-// Well-formedness check
-pub fn check(protos: InterfacingProtocols, subs: &Subscriptions) -> ErrorReport {
+// Well-formedness check. Fails if the composition has more than max_states states.
+pub fn check(protos: InterfacingProtocols, subs: &Subscriptions, max_states: usize) -> ErrorReport {
     let _span = tracing::info_span!("check").entered();
     let combined_proto_info = proto_info::swarms_to_proto_info(protos);
     if !combined_proto_info.no_errors() {
@@ -44,7 +46,13 @@ pub fn check(protos: InterfacingProtocols, subs: &Subscriptions) -> ErrorReport
     // We construct a ProtoInfo with the composition as the only protocol and all the
     // information about branches etc. from combined_proto_info
     // and the succeeding_events field updated using the expanded composition.
-    let composition = proto_info::explicit_composition_proto_info(combined_proto_info);
+    let composition = match proto_info::explicit_composition_proto_info_bounded(
+        combined_proto_info,
+        max_states,
+    ) {
+        Ok(composition) => composition,
+        Err(error_report) => return error_report,
+    };
     let composition_checked = well_formed_proto_info(composition, subs);
     composition_checked.to_error_report()
 }
@@ -53,16 +61,18 @@ pub fn check(protos: InterfacingProtocols, subs: &Subscriptions) -> ErrorReport
 // Does not check confusion-freeness.
 fn well_formed_proto_info(proto_info: ProtoInfo, subs: &Subscriptions) -> ProtoInfo {
     let _span = tracing::info_span!("well_formed_proto_info").entered();
-    let protocols: Vec<_> = proto_info
-        .protocols
-        .clone()
-        .into_iter()
-        .enumerate()
-        .map(|(i, p)| {
+    let protocols = parallel::map(
+        proto_info
+            .protocols
+            .clone()
+            .into_iter()
+            .enumerate()
+            .collect(),
+        |(i, p)| {
             let errors = vec![p.errors, well_formed(&proto_info, i, subs)].concat();
             ProtoStruct { errors, ..p }
-        })
-        .collect();
+        },
+    );
 
     ProtoInfo {
         protocols,
@@ -80,13 +90,15 @@ fn well_formed_proto_info(proto_info: ProtoInfo, subs: &Subscriptions) -> ProtoI
  */
 fn well_formed(proto_info: &ProtoInfo, proto_pointer: usize, subs: &Subscriptions) -> Vec<Error> {
     let _span = tracing::info_span!("well_formed").entered();
-    let mut errors = Vec::new();
     let empty = BTreeSet::new();
     let sub = |r: &Role| subs.get(r).unwrap_or(&empty);
     let (graph, initial, _) = get_ith_or_error!(proto_info, proto_pointer);
 
     // Visit all transitions in protocol and perform causal consistency and determinacy checks.
-    for node in Dfs::new(&graph, initial).iter(&graph) {
+    // The nodes are checked independently, errors are reported in the order of the traversal.
+    let nodes: Vec<NodeId> = Dfs::new(&graph, initial).iter(&graph).collect();
+    let node_errors = parallel::map(nodes, |node| {
+        let mut errors = Vec::new();
         for edge in graph.edges_directed(node, Outgoing) {
             let event_type = edge.weight().get_event_type();
 
@@ -96,6 +108,30 @@ fn well_formed(proto_info: &ProtoInfo, proto_pointer: usize, subs: &Subscription
                 errors.push(Error::ActiveRoleNotSubscribed(edge.id()));
             }
 
+            // Data guards
+            // Check if role subscribes to the event types read by the guard of the transition.
+            if let Some(guard) = &edge.weight().guard {
+                for guard_event_type in guard.event_types() {
+                    if !sub(&edge.weight().role).contains(&guard_event_type) {
+                        errors.push(Error::DataGuardNotSubscribed(edge.id(), guard_event_type));
+                    }
+                }
+            }
+
+            // Timeouts
+            // Check if role subscribes to the event types of the transitions competing with its timeout.
+            if edge.weight().timeout.is_some() {
+                for competing in proto_graph::competing_event_types(
+                    &graph,
+                    edge.id(),
+                    &proto_info.concurrent_events,
+                ) {
+                    if !sub(&edge.weight().role).contains(&competing) {
+                        errors.push(Error::TimeoutNotSubscribed(edge.id(), competing));
+                    }
+                }
+            }
+
             // Causal consistency
             // Check if roles with an enabled command in direct successor subscribe to event_type.
             // Active transitions_not_conc gets the transitions going out of edge.target()
@@ -224,10 +260,11 @@ fn well_formed(proto_info: &ProtoInfo, proto_pointer: usize, subs: &Subscription
                 }
             }
         }
-    }
+        errors
+    });
 
     // We do not check looping errors since we only accept terminating protocols.
-    errors
+    node_errors.concat()
 }
 
 // True if there exists an event type in event_types such that all roles in involved_roles subscribe to it.
@@ -468,10 +505,46 @@ mod tests {
         .unwrap()
     }
 
+    // The seller selects a bid only if its price is below the limit set by the auctioneer.
+    fn get_guarded_proto() -> SwarmProtocolType {
+        serde_json::from_str::<SwarmProtocolType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "source": "0", "target": "1", "label": { "cmd": "setLimit", "logType": ["limit"], "role": "A" } },
+                    { "source": "1", "target": "2", "label": { "cmd": "bid", "logType": ["bid"], "role": "B" } },
+                    { "source": "2", "target": "3", "label": { "cmd": "select", "logType": ["selected"], "role": "S",
+                        "guard": { "predicate": "bid.price < limit.value", "fields": [
+                            { "eventType": "bid", "field": "price" },
+                            { "eventType": "limit", "field": "value" }
+                        ] } } },
+                    { "source": "2", "target": "3", "label": { "cmd": "pass", "logType": ["passed"], "role": "S" } }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    // The warehouse W may cancel if the transport T does not deliver within five minutes.
+    fn get_timed_proto() -> SwarmProtocolType {
+        serde_json::from_str::<SwarmProtocolType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "source": "0", "target": "1", "label": { "cmd": "request", "logType": ["partID"], "role": "W" } },
+                    { "source": "1", "target": "2", "label": { "cmd": "deliver", "logType": ["part"], "role": "T" } },
+                    { "source": "1", "target": "3", "label": { "cmd": "cancel", "logType": ["cancelled"], "role": "W", "timeout": 300000 } }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
     mod well_formedness_check_tests {
         use std::collections::BTreeMap;
 
         use super::*;
+        use machine_core::types::proto_info::DEFAULT_MAX_STATES;
         use machine_core::types::typescript_types::{DataResult, SubscriptionsWrapped};
         // Tests relating to well-formedness checking.
         #[test]
@@ -481,11 +554,12 @@ mod tests {
             let subs1 = match machine_core::exact_well_formed_sub(
                 proto1.clone(),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            let error_report = check(proto1, &subs1);
+            let error_report = check(proto1, &subs1, DEFAULT_MAX_STATES);
             assert!(error_report.is_empty());
             assert_eq!(get_subs1(), subs1);
 
@@ -493,11 +567,12 @@ mod tests {
             let subs2 = match machine_core::exact_well_formed_sub(
                 proto2.clone(),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            let error_report = check(proto2, &subs2);
+            let error_report = check(proto2, &subs2, DEFAULT_MAX_STATES);
             assert!(error_report.is_empty());
             assert_eq!(get_subs2(), subs2);
 
@@ -505,11 +580,12 @@ mod tests {
             let subs3 = match machine_core::exact_well_formed_sub(
                 proto3.clone(),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            let error_report = check(proto3, &subs3);
+            let error_report = check(proto3, &subs3, DEFAULT_MAX_STATES);
             assert!(error_report.is_empty());
             assert_eq!(get_subs3(), subs3);
 
@@ -517,11 +593,12 @@ mod tests {
             let subs_composition = match machine_core::exact_well_formed_sub(
                 composition1.clone(),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            let error_report = check(composition1, &subs_composition);
+            let error_report = check(composition1, &subs_composition, DEFAULT_MAX_STATES);
             assert!(error_report.is_empty());
             assert_eq!(get_subs_composition_1(), subs_composition);
 
@@ -529,11 +606,12 @@ mod tests {
             let subs_composition = match machine_core::exact_well_formed_sub(
                 composition2.clone(),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            let error_report = check(composition2, &subs_composition);
+            let error_report = check(composition2, &subs_composition, DEFAULT_MAX_STATES);
             assert!(error_report.is_empty());
             assert_eq!(get_subs_composition_2(), subs_composition);
         }
@@ -547,7 +625,7 @@ mod tests {
                 (Role::new("D"), BTreeSet::from([EventType::new("pos")])),
                 (Role::new("FL"), BTreeSet::from([EventType::new("partID")])),
             ]);
-            let error_report = check(input, &subs);
+            let error_report = check(input, &subs, DEFAULT_MAX_STATES);
             let mut errors = error_report.to_strings();
             errors.sort();
             let mut expected_errors = vec![
@@ -566,7 +644,7 @@ mod tests {
             assert_eq!(errors, expected_errors);
 
             let input: InterfacingProtocols = InterfacingProtocols(vec![get_proto2()]);
-            let error_report = check(input, &get_subs3());
+            let error_report = check(input, &get_subs3(), DEFAULT_MAX_STATES);
             let mut errors = error_report.to_strings();
             errors.sort();
             let mut expected_errors = vec![
@@ -581,7 +659,7 @@ mod tests {
 
             let input: InterfacingProtocols = InterfacingProtocols(vec![get_proto3()]);
 
-            let error_report = check(input, &get_subs1());
+            let error_report = check(input, &get_subs1(), DEFAULT_MAX_STATES);
             let mut errors = error_report.to_strings();
             errors.sort();
             let mut expected_errors = vec![
@@ -600,6 +678,52 @@ mod tests {
             assert_eq!(errors, expected_errors);
         }
 
+        // Check that the protocol is not well-formed under the subscription,
+        // but becomes well-formed once the role also subscribes to the event type.
+        fn assert_fixed_by_subscribing(
+            proto: SwarmProtocolType,
+            subs: &str,
+            expected_errors: Vec<&str>,
+            (role, event_type): (&str, &str),
+        ) {
+            let input = InterfacingProtocols(vec![proto]);
+            let mut subs: Subscriptions = serde_json::from_str(subs).unwrap();
+            assert_eq!(
+                check(input.clone(), &subs, DEFAULT_MAX_STATES).to_strings(),
+                expected_errors
+            );
+
+            subs.get_mut(&Role::new(role))
+                .unwrap()
+                .insert(EventType::new(event_type));
+            assert!(check(input, &subs, DEFAULT_MAX_STATES).is_empty());
+        }
+
+        #[test]
+        fn test_wf_data_guard() {
+            setup_logger();
+            assert_fixed_by_subscribing(
+                get_guarded_proto(),
+                r#"{ "A": ["limit"], "B": ["limit", "bid"], "S": ["bid", "selected", "passed"] }"#,
+                vec!["active role does not subscribe to event type limit read by the guard in transition (2)--[select@S<selected>[bid.price < limit.value]]-->(3)"],
+                ("S", "limit"),
+            );
+        }
+
+        #[test]
+        fn test_wf_timeout() {
+            setup_logger();
+            assert_fixed_by_subscribing(
+                get_timed_proto(),
+                r#"{ "W": ["partID", "cancelled"], "T": ["partID", "part", "cancelled"] }"#,
+                vec![
+                    "active role does not subscribe to event type part competing with the timeout in transition (1)--[cancel@W<cancelled> after 300000ms]-->(3)",
+                    "role W does not subscribe to event types part in branching transitions at state 1, but is involved after transition (1)--[cancel@W<cancelled> after 300000ms]-->(3)"
+                ],
+                ("W", "part"),
+            );
+        }
+
         #[test]
         fn test_compose_non_wf_swarms() {
             setup_logger();
@@ -610,7 +734,7 @@ mod tests {
                 (Role::new("FL"), BTreeSet::from([EventType::new("part")])),
                 (Role::new("F"), BTreeSet::from([EventType::new("part")])),
             ]);
-            let error_report = check(input, &subs);
+            let error_report = check(input, &subs, DEFAULT_MAX_STATES);
             let mut errors = error_report.to_strings();
             errors.sort();
             let mut expected_errors = vec![
@@ -638,14 +762,15 @@ mod tests {
             let subs1 = match machine_core::exact_well_formed_sub(
                 get_fail_1_swarms(),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            let error_report = check(get_fail_1_swarms(), &subs1);
+            let error_report = check(get_fail_1_swarms(), &subs1, DEFAULT_MAX_STATES);
             assert!(error_report.is_empty());
 
-            let error_report = check(get_fail_1_swarms(), &BTreeMap::new());
+            let error_report = check(get_fail_1_swarms(), &BTreeMap::new(), DEFAULT_MAX_STATES);
             let mut errors = error_report.to_strings();
             errors.sort();
             let mut expected_errors = vec![
@@ -674,6 +799,7 @@ mod tests {
             let mut subs_composition = match machine_core::exact_well_formed_sub(
                 composition.clone(),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
@@ -690,7 +816,7 @@ mod tests {
             subs_composition.entry(Role::new("F")).and_modify(|s| {
                 s.remove(&EventType::new("report1"));
             });
-            let error_report = check(composition.clone(), &subs_composition);
+            let error_report = check(composition.clone(), &subs_composition, DEFAULT_MAX_STATES);
             let mut errors = error_report.to_strings();
             let mut expected_errors = vec![
                 "role F does not subscribe to event types report1 leading to or in joining event in transition (0 || 2 || 1)--[build@F<car>]-->(0 || 3 || 2)",
@@ -744,10 +870,11 @@ mod tests {
                 InterfacingProtocols(vec![proto1(), proto2()])
             }
 
-            assert!(check(as_interfacing_protocols(), &subs()).is_empty());
+            assert!(check(as_interfacing_protocols(), &subs(), DEFAULT_MAX_STATES).is_empty());
             let smallest_sub = match machine_core::exact_well_formed_sub(
                 as_interfacing_protocols(),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
@@ -799,10 +926,11 @@ mod tests {
             fn as_interfacing_protocols() -> InterfacingProtocols {
                 InterfacingProtocols(vec![proto1(), proto2()])
             }
-            assert!(check(as_interfacing_protocols(), &subs()).is_empty());
+            assert!(check(as_interfacing_protocols(), &subs(), DEFAULT_MAX_STATES).is_empty());
             let smallest_sub = match machine_core::exact_well_formed_sub(
                 as_interfacing_protocols(),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
@@ -855,10 +983,11 @@ mod tests {
                 InterfacingProtocols(vec![proto1(), proto2()])
             }
 
-            assert!(check(as_interfacing_protocols(), &subs()).is_empty());
+            assert!(check(as_interfacing_protocols(), &subs(), DEFAULT_MAX_STATES).is_empty());
             let smallest_sub = match machine_core::exact_well_formed_sub(
                 as_interfacing_protocols(),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
@@ -909,10 +1038,11 @@ mod tests {
                 InterfacingProtocols(vec![proto1(), proto2()])
             }
 
-            assert!(check(as_interfacing_protocols(), &subs()).is_empty());
+            assert!(check(as_interfacing_protocols(), &subs(), DEFAULT_MAX_STATES).is_empty());
             let smallest_sub = match machine_core::exact_well_formed_sub(
                 as_interfacing_protocols(),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
@@ -962,10 +1092,11 @@ mod tests {
                 InterfacingProtocols(vec![proto1(), proto2()])
             }
 
-            assert!(check(as_interfacing_protocols(), &subs()).is_empty());
+            assert!(check(as_interfacing_protocols(), &subs(), DEFAULT_MAX_STATES).is_empty());
             let smallest_sub = match machine_core::exact_well_formed_sub(
                 as_interfacing_protocols(),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
@@ -979,6 +1110,7 @@ mod tests {
         use std::collections::BTreeMap;
 
         use super::*;
+        use machine_core::types::proto_info::DEFAULT_MAX_STATES;
         use machine_core::types::typescript_types::{
             DataResult, Granularity, SubscriptionsWrapped,
         };
@@ -1005,11 +1137,17 @@ mod tests {
             let sub = match machine_core::exact_well_formed_sub(
                 InterfacingProtocols(vec![proto1()]),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
 
             // Check overapprox well-formed subscriptions
             let sub = match machine_core::overapproximated_well_formed_sub(
@@ -1020,7 +1158,12 @@ mod tests {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
 
             let sub = match machine_core::overapproximated_well_formed_sub(
                 InterfacingProtocols(vec![proto1()]),
@@ -1030,7 +1173,12 @@ mod tests {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
         }
 
         #[test]
@@ -1056,11 +1204,17 @@ mod tests {
             let sub = match machine_core::exact_well_formed_sub(
                 InterfacingProtocols(vec![proto1()]),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
 
             // Check overapprox well-formed subscriptions
             let sub = match machine_core::overapproximated_well_formed_sub(
@@ -1071,7 +1225,12 @@ mod tests {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
 
             let sub = match machine_core::overapproximated_well_formed_sub(
                 InterfacingProtocols(vec![proto1()]),
@@ -1081,7 +1240,12 @@ mod tests {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
         }
 
         #[test]
@@ -1111,11 +1275,17 @@ mod tests {
             let sub = match machine_core::exact_well_formed_sub(
                 InterfacingProtocols(vec![proto1()]),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
 
             // Check overapprox well-formed subscriptions
             let sub = match machine_core::overapproximated_well_formed_sub(
@@ -1126,7 +1296,12 @@ mod tests {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
 
             let sub = match machine_core::overapproximated_well_formed_sub(
                 InterfacingProtocols(vec![proto1()]),
@@ -1136,7 +1311,12 @@ mod tests {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
         }
 
         #[test]
@@ -1165,11 +1345,17 @@ mod tests {
             let sub = match machine_core::exact_well_formed_sub(
                 InterfacingProtocols(vec![proto1()]),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
 
             // Check overapprox well-formed subscriptions
             let sub = match machine_core::overapproximated_well_formed_sub(
@@ -1180,7 +1366,12 @@ mod tests {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
 
             let sub = match machine_core::overapproximated_well_formed_sub(
                 InterfacingProtocols(vec![proto1()]),
@@ -1190,7 +1381,12 @@ mod tests {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
         }
 
         #[test]
@@ -1219,11 +1415,17 @@ mod tests {
             let sub = match machine_core::exact_well_formed_sub(
                 InterfacingProtocols(vec![proto1()]),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
 
             // Check overapprox well-formed subscriptions
             let sub = match machine_core::overapproximated_well_formed_sub(
@@ -1234,7 +1436,12 @@ mod tests {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
 
             let sub = match machine_core::overapproximated_well_formed_sub(
                 InterfacingProtocols(vec![proto1()]),
@@ -1244,7 +1451,12 @@ mod tests {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
         }
 
         #[test]
@@ -1271,11 +1483,17 @@ mod tests {
             let sub = match machine_core::exact_well_formed_sub(
                 InterfacingProtocols(vec![proto1()]),
                 SubscriptionsWrapped(BTreeMap::new()),
+                None,
             ) {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
 
             // Check overapprox well-formed subscriptions
             let sub = match machine_core::overapproximated_well_formed_sub(
@@ -1286,7 +1504,12 @@ mod tests {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
 
             let sub = match machine_core::overapproximated_well_formed_sub(
                 InterfacingProtocols(vec![proto1()]),
@@ -1296,7 +1519,12 @@ mod tests {
                 DataResult::ERROR { errors: _ } => panic!(),
                 DataResult::OK { data } => data,
             };
-            assert!(check(InterfacingProtocols(vec![proto1()]), &sub).is_empty());
+            assert!(check(
+                InterfacingProtocols(vec![proto1()]),
+                &sub,
+                DEFAULT_MAX_STATES
+            )
+            .is_empty());
         }
     }
 }
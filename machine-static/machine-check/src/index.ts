@@ -1,5 +1,5 @@
-import { MachineType, Role, Subscriptions, SwarmProtocolType } from 'machine-core';
-import { check_swarm, check_projection, check_composed_swarm, check_composed_projection, InterfacingProtocols, CheckResult } from '../pkg/machine_check.js'
+import { DataResult, MachineType, ProjectionInfo, Role, Subscriptions, SwarmProtocolType } from 'machine-core';
+import { check_swarm, check_projection, check_composed_swarm, check_composed_projection, projection_information, InterfacingProtocols, CheckResult } from '../pkg/machine_check.js'
 export { CheckResult }
 
 /**
@@ -43,10 +43,11 @@ export function checkProjection(
  *
  * @param protos - An array of swarm protocols representing a composition.
  * @param subscriptions - A subscription.
+ * @param maxStates - Optional limit on the number of states of the expanded composition, an error is returned if it is exceeded.
  * @returns - Result indicating successful verification or a list of error messages.
  */
-export function checkComposedSwarmProtocol(protos: InterfacingProtocols, subscriptions: Subscriptions): CheckResult {
-  return check_composed_swarm(protos, subscriptions)
+export function checkComposedSwarmProtocol(protos: InterfacingProtocols, subscriptions: Subscriptions, maxStates?: number): CheckResult {
+  return check_composed_swarm(protos, subscriptions, maxStates)
 }
 
 /**
@@ -65,4 +66,31 @@ export function checkComposedProjection(
   machine: MachineType,
 ): CheckResult {
   return check_composed_projection(protos, subscriptions, role, machine)
-}
\ No newline at end of file
+}
+
+/**
+ * Returns the adapted projection of a composed swarm protocol over a role and the information
+ * used for running a branch-tracking adapted machine (see ```projectionInformation``` in machine-core).
+ * If ```verify``` is true the adapted machine is checked to be equivalent to the projection of the
+ * expanded composition over the role and a list of discrepancies is returned if it is not.
+ *
+ * @param role - The role
+ * @param protos - An array of swarm protocols representing a composition.
+ * @param k - The index of the protocol in ```protos``` for which ```machine``` was implemented.
+ * @param subscriptions - A subscription.
+ * @param machine - The (unadapted) original machine.
+ * @param minimize - The projection is minimized if ```minimize``` is true and returned as is otherwise.
+ * @param verify - Check the adapted machine against the projection of the expanded composition if true.
+ * @returns Result containing the projection information or a list of error messages.
+ */
+export function verifiedProjectionInformation(
+  role: Role,
+  protos: InterfacingProtocols,
+  k: number,
+  subscriptions: Subscriptions,
+  machine: MachineType,
+  minimize: boolean,
+  verify: boolean,
+): DataResult<ProjectionInfo> {
+  return projection_information(role, protos, k, subscriptions, machine, minimize, verify)
+}
@@ -9,7 +9,8 @@ mod swarm;
 use machine_core::types::proto_info;
 use machine_core::types::typescript_types::InterfacingProtocols;
 use machine_core::types::typescript_types::{
-    DataResult, MachineType, Role, Subscriptions, SubscriptionsWrapped, SwarmProtocolType,
+    DataResult, MachineType, ProjectionInfo, Role, Subscriptions, SubscriptionsWrapped,
+    SwarmProtocolType,
 };
 
 #[derive(Tsify, Serialize)]
@@ -58,9 +59,11 @@ pub fn check_projection(
     let Some(initial) = initial else {
         return CheckResult::ERROR { errors: errors };
     };
-    let (proj, proj_initial) = machine::project(&swarm, initial, &subs.0, role);
+    let (proj, proj_initial) = machine::project(&swarm, initial, &subs.0, role.clone());
+    let v_errors = machine_core::validation::validate_machine(&machine, &role, &subs.0);
     let (machine, json_initial, m_errors) = machine::from_json(machine);
-    let machine_problem = !m_errors.is_empty();
+    let machine_problem = !v_errors.is_empty() || !m_errors.is_empty();
+    errors.extend(v_errors.iter().map(|e| e.to_string()));
     errors.extend(m_errors);
     let Some(json_initial) = json_initial else {
         errors.push(format!("initial machine state has no transitions"));
@@ -83,12 +86,18 @@ pub fn check_projection(
     }
 }
 
+// Fails if the composition has more than max_states states, DEFAULT_MAX_STATES if not given.
 #[wasm_bindgen]
 pub fn check_composed_swarm(
     protos: InterfacingProtocols,
     subs: SubscriptionsWrapped,
+    max_states: Option<usize>,
 ) -> CheckResult {
-    let error_report = composition::composition_swarm::check(protos, &subs.0);
+    let error_report = composition::composition_swarm::check(
+        protos,
+        &subs.0,
+        max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES),
+    );
     if error_report.is_empty() {
         CheckResult::OK
     } else {
@@ -111,14 +120,19 @@ pub fn check_composed_projection(
             errors: proto_info.to_error_report().to_strings(),
         };
     }
-    let proj_machine = match machine_core::project(protos, subs, role.clone(), false, false) {
+    let proj_machine = match machine_core::project(protos, subs.clone(), role.clone(), false, false, None)
+    {
         DataResult::OK { data } => data,
         DataResult::ERROR { errors } => return CheckResult::ERROR { errors },
     };
     let (proj, proj_initial, _) = machine::from_json(proj_machine);
+    let mut errors: Vec<String> =
+        machine_core::validation::validate_machine(&machine, &role, &subs.0)
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
     let (machine, json_initial, m_errors) = machine::from_json(machine);
-    let machine_problem = !m_errors.is_empty();
-    let mut errors = vec![];
+    let machine_problem = !errors.is_empty() || !m_errors.is_empty();
     errors.extend(m_errors);
     let Some(json_initial) = json_initial else {
         errors.push(format!("initial machine state has no transitions"));
@@ -129,9 +143,14 @@ pub fn check_composed_projection(
     }
 
     errors.extend(
-        composition::composition_machine::equivalent(&proj, proj_initial.unwrap(), &machine, json_initial)
-            .into_iter()
-            .map(machine::Error::convert(&proj, &machine)),
+        composition::composition_machine::equivalent(
+            &proj,
+            proj_initial.unwrap(),
+            &machine,
+            json_initial,
+        )
+        .into_iter()
+        .map(machine::Error::convert(&proj, &machine)),
     );
 
     if errors.is_empty() {
@@ -141,6 +160,49 @@ pub fn check_composed_projection(
     }
 }
 
+#[wasm_bindgen]
+pub fn projection_information(
+    role: Role,
+    protos: InterfacingProtocols,
+    k: usize,
+    subs: SubscriptionsWrapped,
+    machine: MachineType,
+    minimize: bool,
+    verify: bool,
+) -> DataResult<ProjectionInfo> {
+    let projection_info = match machine_core::projection_information(
+        role.clone(),
+        protos.clone(),
+        k,
+        subs.clone(),
+        machine,
+        minimize,
+    ) {
+        DataResult::OK { data } => data,
+        DataResult::ERROR { errors } => return DataResult::ERROR { errors },
+    };
+    if !verify {
+        return DataResult::OK {
+            data: projection_info,
+        };
+    }
+
+    // the adapted machine should behave like the projection of the expanded composition
+    let errors = composition::composition_machine::verify_adaptation(
+        protos,
+        subs,
+        role,
+        projection_info.projection.clone(),
+    );
+    if errors.is_empty() {
+        DataResult::OK {
+            data: projection_info,
+        }
+    } else {
+        DataResult::ERROR { errors }
+    }
+}
+
 trait MapVec<T> {
     fn map<U>(self, f: impl Fn(T) -> U) -> Vec<U>;
 }
@@ -56,6 +56,8 @@ pub fn project(
                     MachineLabel::Execute {
                         cmd: l.cmd.clone(),
                         log_type: l.log_type.clone(),
+                        guard: l.guard.clone(),
+                        timeout: l.timeout,
                     },
                 );
             }
@@ -387,4 +389,47 @@ mod tests {
             ["guard event type Bid appears in transitions from multiple states"]
         );
     }
+
+    #[test]
+    fn invalid_machine() {
+        setup_logger();
+        let swarm = r#"{
+            "initial":"S0",
+            "transitions":[
+                {"source":"S0","target":"S1","label":{"role":"P","cmd":"Request","logType":["Requested"]}},
+                {"source":"S1","target":"S2","label":{"role":"T","cmd":"Offer","logType":["Bid"]}}
+            ]}"#;
+        let subs = r#"{
+            "P":["Requested","Bid"],
+            "T":["Requested","Bid"]
+        }"#;
+        let machine = r#"{
+            "initial":"S0",
+            "transitions":[
+                {"source":"S0","target":"S0","label":{"tag":"Execute","cmd":"Request","logType":["Requested"]}},
+                {"source":"S0","target":"S1","label":{"tag":"Input","eventType":"Requested"}},
+                {"source":"S0","target":"S2","label":{"tag":"Input","eventType":"Requested"}},
+                {"source":"S1","target":"S2","label":{"tag":"Input","eventType":"Bid"}},
+                {"source":"S3","target":"S2","label":{"tag":"Input","eventType":"Cancelled"}}
+            ]}"#;
+
+        let result = crate::check_projection(
+            serde_json::from_str::<SwarmProtocolType>(swarm).unwrap(),
+            SubscriptionsWrapped(serde_json::from_str::<Subscriptions>(subs).unwrap()),
+            Role::new("P"),
+            serde_json::from_str::<MachineType>(machine).unwrap(),
+        );
+        let errors = match result {
+            CheckResult::OK => vec![],
+            CheckResult::ERROR { errors } => errors,
+        };
+        assert_eq!(
+            errors,
+            [
+                "non-deterministic reaction to event type Requested in state S0",
+                "machine state S3 is unreachable from initial state",
+                "machine reacts to event type Cancelled but role P does not subscribe to it"
+            ]
+        );
+    }
 }
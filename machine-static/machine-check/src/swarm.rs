@@ -3,7 +3,7 @@ use bitvec::{bitvec, vec::BitVec};
 use machine_core::{
     errors::Error,
     types::{
-        proto_graph::{EdgeId, NodeId},
+        proto_graph::{self, EdgeId, NodeId},
         typescript_types::{
             EventType, Role, State, StateName, Subscriptions, SwarmLabel, SwarmProtocolType,
         },
@@ -123,6 +123,12 @@ fn all_nodes_reachable(graph: &Graph, initial: NodeId) -> Vec<Error> {
         .collect()
 }
 
+// Event types of the other transitions leaving the source of the timeout transition edge.
+// A single protocol has no concurrent event types.
+fn competing_event_types(graph: &Graph, edge: EdgeId) -> BTreeSet<EventType> {
+    proto_graph::competing_event_types(graph, edge, &BTreeSet::new())
+}
+
 fn well_formed(graph: &Graph, initial: NodeId, subs: &Subscriptions) -> Vec<Error> {
     let mut errors = Vec::new();
     let empty = BTreeSet::new(); // just for `sub` but needs its own lifetime
@@ -158,6 +164,22 @@ fn well_formed(graph: &Graph, initial: NodeId, subs: &Subscriptions) -> Vec<Erro
             if log_filter(log, sub(&role)).first_one().is_none() {
                 errors.push(Error::ActiveRoleNotSubscribed(edge.id()));
             }
+            // data guards are evaluated by the active role
+            if let Some(guard) = &edge.weight().guard {
+                for guard_event_type in guard.event_types() {
+                    if !sub(role).contains(&guard_event_type) {
+                        errors.push(Error::DataGuardNotSubscribed(edge.id(), guard_event_type));
+                    }
+                }
+            }
+            // timeouts are taken by the active role if no competing transition happened
+            if edge.weight().timeout.is_some() {
+                for competing in competing_event_types(graph, edge.id()) {
+                    if !sub(role).contains(&competing) {
+                        errors.push(Error::TimeoutNotSubscribed(edge.id(), competing));
+                    }
+                }
+            }
             for active in &graph[target].active {
                 let filtered = log_filter(log, sub(active));
                 if filtered.first_one().is_none() {
@@ -206,6 +228,22 @@ fn wf_sub(
     let mut errors = Vec::new();
     // subscriptions to construct
     let mut subs = subs.clone();
+    // data guards are evaluated by the active role
+    for label in graph.edge_weights() {
+        if let Some(guard) = &label.guard {
+            subs.entry(label.role.clone())
+                .or_default()
+                .extend(guard.event_types());
+        }
+    }
+    // timeouts are taken by the active role if no competing transition happened
+    for edge in graph.edge_indices() {
+        if graph[edge].timeout.is_some() {
+            subs.entry(graph[edge].role.clone())
+                .or_default()
+                .extend(competing_event_types(&graph, edge));
+        }
+    }
 
     // visit all reachable nodes of checking determinism; order doesn’t matter
     for node in Dfs::new(&graph, initial).iter(&graph) {
@@ -421,6 +459,7 @@ fn prepare_graph(
         }
         tracing::debug!("added {:?} --> {:?}", source, target);
     }
+    errors.extend(proto_graph::multiple_timeouts(&graph));
     let initial = if let Some(idx) = nodes.get(&proto.initial) {
         tracing::debug!("initial state {:?}", idx);
         *idx
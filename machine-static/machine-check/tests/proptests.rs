@@ -66,7 +66,7 @@ prop_compose! {
         vec.into_iter()
         .enumerate()
         .map(|(i, (cmd, event))|
-            SwarmLabel { cmd: Command::new(&format!("{role}_{cmd}_{i}")), log_type: vec![EventType::new(&format!("{role}_{event}_{i}"))], role: role.clone()})
+            SwarmLabel { cmd: Command::new(&format!("{role}_{cmd}_{i}")), log_type: vec![EventType::new(&format!("{role}_{event}_{i}"))], role: role.clone(), guard: None, timeout: None})
         .collect()
     }
 }
@@ -229,6 +229,7 @@ pub fn to_swarm_json(graph: crate::Graph, initial: NodeId) -> SwarmProtocolType
     SwarmProtocolType {
         initial: graph[initial].state_name().clone(),
         transitions,
+        payloads: BTreeMap::new(),
     }
 }
 // generate a number of protocols that interface. interfacing events may appear in different orderes in the protocols
@@ -316,11 +317,15 @@ fn refinement_initial_proto() -> (Graph, NodeId) {
         cmd: Command::new(&format!("{IR_BASE}_0_{CMD_BASE}_0")),
         log_type: vec![EventType::new(&format!("{IR_BASE}_0_{E_BASE}_0"))],
         role: Role::new(&format!("{IR_BASE}_0")),
+        guard: None,
+        timeout: None,
     };
     let end_label = SwarmLabel {
         cmd: Command::new(&format!("{IR_BASE}_0_{CMD_BASE}_1")),
         log_type: vec![EventType::new(&format!("{IR_BASE}_0_{E_BASE}_1"))],
         role: Role::new(&format!("{IR_BASE}_0")),
+        guard: None,
+        timeout: None,
     };
 
     graph.add_edge(initial, middle, start_label);
@@ -354,11 +359,15 @@ fn refinement_shape(level: usize, mut proto: Graph, initial: NodeId) -> (Graph,
         cmd: Command::new(&format!("{next_ir}_{CMD_BASE}_0")),
         log_type: vec![EventType::new(&format!("{next_ir}_{E_BASE}_0"))],
         role: Role::new(&next_ir),
+        guard: None,
+        timeout: None,
     };
     let next_if_label_1 = SwarmLabel {
         cmd: Command::new(&format!("{next_ir}_{CMD_BASE}_1")),
         log_type: vec![EventType::new(&format!("{next_ir}_{E_BASE}_1"))],
         role: Role::new(&next_ir),
+        guard: None,
+        timeout: None,
     };
 
     let index = rng.gen_range(0..nodes_on_path.len());
@@ -394,11 +403,15 @@ fn refinement_shape(level: usize, mut proto: Graph, initial: NodeId) -> (Graph,
         cmd: Command::new(&format!("{ir}_{CMD_BASE}_0")),
         log_type: vec![EventType::new(&format!("{ir}_{E_BASE}_0"))],
         role: Role::new(&ir),
+        guard: None,
+        timeout: None,
     };
     let if_label_1 = SwarmLabel {
         cmd: Command::new(&format!("{ir}_{CMD_BASE}_1")),
         log_type: vec![EventType::new(&format!("{ir}_{E_BASE}_1"))],
         role: Role::new(&ir),
+        guard: None,
+        timeout: None,
     };
 
     let new_initial = proto.add_node(State::new(&fresh_i().to_string()));
@@ -568,13 +581,13 @@ proptest! {
     fn test_exact_1(protos in generate_interfacing_protocols(5, 5, 5, false)) {
         setup_logger();
         let subs = BTreeMap::<Role, BTreeSet::<EventType>>::new();
-        let subscription: Option<Subscriptions> = match machine_core::exact_well_formed_sub(protos.clone(), SubscriptionsWrapped(subs)) {
+        let subscription: Option<Subscriptions> = match machine_core::exact_well_formed_sub(protos.clone(), SubscriptionsWrapped(subs), None) {
             DataResult::OK{data: subscriptions} => Some(subscriptions),
             DataResult::ERROR{ .. } => None,
         };
         assert!(subscription.is_some());
         let subscription = subscription.unwrap();
-        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription.clone()));
+        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription.clone()), Some(usize::MAX));
         let ok = match errors {
             CheckResult::OK => true,
             CheckResult::ERROR { .. } => false
@@ -589,13 +602,13 @@ proptest! {
     fn test_exact_2(protos in generate_interfacing_protocols_refinement(5, 5, 5)) {
         setup_logger();
         let subs = BTreeMap::<Role, BTreeSet::<EventType>>::new();
-        let subscription: Option<Subscriptions> = match machine_core::exact_well_formed_sub(protos.clone(), SubscriptionsWrapped(subs)) {
+        let subscription: Option<Subscriptions> = match machine_core::exact_well_formed_sub(protos.clone(), SubscriptionsWrapped(subs), None) {
             DataResult::OK{data: subscriptions} => Some(subscriptions),
             DataResult::ERROR{ .. } => None,
         };
         assert!(subscription.is_some());
         let subscription = subscription.unwrap();
-        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription.clone()));
+        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription.clone()), Some(usize::MAX));
         let ok = match errors {
             CheckResult::OK => true,
             CheckResult::ERROR { .. } => false
@@ -610,13 +623,13 @@ proptest! {
     fn test_exact_3(protos in generate_interfacing_protocols_general_pattern(5, 5, 3)) {
         setup_logger();
         let subs = BTreeMap::<Role, BTreeSet::<EventType>>::new();
-        let subscription: Option<Subscriptions> = match machine_core::exact_well_formed_sub(protos.clone(), SubscriptionsWrapped(subs)) {
+        let subscription: Option<Subscriptions> = match machine_core::exact_well_formed_sub(protos.clone(), SubscriptionsWrapped(subs), None) {
             DataResult::OK{data: subscriptions} => Some(subscriptions),
             DataResult::ERROR{ .. } => None,
         };
         assert!(subscription.is_some());
         let subscription = subscription.unwrap();
-        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription.clone()));
+        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription.clone()), Some(usize::MAX));
         let ok = match errors {
             CheckResult::OK => true,
             CheckResult::ERROR { .. } => false
@@ -637,7 +650,7 @@ proptest! {
         };
         assert!(subscription.is_some());
         let subscription = subscription.unwrap();
-        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription));
+        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription), Some(usize::MAX));
         let ok = match errors {
             CheckResult::OK => true,
             CheckResult::ERROR { .. } => false
@@ -658,7 +671,7 @@ proptest! {
         };
         assert!(subscription.is_some());
         let subscription = subscription.unwrap();
-        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription));
+        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription), Some(usize::MAX));
         let ok = match errors {
             CheckResult::OK => true,
             CheckResult::ERROR { .. } => false
@@ -679,7 +692,7 @@ proptest! {
         };
         assert!(subscription.is_some());
         let subscription = subscription.unwrap();
-        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription));
+        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription), Some(usize::MAX));
         let ok = match errors {
             CheckResult::OK => true,
             CheckResult::ERROR { .. } => false
@@ -700,7 +713,7 @@ proptest! {
         };
         assert!(subscription.is_some());
         let subscription = subscription.unwrap();
-        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription));
+        let errors = check_composed_swarm(protos.clone(), SubscriptionsWrapped(subscription), Some(usize::MAX));
         let ok = match errors {
             CheckResult::OK => true,
             CheckResult::ERROR { .. } => false
@@ -741,7 +754,7 @@ proptest! {
 
         };
         assert!(ok); */
-        let subscription: Option<Subscriptions> = match machine_core::exact_well_formed_sub(protos.clone(), SubscriptionsWrapped(subs.clone())) {
+        let subscription: Option<Subscriptions> = match machine_core::exact_well_formed_sub(protos.clone(), SubscriptionsWrapped(subs.clone()), None) {
             DataResult::OK{data: subscriptions} => Some(subscriptions),
             DataResult::ERROR{ .. } => None,
         };
@@ -815,7 +828,7 @@ proptest! {
             DataResult::OK{data: subscriptions} => Some(subscriptions),
             DataResult::ERROR{ .. } => None,
         };
-        let composition: Option<SwarmProtocolType> = match compose_protocols(protos.clone()) {
+        let composition: Option<SwarmProtocolType> = match compose_protocols(protos.clone(), None) {
             DataResult::OK{data: composition} => Some(composition),
             DataResult::ERROR{ .. } => None,
         };
@@ -825,7 +838,7 @@ proptest! {
         let composition = composition.unwrap();
 
         for role in subscriptions.keys() {
-            let projection: Option<MachineType> = match machine_core::project(protos.clone(), SubscriptionsWrapped(subscriptions.clone()), role.clone(), true, true) {
+            let projection: Option<MachineType> = match machine_core::project(protos.clone(), SubscriptionsWrapped(subscriptions.clone()), role.clone(), true, true, None) {
                 DataResult::OK{data: projection} => {
                 Some(projection) },
                 DataResult::ERROR{ .. } => None,
@@ -836,7 +849,7 @@ proptest! {
             match check_composed_projection(protos.clone(), SubscriptionsWrapped(subscriptions.clone()), role.clone(), projection.clone().unwrap()) {
                 CheckResult::OK => (),
                 CheckResult::ERROR {errors: e} => {
-                    match machine_core::project(protos.clone(), SubscriptionsWrapped(subscriptions.clone()), role.clone(), false, false) {
+                    match machine_core::project(protos.clone(), SubscriptionsWrapped(subscriptions.clone()), role.clone(), false, false, None) {
                         DataResult::OK{data: projection1} => {
                             println!("machine combined: {}", serde_json::to_string_pretty::<MachineType>(&projection1).unwrap());
                         },
@@ -870,7 +883,7 @@ proptest! {
         let subscriptions = subscriptions.unwrap();
 
         for role in subscriptions.keys() {
-            let projection: Option<MachineType> = match machine_core::project(protos.clone(), SubscriptionsWrapped(subscriptions.clone()), role.clone(), true, true) {
+            let projection: Option<MachineType> = match machine_core::project(protos.clone(), SubscriptionsWrapped(subscriptions.clone()), role.clone(), true, true, None) {
                 DataResult::OK{data: projection} => {
                 Some(projection) },
                 DataResult::ERROR{ .. } => None,
@@ -879,7 +892,7 @@ proptest! {
             // should work like this projecting over the explicit composition initially and comparing that with combined machines?
             match check_composed_projection(protos.clone(), SubscriptionsWrapped(subscriptions.clone()), role.clone(), projection.clone().unwrap()) {
                 CheckResult::OK => {
-                    let combined: Option<MachineType> = match machine_core::project(protos.clone(), SubscriptionsWrapped(subscriptions.clone()), role.clone(), false, false) {
+                    let combined: Option<MachineType> = match machine_core::project(protos.clone(), SubscriptionsWrapped(subscriptions.clone()), role.clone(), false, false, None) {
                         DataResult::OK{data: combined} => {
                         Some(combined) },
                         DataResult::ERROR{ .. } => None,
@@ -891,7 +904,7 @@ proptest! {
                     println!("");
                 },//(),
                 CheckResult::ERROR {errors: e} => {
-                    match machine_core::project(protos.clone(), SubscriptionsWrapped(subscriptions.clone()), role.clone(), false, false) {
+                    match machine_core::project(protos.clone(), SubscriptionsWrapped(subscriptions.clone()), role.clone(), false, false, None) {
                         DataResult::OK{data: projection1} => {
                             println!("machine combined: {}", serde_json::to_string_pretty::<MachineType>(&projection1).unwrap());
                         },
@@ -0,0 +1,65 @@
+// Print a machine-runner TypeScript skeleton for a role from its projection of a swarm protocol composition.
+//
+// Usage: generate_machine_runner <swarm name> <role> <protocols.json> [<subscriptions.json>]
+//
+// protocols.json contains an array of swarm protocols. The subscription used is the
+// overapproximated well-formed subscription containing the one in subscriptions.json, if given.
+use std::{collections::BTreeMap, fs, process::ExitCode};
+
+use machine_core::types::typescript_types::{
+    DataResult, Granularity, InterfacingProtocols, Role, Subscriptions, SubscriptionsWrapped,
+};
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> Result<T, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| format!("could not parse {path}: {e}"))
+}
+
+fn generate(args: &[String]) -> Result<String, Vec<String>> {
+    let [swarm_name, role, protos, rest @ ..] = args else {
+        return Err(vec![
+            "usage: generate_machine_runner <swarm name> <role> <protocols.json> [<subscriptions.json>]"
+                .to_string(),
+        ]);
+    };
+    let protos: InterfacingProtocols = read_json(protos).map_err(|e| vec![e])?;
+    let subs: Subscriptions = match rest {
+        [] => BTreeMap::new(),
+        [subs] => read_json(subs).map_err(|e| vec![e])?,
+        _ => return Err(vec!["too many arguments".to_string()]),
+    };
+    let subs = match machine_core::overapproximated_well_formed_sub(
+        protos.clone(),
+        SubscriptionsWrapped(subs),
+        Granularity::TwoStep,
+    ) {
+        DataResult::OK { data } => data,
+        DataResult::ERROR { errors } => return Err(errors),
+    };
+    match machine_core::generate_machine_runner(
+        swarm_name.clone(),
+        protos,
+        SubscriptionsWrapped(subs),
+        Role::new(role),
+        true,
+    ) {
+        DataResult::OK { data } => Ok(data),
+        DataResult::ERROR { errors } => Err(errors),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match generate(&args) {
+        Ok(ts) => {
+            print!("{ts}");
+            ExitCode::SUCCESS
+        }
+        Err(errors) => {
+            for error in errors {
+                eprintln!("{error}");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
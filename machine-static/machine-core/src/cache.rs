@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::types::{
+    proto_info::{self, ProtoInfo},
+    typescript_types::InterfacingProtocols,
+};
+
+// Version of the serialized analysis. Bump when ProtoInfo or the analysis changes,
+// so that analyses cached by an older version are recomputed.
+pub const CACHE_VERSION: u32 = 4;
+
+// The result of swarms_to_proto_info together with a content hash of the protocols it was computed from.
+// Can be stored as JSON or in a compact binary format and reused across runs. The binary format is
+// MessagePack with named fields: it is self-describing, so optional fields of the protocols
+// that are skipped when absent round-trip, and it can be inspected without this crate.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedAnalysis {
+    pub version: u32,
+    pub hash: String,
+    pub proto_info: ProtoInfo,
+}
+
+// Hex encoded sha256 of the JSON representation of protos.
+pub fn content_hash(protos: &InterfacingProtocols) -> String {
+    let json = serde_json::to_string(protos).unwrap();
+    Sha256::digest(json.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+impl CachedAnalysis {
+    pub fn new(protos: InterfacingProtocols) -> Self {
+        let _span = tracing::info_span!("cached_analysis_new").entered();
+        let hash = content_hash(&protos);
+        Self {
+            version: CACHE_VERSION,
+            hash,
+            proto_info: proto_info::swarms_to_proto_info(protos),
+        }
+    }
+
+    // True if the analysis was computed from protos by this version.
+    pub fn is_valid_for(&self, protos: &InterfacingProtocols) -> bool {
+        self.version == CACHE_VERSION && self.hash == content_hash(protos)
+    }
+
+    // Reuse cached if it is valid for protos, analyze protos otherwise.
+    pub fn load_or_compute(cached: Option<Self>, protos: InterfacingProtocols) -> Self {
+        match cached {
+            Some(cached) if cached.is_valid_for(&protos) => cached,
+            _ => Self::new(protos),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let cached: Self = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        cached.check_version()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        rmp_serde::to_vec_named(self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let cached: Self = rmp_serde::from_slice(bytes).map_err(|e| e.to_string())?;
+        cached.check_version()
+    }
+
+    fn check_version(self) -> Result<Self, String> {
+        if self.version != CACHE_VERSION {
+            return Err(format!(
+                "analysis has version {}, expected {}",
+                self.version, CACHE_VERSION
+            ));
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::types::typescript_types::{Granularity, Role, Subscriptions};
+    use crate::{machine::projection, subscription::overapproximation};
+
+    #[test]
+    fn test_content_hash() {
+        test_utils::setup_logger();
+        let hash = content_hash(&test_utils::get_interfacing_swarms_1());
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, content_hash(&test_utils::get_interfacing_swarms_1()));
+        assert_ne!(hash, content_hash(&test_utils::get_interfacing_swarms_2()));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        test_utils::setup_logger();
+        let protos = test_utils::get_interfacing_swarms_2();
+        let cached = CachedAnalysis::new(protos.clone());
+        let subs: Subscriptions =
+            serde_json::from_str(r#"{ "T": ["partID", "part"], "F": ["car"] }"#).unwrap();
+        let role = Role::new("F");
+
+        let from_json = CachedAnalysis::from_json(&cached.to_json()).unwrap();
+        let from_bytes = CachedAnalysis::from_bytes(&cached.to_bytes()).unwrap();
+        assert!(cached.to_bytes().len() < cached.to_json().len());
+        for loaded in [from_json, from_bytes] {
+            assert!(loaded.is_valid_for(&protos));
+            assert_eq!(
+                format!("{:?}", loaded.proto_info),
+                format!("{:?}", cached.proto_info)
+            );
+            assert_eq!(
+                overapproximation::overapprox_wf_sub(
+                    &mut loaded.proto_info.clone(),
+                    &subs,
+                    Granularity::TwoStep
+                ),
+                overapproximation::overapprox_well_formed_sub(
+                    protos.clone(),
+                    &subs,
+                    Granularity::TwoStep
+                )
+                .unwrap()
+            );
+            let (proj, _) =
+                projection::project_combine(&loaded.proto_info, &subs, role.clone(), true);
+            let (expected, _) =
+                projection::project_combine(&cached.proto_info, &subs, role.clone(), true);
+            assert_eq!(format!("{:?}", proj), format!("{:?}", expected));
+        }
+    }
+
+    #[test]
+    fn test_binary_format() {
+        test_utils::setup_logger();
+        let cached = CachedAnalysis::new(test_utils::get_interfacing_swarms_1());
+        let value: serde_json::Value = rmp_serde::from_slice(&cached.to_bytes()).unwrap();
+        assert_eq!(value["version"], CACHE_VERSION);
+        assert_eq!(value["hash"], cached.hash);
+    }
+
+    #[test]
+    fn test_invalid_cache() {
+        test_utils::setup_logger();
+        let cached = CachedAnalysis::new(test_utils::get_interfacing_swarms_1());
+        assert!(!cached.is_valid_for(&test_utils::get_interfacing_swarms_2()));
+        let recomputed =
+            CachedAnalysis::load_or_compute(Some(cached), test_utils::get_interfacing_swarms_2());
+        assert!(recomputed.is_valid_for(&test_utils::get_interfacing_swarms_2()));
+
+        let old = CachedAnalysis {
+            version: CACHE_VERSION + 1,
+            ..recomputed
+        };
+        assert!(CachedAnalysis::from_json(&old.to_json()).is_err());
+        assert!(CachedAnalysis::from_bytes(&old.to_bytes()).is_err());
+        assert!(CachedAnalysis::from_bytes(&[1, 2, 3]).is_err());
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use crate::machine::adaptation;
+use crate::types::{
+    proto_info::ProtoInfo,
+    typescript_types::{
+        EventType, MachineLabel, PayloadSchema, ProjectionInfo, Role, Subscriptions,
+    },
+};
+
+// Generate a machine-runner TypeScript skeleton for role from its projection of the protocols in proto_info.
+// Contains the event designs and the SwarmProtocol.make event tuple, the subscriptions and projection information
+// needed for createMachineRunnerBT, the adapted machine made from them with makeAdaptedMachine, a state design
+// with command stubs for each state of the projection and a reaction for each input transition.
+// Payloads of commands are left as TODOs.
+// Returns None if the projection can not be computed, proto_info is assumed to have no errors.
+pub fn machine_runner_ts(
+    swarm_name: &str,
+    proto_info: &ProtoInfo,
+    subs: &Subscriptions,
+    role: Role,
+    minimize: bool,
+) -> Option<String> {
+    let _span = tracing::info_span!("machine_runner_ts", %role).entered();
+    let projection_info =
+        adaptation::projection_information_projection(proto_info, subs, role.clone(), minimize)?;
+    let event_types: BTreeSet<EventType> = proto_info
+        .role_event_map
+        .values()
+        .flatten()
+        .flat_map(|label| label.log_type.clone())
+        .collect();
+    // Event designs share the Events namespace with allEvents, everything else is declared
+    // next to the imports and the exported subscriptions and projection information.
+    let mut used = BTreeSet::from(["allEvents".to_string()]);
+    let events = identifiers(event_types.iter().map(|t| t.to_string()), "", &mut used);
+    let mut used = BTreeSet::from(
+        [
+            "MachineEvent",
+            "SwarmProtocol",
+            "utils",
+            "ProjectionInfo",
+            "Subscriptions",
+            "Events",
+            "subscriptions",
+            "projectionInfo",
+        ]
+        .map(String::from),
+    );
+    let protocol = fresh_identifier(swarm_name, "", &mut used);
+    let machine = fresh_identifier(&role, "", &mut used);
+    let state_names = projection_states(&projection_info);
+    let states = identifiers(state_names.iter().cloned(), "S", &mut used);
+
+    let mut ts = String::new();
+    writeln!(
+        ts,
+        "// Generated from the projection of role {role} on swarm protocol {swarm_name}."
+    )
+    .unwrap();
+    writeln!(
+        ts,
+        "import {{ MachineEvent, SwarmProtocol, utils }} from '@actyx/machine-runner'"
+    )
+    .unwrap();
+    writeln!(
+        ts,
+        "import type {{ ProjectionInfo, Subscriptions }} from 'machine-core'"
+    )
+    .unwrap();
+    writeln!(ts).unwrap();
+
+    writeln!(ts, "export namespace Events {{").unwrap();
+    for t in &event_types {
+        let payload = match proto_info.payloads.get(t) {
+            Some(PayloadSchema::Null) => "withoutPayload()".to_string(),
+            Some(schema @ PayloadSchema::Object { .. }) => {
+                format!("withPayload<{}>()", ts_type(schema))
+            }
+            // machine-runner payloads are objects, the event carries no schema it can be checked against.
+            Some(_) | None => "withPayload<utils.types.SerializableObject>()".to_string(),
+        };
+        writeln!(
+            ts,
+            "  export const {} = MachineEvent.design('{}').{payload}",
+            events[&t.to_string()],
+            escape(t)
+        )
+        .unwrap();
+    }
+    writeln!(
+        ts,
+        "  export const allEvents = [{}] as const",
+        event_types
+            .iter()
+            .map(|t| &events[&t.to_string()])
+            .join(", ")
+    )
+    .unwrap();
+    writeln!(ts, "}}").unwrap();
+    writeln!(ts).unwrap();
+
+    writeln!(
+        ts,
+        "export const {protocol} = SwarmProtocol.make('{}', Events.allEvents)",
+        escape(swarm_name)
+    )
+    .unwrap();
+    writeln!(
+        ts,
+        "export const subscriptions: Subscriptions = {}",
+        serde_json::to_string(subs).unwrap()
+    )
+    .unwrap();
+    writeln!(
+        ts,
+        "export const projectionInfo: ProjectionInfo = {}",
+        serde_json::to_string(&projection_info).unwrap()
+    )
+    .unwrap();
+    writeln!(ts).unwrap();
+
+    writeln!(
+        ts,
+        "export const {machine} = {protocol}.makeAdaptedMachine('{}', projectionInfo, {minimize})",
+        escape(&role)
+    )
+    .unwrap();
+    writeln!(ts).unwrap();
+
+    let projection = &projection_info.projection;
+    for state in &state_names {
+        writeln!(
+            ts,
+            "export const {} = {machine}.designEmpty('{}')",
+            states[state],
+            escape(state)
+        )
+        .unwrap();
+        for t in projection
+            .transitions
+            .iter()
+            .filter(|t| t.source.to_string() == *state)
+        {
+            let MachineLabel::Execute {
+                cmd,
+                log_type,
+                guard,
+                timeout,
+            } = &t.label
+            else {
+                continue;
+            };
+            if let Some(guard) = guard {
+                writeln!(ts, "  // enabled if {}", guard.predicate).unwrap();
+            }
+            if let Some(timeout) = timeout {
+                writeln!(
+                    ts,
+                    "  // on timeout: execute {timeout}ms after entering the state, the runner starts no timer"
+                )
+                .unwrap();
+            }
+            let factories = log_type
+                .iter()
+                .map(|t| format!("Events.{}", events[&t.to_string()]))
+                .join(", ");
+            let payloads = log_type
+                .iter()
+                .map(|t| {
+                    format!(
+                        "{{}} as MachineEvent.Payload.Of<typeof Events.{}>",
+                        events[&t.to_string()]
+                    )
+                })
+                .join(", ");
+            writeln!(
+                ts,
+                "  .command('{}', [{factories}], (_ctx) => [/* TODO */ {payloads}])",
+                escape(cmd)
+            )
+            .unwrap();
+        }
+        writeln!(ts, "  .finish()").unwrap();
+    }
+    writeln!(ts).unwrap();
+
+    for t in &projection.transitions {
+        if let MachineLabel::Input { event_type } = &t.label {
+            writeln!(
+                ts,
+                "{}.react([Events.{}], {}, (_ctx, _event) => {{}})",
+                states[&t.source.to_string()],
+                events[&event_type.to_string()],
+                states[&t.target.to_string()]
+            )
+            .unwrap();
+        }
+    }
+    writeln!(ts).unwrap();
+    writeln!(
+        ts,
+        "// const runner = createMachineRunnerBT(app, {protocol}.tagWithEntityId('id'), {}, undefined, {machine})",
+        states[&projection.initial.to_string()]
+    )
+    .unwrap();
+
+    Some(ts)
+}
+
+// The states of the projection in the order they first appear in its transitions.
+fn projection_states(projection_info: &ProjectionInfo) -> Vec<String> {
+    let mut states = vec![projection_info.projection.initial.clone()];
+    for t in &projection_info.projection.transitions {
+        for state in [&t.source, &t.target] {
+            if !states.contains(state) {
+                states.push(state.clone());
+            }
+        }
+    }
+    states.into_iter().map(|s| s.to_string()).collect()
+}
+
+// Distinct TypeScript identifiers for names, none of them in used. Adds them to used.
+fn identifiers(
+    names: impl Iterator<Item = String>,
+    prefix: &str,
+    used: &mut BTreeSet<String>,
+) -> BTreeMap<String, String> {
+    names
+        .map(|name| {
+            let ident = fresh_identifier(&name, prefix, used);
+            (name, ident)
+        })
+        .collect()
+}
+
+// An identifier for name that is not in used, suffixed with a counter if needed. Adds it to used.
+fn fresh_identifier(name: &str, prefix: &str, used: &mut BTreeSet<String>) -> String {
+    let base = identifier(name, prefix);
+    let mut ident = base.clone();
+    let mut i = 1;
+    while !used.insert(ident.clone()) {
+        ident = format!("{base}_{i}");
+        i += 1;
+    }
+    ident
+}
+
+// Words that can not be used as names of constants in a TypeScript module.
+const RESERVED_WORDS: &str = "arguments await break case catch class const continue debugger default \
+    delete do else enum eval export extends false finally for function if implements import in \
+    instanceof interface let new null package private protected public return static super switch \
+    this throw true try typeof undefined var void while with yield";
+
+// name with runs of other characters than ASCII letters and digits replaced by '_', e.g. S3_4 for '{ { 3 }, { 4 } }'.
+// Reserved words get a trailing '_', e.g. class_ for 'class'.
+fn identifier(name: &str, prefix: &str) -> String {
+    let ident = format!(
+        "{prefix}{}",
+        name.split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .join("_")
+    );
+    match ident.chars().next() {
+        Some(c) if c.is_ascii_digit() => format!("_{ident}"),
+        None => "_".to_string(),
+        Some(_) if RESERVED_WORDS.split_whitespace().any(|word| word == ident) => {
+            format!("{ident}_")
+        }
+        Some(_) => ident,
+    }
+}
+
+// Escape a string for a single quoted TypeScript string literal.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn ts_type(schema: &PayloadSchema) -> String {
+    match schema {
+        PayloadSchema::Null => "null".to_string(),
+        PayloadSchema::Boolean => "boolean".to_string(),
+        PayloadSchema::Integer | PayloadSchema::Number => "number".to_string(),
+        PayloadSchema::String => "string".to_string(),
+        PayloadSchema::Array { items } => format!("Array<{}>", ts_type(items)),
+        PayloadSchema::Object {
+            properties,
+            required,
+        } => {
+            let fields = properties
+                .iter()
+                .map(|(name, schema)| {
+                    let optional = if required.contains(name) { "" } else { "?" };
+                    let name = if identifier(name, "") == *name {
+                        name.clone()
+                    } else {
+                        format!("'{}'", escape(name))
+                    };
+                    format!("{name}{optional}: {}", ts_type(schema))
+                })
+                .join("; ");
+            format!("{{ {fields} }}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::subscription::exact;
+    use crate::test_utils;
+    use crate::types::{
+        proto_info,
+        typescript_types::{InterfacingProtocols, SwarmProtocolType},
+    };
+
+    #[test]
+    fn test_machine_runner_ts() {
+        test_utils::setup_logger();
+        let protos = InterfacingProtocols(vec![test_utils::get_timed_proto()]);
+        let subs =
+            exact::exact_well_formed_sub(protos.clone(), &BTreeMap::new(), usize::MAX).unwrap();
+        let proto_info = proto_info::swarms_to_proto_info(protos);
+        let ts = machine_runner_ts("warehouse", &proto_info, &subs, Role::new("W"), true).unwrap();
+
+        assert!(ts.contains(
+            "export const warehouse = SwarmProtocol.make('warehouse', Events.allEvents)"
+        ));
+        assert!(ts.contains("export const allEvents = [cancelled, part, partID, stored] as const"));
+        assert!(
+            ts.contains("export const W = warehouse.makeAdaptedMachine('W', projectionInfo, true)")
+        );
+        assert!(ts.contains(
+            "  // on timeout: execute 300000ms after entering the state, the runner starts no timer\n  .command('cancel', [Events.cancelled]"
+        ));
+        assert!(ts.contains("S0.react([Events.partID], S1, (_ctx, _event) => {})"));
+        assert!(ts.contains("S1.react([Events.cancelled], S3_4, (_ctx, _event) => {})"));
+        assert!(ts.contains("S2.react([Events.stored], S3_4, (_ctx, _event) => {})"));
+        assert!(ts.contains(r#""branches":{"cancelled":[],"part":["stored"],"partID":["cancelled","part"],"stored":[]}"#));
+        assert!(ts.contains(
+            "createMachineRunnerBT(app, warehouse.tagWithEntityId('id'), S0, undefined, W)"
+        ));
+
+        // Payload schemas become the payload types of the event designs.
+        let protos = test_utils::get_payload_protos();
+        let subs =
+            exact::exact_well_formed_sub(protos.clone(), &BTreeMap::new(), usize::MAX).unwrap();
+        let proto_info = proto_info::swarms_to_proto_info(protos);
+        let ts = machine_runner_ts("auction", &proto_info, &subs, Role::new("S"), true).unwrap();
+        assert!(ts.contains(
+            "export const bid = MachineEvent.design('bid').withPayload<{ bidder?: string; price: number }>()"
+        ));
+        assert!(ts.contains(
+            "export const selected = MachineEvent.design('selected').withPayload<{ items: Array<number> }>()"
+        ));
+        assert!(
+            ts.contains("export const shipped = MachineEvent.design('shipped').withoutPayload()")
+        );
+    }
+
+    #[test]
+    fn test_machine_runner_ts_identifiers() {
+        test_utils::setup_logger();
+        // The swarm, the role and a state are all named S0, an event type is named allEvents.
+        let proto = serde_json::from_str::<SwarmProtocolType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "source": "0", "target": "1", "label": { "cmd": "start", "logType": ["allEvents"], "role": "S0" } },
+                    { "source": "1", "target": "2", "label": { "cmd": "stop", "logType": ["Events"], "role": "S0" } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let protos = InterfacingProtocols(vec![proto]);
+        let subs =
+            exact::exact_well_formed_sub(protos.clone(), &BTreeMap::new(), usize::MAX).unwrap();
+        let proto_info = proto_info::swarms_to_proto_info(protos);
+        let ts = machine_runner_ts("S0", &proto_info, &subs, Role::new("S0"), true).unwrap();
+
+        assert!(ts.contains("export const allEvents_1 = MachineEvent.design('allEvents')"));
+        assert!(ts.contains("export const allEvents = [Events, allEvents_1] as const"));
+        assert!(ts.contains("export const S0 = SwarmProtocol.make('S0', Events.allEvents)"));
+        assert!(
+            ts.contains("export const S0_1 = S0.makeAdaptedMachine('S0', projectionInfo, true)")
+        );
+        assert!(ts.contains("export const S0_2 = S0_1.designEmpty('{ { 0 } }')"));
+        assert!(ts.contains("S0_2.react([Events.allEvents_1], S1, (_ctx, _event) => {})"));
+        assert!(ts.contains(
+            "createMachineRunnerBT(app, S0.tagWithEntityId('id'), S0_2, undefined, S0_1)"
+        ));
+
+        // No name is declared twice in the module or in the Events namespace.
+        let declared = |indent: &str| -> Vec<String> {
+            ts.lines()
+                .filter_map(|line| line.strip_prefix(indent)?.strip_prefix("export const "))
+                .map(|line| line.split(' ').next().unwrap().to_string())
+                .collect()
+        };
+        for names in [declared(""), declared("  ")] {
+            assert_eq!(names.iter().collect::<BTreeSet<_>>().len(), names.len());
+        }
+
+        // Reserved words are not used as names.
+        let proto = serde_json::from_str::<SwarmProtocolType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "source": "0", "target": "1", "label": { "cmd": "new", "logType": ["new"], "role": "class" } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let protos = InterfacingProtocols(vec![proto]);
+        let subs =
+            exact::exact_well_formed_sub(protos.clone(), &BTreeMap::new(), usize::MAX).unwrap();
+        let proto_info = proto_info::swarms_to_proto_info(protos);
+        let ts = machine_runner_ts("delete", &proto_info, &subs, Role::new("class"), true).unwrap();
+        assert!(ts.contains("export const new_ = MachineEvent.design('new')"));
+        assert!(
+            ts.contains("export const delete_ = SwarmProtocol.make('delete', Events.allEvents)")
+        );
+        assert!(ts.contains(
+            "export const class_ = delete_.makeAdaptedMachine('class', projectionInfo, true)"
+        ));
+        assert!(ts.contains("  .command('new', [Events.new_]"));
+    }
+
+    // The generated skeleton for W in the timed protocol is type checked and run against
+    // machine-runner by its tests. Update the copy there if the generated code changes.
+    #[test]
+    fn test_machine_runner_ts_checked_copy() {
+        test_utils::setup_logger();
+        let protos = InterfacingProtocols(vec![test_utils::get_timed_proto()]);
+        let subs =
+            exact::exact_well_formed_sub(protos.clone(), &BTreeMap::new(), usize::MAX).unwrap();
+        let proto_info = proto_info::swarms_to_proto_info(protos);
+        let ts = machine_runner_ts("warehouse", &proto_info, &subs, Role::new("W"), true).unwrap();
+        let copy = std::fs::read_to_string(format!(
+            "{}/../../machine-runner/tests/esm/protocol-generated.ts",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap();
+        assert_eq!(ts, copy);
+    }
+}
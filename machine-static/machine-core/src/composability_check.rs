@@ -4,10 +4,11 @@ use petgraph::visit::EdgeRef;
 
 use crate::{
     errors::Error,
+    parallel,
     types::{
         proto_graph::EdgeId,
         proto_info::{ProtoInfo, ProtoStruct},
-        typescript_types::{Command, EventLabel, EventType},
+        typescript_types::{Command, EventLabel, EventType, SwarmLabel},
     },
 };
 
@@ -35,16 +36,18 @@ macro_rules! get_ith_or_error {
 // Perform confusion freeness check on every protocol in a ProtoInfo.
 pub fn confusion_free_proto_info(proto_info: ProtoInfo) -> ProtoInfo {
     let _span = tracing::info_span!("confusion_free_proto_info").entered();
-    let protocols: Vec<_> = proto_info
-        .protocols
-        .clone()
-        .into_iter()
-        .enumerate()
-        .map(|(i, p)| {
+    let protocols = parallel::map(
+        proto_info
+            .protocols
+            .clone()
+            .into_iter()
+            .enumerate()
+            .collect(),
+        |(i, p)| {
             let errors = vec![p.errors, confusion_free(&proto_info, i)].concat();
             ProtoStruct { errors, ..p }
-        })
-        .collect();
+        },
+    );
 
     ProtoInfo {
         protocols,
@@ -187,11 +190,65 @@ fn cross_protocol_command_errors(proto_info1: &ProtoInfo, proto_info2: &ProtoInf
     command_errors
 }
 
-// Checks that event types (commands) appearing in different swarm protocols are associated with the same commands (event types) and roles
+// The event types appearing in proto_info1 and proto_info2 with a different label_part in each, e.g. the data guard.
+fn cross_protocol_label_mismatches<T: PartialEq>(
+    proto_info1: &ProtoInfo,
+    proto_info2: &ProtoInfo,
+    label_part: impl Fn(&SwarmLabel) -> T,
+) -> Vec<EventType> {
+    let label_map = |proto_info: &ProtoInfo| -> BTreeMap<EventType, T> {
+        proto_info
+            .role_event_map
+            .values()
+            .flatten()
+            .map(|label| (label.get_event_type(), label_part(label)))
+            .collect()
+    };
+    let label_map1 = label_map(proto_info1);
+    let label_map2 = label_map(proto_info2);
+
+    label_map1
+        .into_iter()
+        .filter(|(t, part1)| label_map2.get(t).is_some_and(|part2| part2 != part1))
+        .map(|(t, _)| t)
+        .collect()
+}
+
+// Event types declaring a payload schema in both protocols must declare the same schema.
+fn cross_protocol_payload_errors(proto_info1: &ProtoInfo, proto_info2: &ProtoInfo) -> Vec<Error> {
+    proto_info1
+        .payloads
+        .iter()
+        .filter_map(|(t, schema1)| {
+            proto_info2
+                .payloads
+                .get(t)
+                .filter(|schema2| *schema2 != schema1)
+                .map(|schema2| {
+                    Error::PayloadSchemaOnDifferentLabels(
+                        t.clone(),
+                        schema1.clone(),
+                        schema2.clone(),
+                    )
+                })
+        })
+        .collect()
+}
+
+// Checks that event types (commands) appearing in different swarm protocols are associated with the same commands (event types), roles, data guards, timeouts and payload schemas
 pub fn check_interface(proto_info1: &ProtoInfo, proto_info2: &ProtoInfo) -> Vec<Error> {
     vec![
         cross_protocol_event_type_errors(proto_info1, proto_info2),
         cross_protocol_command_errors(proto_info1, proto_info2),
+        cross_protocol_label_mismatches(proto_info1, proto_info2, |label| label.guard.clone())
+            .into_iter()
+            .map(Error::DataGuardOnDifferentLabels)
+            .collect(),
+        cross_protocol_label_mismatches(proto_info1, proto_info2, |label| label.timeout)
+            .into_iter()
+            .map(Error::TimeoutOnDifferentLabels)
+            .collect(),
+        cross_protocol_payload_errors(proto_info1, proto_info2),
     ]
     .concat()
 }
@@ -214,7 +271,8 @@ mod tests {
         let composition = test_utils::get_interfacing_swarms_1();
         let proto_info =
             proto_info::combine_proto_infos(proto_info::prepare_proto_infos(composition));
-        let proto_info = proto_info::explicit_composition_proto_info(proto_info);
+        let proto_info =
+            proto_info::explicit_composition_proto_info_bounded(proto_info, usize::MAX).unwrap();
 
         assert!(proto_info.get_ith_proto(0).is_some());
         assert!(proto_info.get_ith_proto(0).unwrap().errors.is_empty());
@@ -241,11 +299,15 @@ mod tests {
                         cmd: Command::new("deliver"),
                         log_type: vec![EventType::new("part")],
                         role: Role::new("T"),
+                        guard: None,
+                        timeout: None,
                     },
                     SwarmLabel {
                         cmd: Command::new("request"),
                         log_type: vec![EventType::new("partID")],
                         role: Role::new("T"),
+                        guard: None,
+                        timeout: None,
                     },
                 ]),
             ),
@@ -255,6 +317,8 @@ mod tests {
                     cmd: Command::new("get"),
                     log_type: vec![EventType::new("pos")],
                     role: Role::new("FL"),
+                    guard: None,
+                    timeout: None,
                 }]),
             ),
             (
@@ -263,6 +327,8 @@ mod tests {
                     cmd: Command::new("close"),
                     log_type: vec![EventType::new("time")],
                     role: Role::new("D"),
+                    guard: None,
+                    timeout: None,
                 }]),
             ),
             (
@@ -271,6 +337,8 @@ mod tests {
                     cmd: Command::new("build"),
                     log_type: vec![EventType::new("car")],
                     role: Role::new("F"),
+                    guard: None,
+                    timeout: None,
                 }]),
             ),
         ]);
@@ -15,6 +15,28 @@ pub(crate) fn compose<N, E: EventLabel>(
     interface: BTreeSet<EventType>,
     gen_node: fn(&N, &N) -> N,
 ) -> (petgraph::Graph<N, E>, NodeId) {
+    compose_bounded(
+        graph1,
+        initial1,
+        graph2,
+        initial2,
+        interface,
+        gen_node,
+        usize::MAX,
+    )
+    .unwrap()
+}
+
+// Like compose, but gives up and returns None as soon as the composition has more than max_states nodes.
+pub(crate) fn compose_bounded<N, E: EventLabel>(
+    graph1: petgraph::Graph<N, E>,
+    initial1: NodeId,
+    graph2: petgraph::Graph<N, E>,
+    initial2: NodeId,
+    interface: BTreeSet<EventType>,
+    gen_node: fn(&N, &N) -> N,
+    max_states: usize,
+) -> Option<(petgraph::Graph<N, E>, NodeId)> {
     let _span = tracing::info_span!("compose").entered();
     let mut graph = petgraph::Graph::<N, E>::new();
     let mut node_map: BTreeMap<(NodeId, NodeId), NodeId> = BTreeMap::new();
@@ -81,11 +103,15 @@ pub(crate) fn compose<N, E: EventLabel>(
                 graph.add_edge(src, new_dst, e);
                 node_map.insert((dst1, dst2), new_dst);
                 worklist.push((new_dst, (dst1, dst2)));
+                if graph.node_count() > max_states {
+                    tracing::debug!(max_states, "composition exceeds limit");
+                    return None;
+                }
             }
         }
     }
 
-    (graph, combined_initial)
+    Some((graph, combined_initial))
 }
 
 pub(crate) fn gen_state_name<N: StateName + From<String>>(n1: &N, n2: &N) -> N {
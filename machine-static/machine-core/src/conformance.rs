@@ -0,0 +1,529 @@
+use std::collections::BTreeSet;
+
+use petgraph::{
+    Direction::{Incoming, Outgoing},
+    visit::{Dfs, EdgeRef, Reversed},
+};
+
+use crate::machine::projection;
+use crate::types::{
+    proto_graph::{Graph, NodeId},
+    proto_info::{self, ProtoInfo},
+    typescript_types::{
+        ConformanceReport, EventLabel, EventType, ExpectedEvent, InterfacingProtocols,
+        LocalConformanceReport, LocalNonConformance, LogEntry, MachineLabel, NonConformance, Role,
+        StateName, Subscriptions,
+    },
+    unordered_event_pair::UnordEventPair,
+};
+
+// Parse a log given as JSON lines. Blank lines are skipped.
+pub fn parse_event_log(log: &str) -> Result<Vec<LogEntry>, Vec<String>> {
+    let mut entries = vec![];
+    let mut errors = vec![];
+    for (i, line) in log.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<LogEntry>(line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => errors.push(format!("line {}: {e}", i + 1)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(entries)
+    } else {
+        Err(errors)
+    }
+}
+
+// Check whether a log is an execution of the composition of protos.
+// Entries are taken in timestamp order. Entries with equal timestamps
+// may appear in any order in the log if their event types are concurrent.
+// Payloads of entries are checked against the schemas of their event types.
+// Fails if the composition has more than max_states states.
+pub fn check_conformance(
+    protos: InterfacingProtocols,
+    log: &[LogEntry],
+    max_states: usize,
+) -> Result<ConformanceReport, Vec<String>> {
+    let _span = tracing::info_span!("check_conformance").entered();
+    let (explicit, initial) = composed(protos, max_states)?;
+    let graph = &explicit.protocols[0].graph;
+
+    let mut sorted: Vec<(usize, &LogEntry)> = log.iter().enumerate().collect();
+    sorted.sort_by_key(|(_, entry)| entry.timestamp);
+
+    let mut current = initial;
+    for group in sorted.chunk_by(|(_, a), (_, b)| a.timestamp == b.timestamp) {
+        let mut pending = group.to_vec();
+        while !pending.is_empty() {
+            let next = pending.iter().enumerate().find_map(|(j, (_, entry))| {
+                let commutes = pending[..j].iter().all(|(_, earlier)| {
+                    explicit.concurrent_events.contains(&UnordEventPair::new(
+                        earlier.event_type.clone(),
+                        entry.event_type.clone(),
+                    ))
+                });
+                if !commutes {
+                    return None;
+                }
+                step(graph, current, entry).map(|target| (j, target))
+            });
+            let Some((j, target)) = next else {
+                let (index, entry) = pending[0];
+                tracing::debug!(index, event_type = %entry.event_type, "non-conforming entry");
+                return Ok(ConformanceReport {
+                    protocol_state: graph[current].state_name().clone(),
+                    terminal: false,
+                    non_conformance: Some(NonConformance {
+                        index,
+                        entry: entry.clone(),
+                        expected: expected_events(graph, current),
+                        payload_errors: vec![],
+                    }),
+                });
+            };
+            let (index, entry) = pending[j];
+            let payload_errors = payload_errors(&explicit, entry);
+            if !payload_errors.is_empty() {
+                tracing::debug!(index, event_type = %entry.event_type, "invalid payload");
+                return Ok(ConformanceReport {
+                    protocol_state: graph[current].state_name().clone(),
+                    terminal: false,
+                    non_conformance: Some(NonConformance {
+                        index,
+                        entry: entry.clone(),
+                        expected: expected_events(graph, current),
+                        payload_errors,
+                    }),
+                });
+            }
+            current = target;
+            pending.remove(j);
+        }
+    }
+
+    Ok(ConformanceReport {
+        protocol_state: graph[current].state_name().clone(),
+        terminal: graph.edges_directed(current, Outgoing).next().is_none(),
+        non_conformance: None,
+    })
+}
+
+// Check whether the local log of a peer playing role is an execution of the projection
+// of the composition of protos over role. The log is taken in the order given and
+// should only contain the event types role subscribes to.
+// Payloads of entries are checked against the schemas of their event types.
+// Fails if the composition has more than max_states states.
+pub fn check_local_conformance(
+    protos: InterfacingProtocols,
+    subs: &Subscriptions,
+    role: Role,
+    log: &[LogEntry],
+    max_states: usize,
+) -> Result<LocalConformanceReport, Vec<String>> {
+    let _span = tracing::info_span!("check_local_conformance", %role).entered();
+    let sub = subs.get(&role).cloned().unwrap_or_default();
+    let errors: Vec<String> = log
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| !sub.contains(&entry.event_type))
+        .map(|(i, entry)| {
+            format!(
+                "event type {} at index {i} is not subscribed to by role {role}",
+                entry.event_type
+            )
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let (explicit, initial) = composed(protos, max_states)?;
+    let graph = &explicit.protocols[0].graph;
+    let (proj, proj_initial, origin) =
+        projection::project_with_origin(graph, initial, subs, role, true);
+
+    let mut current = proj_initial;
+    for (index, entry) in log.iter().enumerate() {
+        let next = proj
+            .edges_directed(current, Outgoing)
+            .find(|edge| {
+                matches!(edge.weight(), MachineLabel::Input { event_type } if *event_type == entry.event_type)
+            })
+            .map(|edge| edge.target());
+        let Some(next) = next else {
+            tracing::debug!(index, event_type = %entry.event_type, "non-conforming entry");
+            let expected = proj
+                .edges_directed(current, Outgoing)
+                .filter_map(|edge| match edge.weight() {
+                    MachineLabel::Input { event_type } => Some(event_type.clone()),
+                    MachineLabel::Execute { .. } => None,
+                })
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            return Ok(LocalConformanceReport {
+                machine_state: proj[current].clone(),
+                non_conformance: Some(LocalNonConformance {
+                    index,
+                    entry: entry.clone(),
+                    expected,
+                    missing_subscriptions: missing_subscriptions(
+                        graph,
+                        &sub,
+                        &origin[current.index()],
+                        entry,
+                    ),
+                    payload_errors: vec![],
+                }),
+            });
+        };
+        let payload_errors = payload_errors(&explicit, entry);
+        if !payload_errors.is_empty() {
+            tracing::debug!(index, event_type = %entry.event_type, "invalid payload");
+            return Ok(LocalConformanceReport {
+                machine_state: proj[current].clone(),
+                non_conformance: Some(LocalNonConformance {
+                    index,
+                    entry: entry.clone(),
+                    expected: vec![entry.event_type.clone()],
+                    missing_subscriptions: vec![],
+                    payload_errors,
+                }),
+            });
+        }
+        current = next;
+    }
+
+    Ok(LocalConformanceReport {
+        machine_state: proj[current].clone(),
+        non_conformance: None,
+    })
+}
+
+// The event types not in sub leading to protocol states where entry is possible
+// but not leading to the protocol states believed states stands for.
+fn missing_subscriptions(
+    graph: &Graph,
+    sub: &BTreeSet<EventType>,
+    believed: &BTreeSet<NodeId>,
+    entry: &LogEntry,
+) -> Vec<EventType> {
+    let possible: BTreeSet<NodeId> = graph
+        .node_indices()
+        .filter(|node| step(graph, *node, entry).is_some())
+        .collect();
+    let hidden_towards = |targets: &BTreeSet<NodeId>| -> BTreeSet<EventType> {
+        let mut leads_to = BTreeSet::new();
+        let reversed = Reversed(graph);
+        for target in targets {
+            let mut dfs = Dfs::new(reversed, *target);
+            while let Some(node) = dfs.next(reversed) {
+                leads_to.insert(node);
+            }
+        }
+        leads_to
+            .iter()
+            .flat_map(|node| graph.edges_directed(*node, Incoming))
+            .map(|edge| edge.weight().get_event_type())
+            .filter(|event_type| !sub.contains(event_type))
+            .collect()
+    };
+
+    hidden_towards(&possible)
+        .difference(&hidden_towards(believed))
+        .cloned()
+        .collect()
+}
+
+fn composed(
+    protos: InterfacingProtocols,
+    max_states: usize,
+) -> Result<(ProtoInfo, NodeId), Vec<String>> {
+    let proto_info = proto_info::swarms_to_proto_info(protos);
+    if !proto_info.no_errors() {
+        return Err(proto_info.to_error_report().to_strings());
+    }
+    let explicit = proto_info::explicit_composition_proto_info_bounded(proto_info, max_states)
+        .map_err(|errors| errors.to_strings())?;
+    match explicit.protocols[0].initial {
+        Some(initial) => Ok((explicit, initial)),
+        None => Err(vec![
+            "initial swarm protocol state has no transitions".to_string(),
+        ]),
+    }
+}
+
+// Paths of the parts of the payload of entry that do not match the schema of its event type.
+// Entries without a payload and event types without a schema are not checked.
+fn payload_errors(proto_info: &ProtoInfo, entry: &LogEntry) -> Vec<String> {
+    match (proto_info.payloads.get(&entry.event_type), &entry.payload) {
+        (Some(schema), Some(payload)) => schema.validate(payload),
+        _ => vec![],
+    }
+}
+
+fn step(graph: &Graph, node: NodeId, entry: &LogEntry) -> Option<NodeId> {
+    graph
+        .edges_directed(node, Outgoing)
+        .find(|edge| {
+            edge.weight().get_event_type() == entry.event_type && edge.weight().role == entry.role
+        })
+        .map(|edge| edge.target())
+}
+
+fn expected_events(graph: &Graph, node: NodeId) -> Vec<ExpectedEvent> {
+    graph
+        .edges_directed(node, Outgoing)
+        .map(|edge| ExpectedEvent {
+            event_type: edge.weight().get_event_type(),
+            role: edge.weight().role.clone(),
+        })
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::types::proto_info::DEFAULT_MAX_STATES;
+    use crate::types::typescript_types::{State, SwarmProtocolType};
+    use std::collections::BTreeMap;
+
+    fn entry(event_type: &str, role: &str, timestamp: u64) -> LogEntry {
+        LogEntry {
+            event_type: EventType::new(event_type),
+            role: Role::new(role),
+            timestamp,
+            payload: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_event_log() {
+        let log = r#"{"eventType": "partID", "source": "T", "timestamp": 1, "payload": {"id": "x"}}
+
+{"eventType": "pos", "role": "FL", "timestamp": 2}
+{"eventType": "part"}"#;
+        let errors = parse_event_log(log).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("line 4: missing field"));
+
+        let log = log.lines().take(3).collect::<Vec<_>>().join("\n");
+        assert_eq!(
+            parse_event_log(&log).unwrap(),
+            vec![
+                LogEntry {
+                    payload: Some(serde_json::json!({"id": "x"})),
+                    ..entry("partID", "T", 1)
+                },
+                entry("pos", "FL", 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conformance_1() {
+        test_utils::setup_logger();
+        let protos = InterfacingProtocols(vec![test_utils::get_proto1()]);
+        let log = vec![
+            entry("partID", "T", 1),
+            entry("pos", "FL", 2),
+            entry("part", "T", 3),
+            entry("time", "D", 4),
+        ];
+        let report = check_conformance(protos.clone(), &log, DEFAULT_MAX_STATES).unwrap();
+        assert_eq!(
+            report,
+            ConformanceReport {
+                protocol_state: State::new("3"),
+                terminal: true,
+                non_conformance: None
+            }
+        );
+
+        // pos emitted by the wrong role. timestamps out of log order are fine.
+        let log = vec![
+            entry("pos", "T", 2),
+            entry("partID", "T", 1),
+            entry("part", "T", 3),
+        ];
+        let report = check_conformance(protos, &log, DEFAULT_MAX_STATES).unwrap();
+        assert_eq!(report.protocol_state, State::new("1"));
+        assert_eq!(
+            report.non_conformance,
+            Some(NonConformance {
+                index: 0,
+                entry: entry("pos", "T", 2),
+                expected: vec![ExpectedEvent {
+                    event_type: EventType::new("pos"),
+                    role: Role::new("FL")
+                }],
+                payload_errors: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn test_conformance_composition() {
+        test_utils::setup_logger();
+        let protos = test_utils::get_interfacing_swarms_1();
+        // car and time are concurrent
+        let log = vec![
+            entry("partID", "T", 1),
+            entry("pos", "FL", 2),
+            entry("part", "T", 3),
+            entry("car", "F", 4),
+            entry("time", "D", 4),
+        ];
+        let report = check_conformance(protos.clone(), &log, DEFAULT_MAX_STATES).unwrap();
+        assert_eq!(report.non_conformance, None);
+        assert!(report.terminal);
+
+        // pos depends on partID so the tie can not be resolved by reordering
+        let log = vec![
+            entry("pos", "FL", 1),
+            entry("partID", "T", 1),
+            entry("part", "T", 3),
+        ];
+        let report = check_conformance(protos, &log, DEFAULT_MAX_STATES).unwrap();
+        let non_conformance = report.non_conformance.unwrap();
+        assert_eq!(non_conformance.index, 0);
+        assert_eq!(
+            non_conformance.expected,
+            vec![
+                ExpectedEvent {
+                    event_type: EventType::new("partID"),
+                    role: Role::new("T")
+                },
+                ExpectedEvent {
+                    event_type: EventType::new("time"),
+                    role: Role::new("D")
+                },
+            ]
+        );
+    }
+
+    fn get_branching_proto() -> SwarmProtocolType {
+        serde_json::from_str::<SwarmProtocolType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "source": "0", "target": "1", "label": { "cmd": "a", "logType": ["a"], "role": "R1" } },
+                    { "source": "1", "target": "2", "label": { "cmd": "c", "logType": ["c"], "role": "R2" } },
+                    { "source": "2", "target": "5", "label": { "cmd": "e", "logType": ["e"], "role": "R3" } },
+                    { "source": "0", "target": "3", "label": { "cmd": "b", "logType": ["b"], "role": "R1" } },
+                    { "source": "3", "target": "4", "label": { "cmd": "d", "logType": ["d"], "role": "R2" } },
+                    { "source": "4", "target": "6", "label": { "cmd": "f", "logType": ["f"], "role": "R3" } }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_local_conformance() {
+        test_utils::setup_logger();
+        let protos = InterfacingProtocols(vec![get_branching_proto()]);
+        let subs = BTreeMap::from([(
+            Role::new("R3"),
+            BTreeSet::from([
+                EventType::new("c"),
+                EventType::new("e"),
+                EventType::new("f"),
+            ]),
+        )]);
+        let log = vec![entry("c", "R2", 2), entry("e", "R3", 3)];
+        let report = check_local_conformance(
+            protos.clone(),
+            &subs,
+            Role::new("R3"),
+            &log,
+            DEFAULT_MAX_STATES,
+        )
+        .unwrap();
+        assert_eq!(report.non_conformance, None);
+
+        // R3 does not see that R1 chose b
+        let log = vec![entry("c", "R2", 2), entry("f", "R3", 3)];
+        let report = check_local_conformance(
+            protos.clone(),
+            &subs,
+            Role::new("R3"),
+            &log,
+            DEFAULT_MAX_STATES,
+        )
+        .unwrap();
+        assert_eq!(
+            report.non_conformance,
+            Some(LocalNonConformance {
+                index: 1,
+                entry: entry("f", "R3", 3),
+                expected: vec![EventType::new("e")],
+                missing_subscriptions: vec![EventType::new("b"), EventType::new("d")],
+                payload_errors: vec![],
+            })
+        );
+
+        let log = vec![entry("a", "R1", 1)];
+        assert_eq!(
+            check_local_conformance(protos, &subs, Role::new("R3"), &log, DEFAULT_MAX_STATES)
+                .unwrap_err(),
+            vec!["event type a at index 0 is not subscribed to by role R3".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_conformance_payloads() {
+        test_utils::setup_logger();
+        let protos = test_utils::get_payload_protos();
+        let with_payload = |entry: LogEntry, payload: serde_json::Value| LogEntry {
+            payload: Some(payload),
+            ..entry
+        };
+        let log = vec![
+            with_payload(entry("bid", "B", 1), serde_json::json!({"price": 3})),
+            with_payload(
+                entry("selected", "S", 2),
+                serde_json::json!({"items": [1, "x"]}),
+            ),
+        ];
+        let report = check_conformance(protos.clone(), &log, DEFAULT_MAX_STATES).unwrap();
+        assert_eq!(report.protocol_state, State::new("1 || 0"));
+        assert_eq!(
+            report.non_conformance,
+            Some(NonConformance {
+                index: 1,
+                entry: log[1].clone(),
+                expected: vec![ExpectedEvent {
+                    event_type: EventType::new("selected"),
+                    role: Role::new("S")
+                }],
+                payload_errors: vec!["$.items[1] is not of type integer".to_string()],
+            })
+        );
+        // entries without a payload are not checked
+        let log = vec![entry("bid", "B", 1), entry("selected", "S", 2)];
+        let report = check_conformance(protos.clone(), &log, DEFAULT_MAX_STATES).unwrap();
+        assert_eq!(report.non_conformance, None);
+
+        let subs = BTreeMap::from([(
+            Role::new("W"),
+            BTreeSet::from([EventType::new("selected"), EventType::new("shipped")]),
+        )]);
+        let log = vec![with_payload(
+            entry("selected", "S", 2),
+            serde_json::json!({}),
+        )];
+        let report =
+            check_local_conformance(protos, &subs, Role::new("W"), &log, DEFAULT_MAX_STATES)
+                .unwrap();
+        assert_eq!(
+            report.non_conformance.unwrap().payload_errors,
+            vec!["$.items is missing".to_string()]
+        );
+    }
+}
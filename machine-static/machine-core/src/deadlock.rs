@@ -0,0 +1,308 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use petgraph::{Direction::Outgoing, algo::tarjan_scc, visit::EdgeRef};
+
+use crate::types::{
+    proto_graph::{self, EdgeId, Graph, NodeId},
+    proto_info::{self, ProtoInfo},
+    typescript_types::{
+        ClosedComponent, DeadlockReport, EventLabel, EventType, InterfaceDeadlock,
+        InterfacingProtocols, State, StateName, StateWitness, SwarmLabel, Transition,
+    },
+};
+
+// Report the states of the composition of protos that can not reach a terminal state,
+// the strongly connected components that can not be left and the composed states where
+// a protocol waits for interfacing events that can not be emitted anymore.
+// Fails if the composition has more than max_states states.
+pub fn deadlock_report(
+    protos: InterfacingProtocols,
+    max_states: usize,
+) -> Result<DeadlockReport, Vec<String>> {
+    let _span = tracing::info_span!("deadlock_report").entered();
+    let proto_info = proto_info::swarms_to_proto_info(protos);
+    if !proto_info.no_errors() {
+        return Err(proto_info.to_error_report().to_strings());
+    }
+    let (tracked, initial) = proto_info::tracked_composition(&proto_info, max_states)
+        .map_err(|errors| errors.to_strings())?;
+    if tracked.node_count() == 0 {
+        return Err(vec![
+            "initial swarm protocol state has no transitions".to_string(),
+        ]);
+    }
+    let graph: Graph = tracked.map(
+        |_, nodes| proto_info::composed_state_name(&proto_info, nodes),
+        |_, label| label.clone(),
+    );
+    let paths = shortest_paths(&graph, initial);
+    let witness = |node: NodeId| StateWitness {
+        state: graph[node].state_name().clone(),
+        path: to_transitions(&graph, &paths[&node]),
+    };
+
+    let mut no_terminal_reachable: Vec<NodeId> = proto_graph::nodes_not_reaching_terminal(&graph);
+    no_terminal_reachable.sort_by_key(|node| paths[node].len());
+
+    let closed_components = tarjan_scc(&graph)
+        .into_iter()
+        .filter(|component| {
+            let inside: BTreeSet<NodeId> = component.iter().cloned().collect();
+            let edges: Vec<_> = component
+                .iter()
+                .flat_map(|node| graph.edges_directed(*node, Outgoing))
+                .collect();
+            !edges.is_empty() && edges.iter().all(|edge| inside.contains(&edge.target()))
+        })
+        .map(|component| {
+            let entry = *component
+                .iter()
+                .min_by_key(|node| paths[node].len())
+                .unwrap();
+            let mut states: Vec<State> = component
+                .iter()
+                .map(|node| graph[*node].state_name().clone())
+                .collect();
+            states.sort();
+            ClosedComponent {
+                states,
+                path: to_transitions(&graph, &paths[&entry]),
+            }
+        })
+        .collect();
+
+    Ok(DeadlockReport {
+        no_terminal_reachable: no_terminal_reachable.into_iter().map(witness).collect(),
+        closed_components,
+        interface_deadlocks: interface_deadlocks(&proto_info, &tracked, &paths)
+            .into_iter()
+            .map(|(k, protocol_state, waiting_for, node)| InterfaceDeadlock {
+                k,
+                protocol_state,
+                waiting_for,
+                witness: witness(node),
+            })
+            .collect(),
+    })
+}
+
+// For each protocol and state of it, the first composed state (by length of the shortest
+// path reaching it) in which the protocol waits for event types that are never emitted.
+fn interface_deadlocks(
+    proto_info: &ProtoInfo,
+    tracked: &proto_info::TrackedGraph,
+    paths: &BTreeMap<NodeId, Vec<EdgeId>>,
+) -> Vec<(usize, State, Vec<EventType>, NodeId)> {
+    let mut nodes: Vec<NodeId> = tracked.node_indices().collect();
+    nodes.sort_by_key(|node| paths[node].len());
+
+    let (component_of, reachable) = reachable_event_types(tracked);
+    let mut seen: BTreeSet<(usize, NodeId)> = BTreeSet::new();
+    let mut deadlocks = vec![];
+    for node in nodes {
+        let emitted = &reachable[component_of[node.index()]];
+        for (k, local) in tracked[node].iter().enumerate() {
+            let protocol = &proto_info.protocols[k].graph;
+            let waiting_for: BTreeSet<EventType> = protocol
+                .edges_directed(*local, Outgoing)
+                .map(|edge| edge.weight().get_event_type())
+                .collect();
+            if waiting_for.is_empty()
+                || !waiting_for.is_disjoint(emitted)
+                || !seen.insert((k, *local))
+            {
+                continue;
+            }
+            deadlocks.push((
+                k,
+                protocol[*local].state_name().clone(),
+                waiting_for.into_iter().collect(),
+                node,
+            ));
+        }
+    }
+    deadlocks
+}
+
+// The strongly connected component of each node and the event types of the transitions
+// reachable from each component. tarjan_scc returns the components in reverse topological
+// order, so the components reachable from a component are done before it.
+fn reachable_event_types(
+    tracked: &proto_info::TrackedGraph,
+) -> (Vec<usize>, Vec<BTreeSet<EventType>>) {
+    let components = tarjan_scc(tracked);
+    let mut component_of = vec![0; tracked.node_count()];
+    let mut reachable: Vec<BTreeSet<EventType>> = Vec::with_capacity(components.len());
+    for (c, component) in components.iter().enumerate() {
+        for node in component {
+            component_of[node.index()] = c;
+        }
+        let mut event_types = BTreeSet::new();
+        for edge in component
+            .iter()
+            .flat_map(|node| tracked.edges_directed(*node, Outgoing))
+        {
+            event_types.insert(edge.weight().get_event_type());
+            let target = component_of[edge.target().index()];
+            if target != c {
+                event_types.extend(reachable[target].iter().cloned());
+            }
+        }
+        reachable.push(event_types);
+    }
+    (component_of, reachable)
+}
+
+// Shortest path from initial to each node, as the edges taken.
+fn shortest_paths(graph: &Graph, initial: NodeId) -> BTreeMap<NodeId, Vec<EdgeId>> {
+    let mut paths = BTreeMap::from([(initial, vec![])]);
+    let mut queue = VecDeque::from([initial]);
+    while let Some(node) = queue.pop_front() {
+        for edge in graph.edges_directed(node, Outgoing) {
+            if !paths.contains_key(&edge.target()) {
+                let mut path = paths[&node].clone();
+                path.push(edge.id());
+                paths.insert(edge.target(), path);
+                queue.push_back(edge.target());
+            }
+        }
+    }
+    paths
+}
+
+fn to_transitions(graph: &Graph, path: &[EdgeId]) -> Vec<Transition<SwarmLabel>> {
+    path.iter()
+        .map(|edge| {
+            let (source, target) = graph.edge_endpoints(*edge).unwrap();
+            Transition {
+                label: graph[*edge].clone(),
+                source: graph[source].state_name().clone(),
+                target: graph[target].state_name().clone(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::types::proto_info::DEFAULT_MAX_STATES;
+    use crate::types::typescript_types::{Command, Role, SwarmProtocolType};
+
+    #[test]
+    fn test_no_deadlocks() {
+        test_utils::setup_logger();
+        let report = deadlock_report(
+            InterfacingProtocols(vec![test_utils::get_proto1()]),
+            DEFAULT_MAX_STATES,
+        )
+        .unwrap();
+        assert_eq!(
+            report,
+            DeadlockReport {
+                no_terminal_reachable: vec![],
+                closed_components: vec![],
+                interface_deadlocks: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_state_space_limit() {
+        test_utils::setup_logger();
+        let errors = deadlock_report(test_utils::get_interfacing_swarms_1(), 2).unwrap_err();
+        assert_eq!(errors, vec!["composition exceeds the limit of 2 states"]);
+    }
+
+    #[test]
+    fn test_close_before_request() {
+        test_utils::setup_logger();
+        // the factory waits forever if the warehouse closes first
+        let report =
+            deadlock_report(test_utils::get_interfacing_swarms_1(), DEFAULT_MAX_STATES).unwrap();
+        assert_eq!(report.no_terminal_reachable, vec![]);
+        assert_eq!(
+            report.interface_deadlocks,
+            vec![InterfaceDeadlock {
+                k: 1,
+                protocol_state: State::new("0"),
+                waiting_for: vec![EventType::new("partID")],
+                witness: StateWitness {
+                    state: State::new("3 || 0"),
+                    path: vec![Transition {
+                        label: SwarmLabel {
+                            cmd: Command::new("close"),
+                            log_type: vec![EventType::new("time")],
+                            role: Role::new("D"),
+                            guard: None,
+                            timeout: None,
+                        },
+                        source: State::new("0 || 0"),
+                        target: State::new("3 || 0"),
+                    }],
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_closed_loop() {
+        test_utils::setup_logger();
+        let proto = serde_json::from_str::<SwarmProtocolType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "source": "0", "target": "1", "label": { "cmd": "a", "logType": ["a"], "role": "R1" } },
+                    { "source": "1", "target": "2", "label": { "cmd": "b", "logType": ["b"], "role": "R2" } },
+                    { "source": "2", "target": "1", "label": { "cmd": "c", "logType": ["c"], "role": "R1" } },
+                    { "source": "0", "target": "3", "label": { "cmd": "d", "logType": ["d"], "role": "R2" } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let report =
+            deadlock_report(InterfacingProtocols(vec![proto]), DEFAULT_MAX_STATES).unwrap();
+        assert_eq!(
+            report
+                .no_terminal_reachable
+                .iter()
+                .map(|w| (w.state.clone(), w.path.len()))
+                .collect::<Vec<_>>(),
+            vec![(State::new("1"), 1), (State::new("2"), 2)]
+        );
+        assert_eq!(report.closed_components.len(), 1);
+        assert_eq!(
+            report.closed_components[0].states,
+            vec![State::new("1"), State::new("2")]
+        );
+        assert_eq!(report.closed_components[0].path[0].target, State::new("1"));
+        assert_eq!(report.interface_deadlocks, vec![]);
+    }
+
+    #[test]
+    fn test_interface_deadlock() {
+        test_utils::setup_logger();
+        // the second protocol expects partID after part, which the first never emits
+        let proto = serde_json::from_str::<SwarmProtocolType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "source": "0", "target": "1", "label": { "cmd": "deliver", "logType": ["part"], "role": "T" } },
+                    { "source": "1", "target": "2", "label": { "cmd": "request", "logType": ["partID"], "role": "T" } },
+                    { "source": "2", "target": "3", "label": { "cmd": "build", "logType": ["car"], "role": "F" } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let protos = InterfacingProtocols(vec![test_utils::get_proto1(), proto]);
+        let report = deadlock_report(protos, DEFAULT_MAX_STATES).unwrap();
+        assert_eq!(report.no_terminal_reachable, vec![]);
+        let deadlock = &report.interface_deadlocks[0];
+        assert_eq!(deadlock.k, 1);
+        assert_eq!(deadlock.protocol_state, State::new("0"));
+        assert_eq!(deadlock.waiting_for, vec![EventType::new("part")]);
+        assert_eq!(deadlock.witness.state, State::new("0 || 0"));
+        assert_eq!(deadlock.witness.path, vec![]);
+    }
+}
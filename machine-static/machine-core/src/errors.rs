@@ -1,8 +1,11 @@
 use crate::types::{
     proto_graph::{EdgeId, Graph, NodeId},
-    typescript_types::{Command, EventType, Role, StateName, SwarmLabel},
+    typescript_types::{
+        Command, EventType, MachineLabel, PayloadSchema, Role, State, StateName, SwarmLabel,
+    },
 };
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 use std::fmt;
 
@@ -24,7 +27,7 @@ impl<'a, N: StateName> fmt::Display for Edge<'a, N> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Error {
     ActiveRoleNotSubscribed(EdgeId),
     LaterActiveRoleNotSubscribed(EdgeId, Role),
@@ -50,6 +53,13 @@ pub enum Error {
     StateUnreachable(NodeId),
     LogTypeEmpty(EdgeId),
     InvalidArg, // weird error. not related to shape of protocol, but ok.
+    StateSpaceLimitExceeded(usize),
+    DataGuardNotSubscribed(EdgeId, EventType),
+    DataGuardOnDifferentLabels(EventType),
+    PayloadSchemaOnDifferentLabels(EventType, PayloadSchema, PayloadSchema),
+    TimeoutOnMultipleTransitions(NodeId),
+    TimeoutNotSubscribed(EdgeId, EventType),
+    TimeoutOnDifferentLabels(EventType),
 }
 
 impl Error {
@@ -170,6 +180,38 @@ impl Error {
             Error::InvalidArg => {
                 format!("invalid argument",)
             }
+            Error::StateSpaceLimitExceeded(max_states) => {
+                format!("composition exceeds the limit of {max_states} states")
+            }
+            Error::DataGuardNotSubscribed(edge, event_type) => {
+                format!(
+                    "active role does not subscribe to event type {event_type} read by the guard in transition {}",
+                    Edge(graph, *edge)
+                )
+            }
+            Error::DataGuardOnDifferentLabels(event_type) => {
+                format!("Event type {event_type} appears with different guards")
+            }
+            Error::PayloadSchemaOnDifferentLabels(event_type, schema1, schema2) => {
+                format!(
+                    "Event type {event_type} appears with payload schema {schema1} and with payload schema {schema2}"
+                )
+            }
+            Error::TimeoutOnMultipleTransitions(node) => {
+                format!(
+                    "state {} has more than one timeout transition",
+                    &graph[*node].state_name()
+                )
+            }
+            Error::TimeoutNotSubscribed(edge, event_type) => {
+                format!(
+                    "active role does not subscribe to event type {event_type} competing with the timeout in transition {}",
+                    Edge(graph, *edge)
+                )
+            }
+            Error::TimeoutOnDifferentLabels(event_type) => {
+                format!("Event type {event_type} appears with different timeouts")
+            }
         }
     }
 
@@ -180,6 +222,40 @@ impl Error {
     }
 }
 
+// Problems with the shape of a machine given as input
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MachineError {
+    NonDeterministicInput(State, EventType),
+    NonDeterministicCommand(State, Command),
+    DuplicateTransition(State, MachineLabel, State),
+    StateUnreachable(State),
+    EventTypeNotSubscribed(EventType, Role),
+}
+
+impl fmt::Display for MachineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MachineError::NonDeterministicInput(state, event_type) => write!(
+                f,
+                "non-deterministic reaction to event type {event_type} in state {state}"
+            ),
+            MachineError::NonDeterministicCommand(state, command) => {
+                write!(f, "non-deterministic command {command} in state {state}")
+            }
+            MachineError::DuplicateTransition(source, label, target) => {
+                write!(f, "duplicate transition ({source})--[{label}]-->({target})")
+            }
+            MachineError::StateUnreachable(state) => {
+                write!(f, "machine state {state} is unreachable from initial state")
+            }
+            MachineError::EventTypeNotSubscribed(event_type, role) => write!(
+                f,
+                "machine reacts to event type {event_type} but role {role} does not subscribe to it"
+            ),
+        }
+    }
+}
+
 // Container for errors accumulated while processing protocols
 #[derive(Debug)]
 pub struct ErrorReport(pub Vec<(Graph, Vec<Error>)>);
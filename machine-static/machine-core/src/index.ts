@@ -1,12 +1,22 @@
 import {
   ProtocolType, SwarmLabel, MachineLabel, MachineType, SwarmProtocolType, SubscriptionsWrapped as Subscriptions,
   Role, EventType, DataResult, Granularity, InterfacingProtocols, exact_well_formed_sub, overapproximated_well_formed_sub,
-  projection_information, project as wasm_project, compose_protocols, ProjectionInfo, BranchMap, SpecialEventTypes, ProjToMachineStates
+  projection_information, project as wasm_project, compose_protocols, ProjectionInfo, BranchMap, SpecialEventTypes, ProjToMachineStates,
+  project_with_provenance, ProjectionWithProvenance, ProjToProtoStates,
+  projection_information_batch, RoleMachine, RoleMachines, RoleProjectionInfo,
+  projection_information_machines, ProtocolMachine, ProtocolMachines,
+  generate_machine_runner, cache_analysis, exact_well_formed_sub_cached, overapproximated_well_formed_sub_cached,
+  project_cached, projection_information_cached, analyze_protocols, ProtocolAnalysis,
+  validate_payload, PayloadSchema, lazy_well_formed_sub, missing_subscriptions, is_valid_analysis,
+  ProtocolSession, SwarmTransition
 } from '../pkg/machine_core.js'
 export {
-  ProtocolType, SwarmLabel, MachineLabel, SwarmProtocolType, MachineType, Subscriptions, Role, EventType,
+  ProtocolType, SwarmLabel, MachineLabel, SwarmProtocolType, MachineType, Subscriptions, Role, EventType, PayloadSchema, SwarmTransition,
   InterfacingProtocols, Granularity, DataResult,
-  ProjectionInfo, BranchMap, SpecialEventTypes, ProjToMachineStates
+  ProjectionInfo, BranchMap, SpecialEventTypes, ProjToMachineStates,
+  ProjectionWithProvenance, ProjToProtoStates,
+  RoleMachine, RoleMachines, RoleProjectionInfo,
+  ProtocolMachine, ProtocolMachines, ProtocolAnalysis
 }
 
 /**
@@ -15,10 +25,37 @@ export {
  *
  * @param protos - An array of swarm protocols representing a composition.
  * @param subscriptions - A subscription.
+ * @param maxStates - Optional limit on the number of states of the expanded composition, an error is returned if it is exceeded.
  * @returns - Result containing the computed subscription or a list of error messages.
  */
-export function exactWFSubscriptions(protos: InterfacingProtocols, subscriptions: Subscriptions): DataResult<Subscriptions> {
-  return exact_well_formed_sub(protos, subscriptions);
+export function exactWFSubscriptions(protos: InterfacingProtocols, subscriptions: Subscriptions, maxStates?: number): DataResult<Subscriptions> {
+  return exact_well_formed_sub(protos, subscriptions, maxStates);
+}
+
+/**
+ * Like ```exactWFSubscriptions```, but exploring the composition state by state
+ * instead of building it first.
+ *
+ * @param protos - An array of swarm protocols representing a composition.
+ * @param subscriptions - A subscription.
+ * @param maxStates - Optional limit on the number of states of the explored composition, an error is returned if it is exceeded.
+ * @returns - Result containing the computed subscription or a list of error messages.
+ */
+export function lazyWFSubscriptions(protos: InterfacingProtocols, subscriptions: Subscriptions, maxStates?: number): DataResult<Subscriptions> {
+  return lazy_well_formed_sub(protos, subscriptions, maxStates);
+}
+
+/**
+ * Check that a subscription is well-formed w.r.t. a swarm protocol composition
+ * without building the composition.
+ *
+ * @param protos - An array of swarm protocols representing a composition.
+ * @param subscriptions - A subscription.
+ * @param maxStates - Optional limit on the number of states of the explored composition, an error is returned if it is exceeded.
+ * @returns - Result containing the event types each role has to subscribe to in addition, empty if the subscription is well-formed, or a list of error messages.
+ */
+export function missingWFSubscriptions(protos: InterfacingProtocols, subscriptions: Subscriptions, maxStates?: number): DataResult<Subscriptions> {
+  return missing_subscriptions(protos, subscriptions, maxStates);
 }
 
 /**
@@ -54,6 +91,40 @@ export function projectionInformation(role: Role, protos: InterfacingProtocols,
   return projection_information(role, protos, k, subscriptions, machine, minimize);
 }
 
+/**
+ * Like ```projectionInformation```, but for a role that is implemented by one machine for each of several
+ * protocols in the composition. Each machine is given together with the index of the protocol in ```protos```
+ * for which it was implemented. The role is given by its projection in the remaining protocols.
+ * The states of the original machines are listed in ```projToMachineStates``` in the order of the protocols,
+ * with ```null``` for the protocols that have no machine.
+ *
+ * @param role - The role
+ * @param protos - An array of swarm protocols representing a composition.
+ * @param subscriptions - A subscription.
+ * @param machines - An array of (unadapted) original machines, each with a protocol index.
+ * @param minimize - The projection is minimized if ```minimize``` is true and returned as is otherwise.
+ * @returns Result containing the projection information or a list of error messages.
+ */
+export function projectionInformationMachines(role: Role, protos: InterfacingProtocols, subscriptions: Subscriptions, machines: ProtocolMachines, minimize: boolean): DataResult<ProjectionInfo> {
+  return projection_information_machines(role, protos, subscriptions, machines, minimize);
+}
+
+/**
+ * Returns the projection information (see ```projectionInformation```) for a number of machines at once.
+ * Each machine is given together with the role it implements and the index of the protocol in ```protos```
+ * for which it was implemented. The analysis of the composition is shared between all adaptations.
+ * Fails if some role was given machines for several protocols whose adaptations do not behave the same.
+ *
+ * @param protos - An array of swarm protocols representing a composition.
+ * @param subscriptions - A subscription.
+ * @param machines - An array of (unadapted) original machines, each with a role and a protocol index.
+ * @param minimize - The projections are minimized if ```minimize``` is true and returned as is otherwise.
+ * @returns Result containing the projection information for each machine or a list of error messages.
+ */
+export function projectionInformationBatch(protos: InterfacingProtocols, subscriptions: Subscriptions, machines: RoleMachines, minimize: boolean): DataResult<RoleProjectionInfo[]> {
+  return projection_information_batch(protos, subscriptions, machines, minimize);
+}
+
 /**
  * Compute the projection of a composed swarm protocol over a role w.r.t. a subscription.
  * Either computes the projection of each swarm protocol in the composition over the role and
@@ -64,18 +135,242 @@ export function projectionInformation(role: Role, protos: InterfacingProtocols,
  * @param role - A role (given as a string).
  * @param minimize - The projection is minimized if ```minimize``` is true and returned as is otherwise.
  * @param expandProtos - Composition of protocols in ```protos``` is expanded before projection if true, otherwise projection of each swarm protocol is computed and then composed.
+ * @param maxStates - Optional limit on the number of states of the expanded composition if ```expandProtos``` is true, an error is returned if it is exceeded.
  * @returns - Result containing the projection or a list of error messages.
  */
-export function project(protos: InterfacingProtocols, subscriptions: Subscriptions, role: string, minimize: boolean, expandProtos: boolean): DataResult<MachineType> {
-  return wasm_project(protos, subscriptions, role, minimize, expandProtos)
+export function project(protos: InterfacingProtocols, subscriptions: Subscriptions, role: string, minimize: boolean, expandProtos: boolean, maxStates?: number): DataResult<MachineType> {
+  return wasm_project(protos, subscriptions, role, minimize, expandProtos, maxStates)
+}
+
+/**
+ * Compute the projection of a composed swarm protocol over a role w.r.t. a subscription
+ * together with the swarm protocol states represented by each state of the projection.
+ * The protocol states are given per swarm protocol in ```protos```, or, if ```expandProtos``` is true,
+ * as states of the expanded composition.
+ *
+ * @param protos - An array of swarm protocols representing a composition.
+ * @param subscriptions - A subscription.
+ * @param role - A role (given as a string).
+ * @param minimize - The projection is minimized if ```minimize``` is true and returned as is otherwise.
+ * @param expandProtos - Composition of protocols in ```protos``` is expanded before projection if true, otherwise projection of each swarm protocol is computed and then composed.
+ * @param maxStates - Optional limit on the number of states of the expanded composition if ```expandProtos``` is true, an error is returned if it is exceeded.
+ * @returns - Result containing the projection and the protocol states of each projection state or a list of error messages.
+ */
+export function projectWithProvenance(protos: InterfacingProtocols, subscriptions: Subscriptions, role: string, minimize: boolean, expandProtos: boolean, maxStates?: number): DataResult<ProjectionWithProvenance> {
+  return project_with_provenance(protos, subscriptions, role, minimize, expandProtos, maxStates)
 }
 
 /**
  * Construct the composition of a number of swarm protocols.
  *
  * @param protos - An array of swarm protocols representing a composition.
+ * @param maxStates - Optional limit on the number of states of the composition, an error is returned if it is exceeded.
  * @returns - Result containing the expanded composition or a list of error messages.
  */
-export function composeProtocols(protos: InterfacingProtocols): DataResult<SwarmProtocolType> {
-  return compose_protocols(protos)
-}
\ No newline at end of file
+export function composeProtocols(protos: InterfacingProtocols, maxStates?: number): DataResult<SwarmProtocolType> {
+  return compose_protocols(protos, maxStates)
+}
+
+/**
+ * Compute the analysis of a swarm protocol composition used by the other functions:
+ * the pairs of concurrent event types, the sets of branching event types, the joining event types
+ * with the event types immediately preceding them, the event types succeeding each event type,
+ * the interfacing event types and the event types that do not lead to a terminal state.
+ *
+ * @param protos - An array of swarm protocols representing a composition.
+ * @returns - Result containing the analysis or a list of error messages.
+ */
+export function analyzeProtocols(protos: InterfacingProtocols): DataResult<ProtocolAnalysis> {
+  return analyze_protocols(protos)
+}
+
+/**
+ * Generate a machine-runner TypeScript skeleton for a role from its projection of a swarm protocol composition.
+ * The generated code contains the event designs, the ```SwarmProtocol.make``` call, the subscriptions and
+ * the projection information, the adapted machine made from them with ```makeAdaptedMachine``` for
+ * ```createMachineRunnerBT```, a state design with command stubs for each state of the projection
+ * and a reaction for each input transition.
+ *
+ * @param swarmName - The name of the swarm protocol.
+ * @param protos - An array of swarm protocols representing a composition.
+ * @param subscriptions - A well-formed subscription.
+ * @param role - A role (given as a string).
+ * @param minimize - The projection is minimized if ```minimize``` is true and used as is otherwise.
+ * @returns - Result containing the generated TypeScript or a list of error messages.
+ */
+export function generateMachineRunner(swarmName: string, protos: InterfacingProtocols, subscriptions: Subscriptions, role: string, minimize: boolean): DataResult<string> {
+  return generate_machine_runner(swarmName, protos, subscriptions, role, minimize)
+}
+
+/**
+ * Analyze a swarm protocol composition once, so that the analysis can be reused by the functions
+ * ending in ```Cached``` instead of analyzing the protocols on every call.
+ * The analysis contains a content hash of ```protos``` and can be stored together with them to be reused across runs.
+ * The functions ending in ```Cached``` do not check that an analysis belongs to the intended protocols,
+ * use ```isValidAnalysis``` before reusing a stored analysis.
+ *
+ * @param protos - An array of swarm protocols representing a composition.
+ * @returns - Result containing the analysis as a JSON string.
+ */
+export function cacheAnalysis(protos: InterfacingProtocols): DataResult<string> {
+  return cache_analysis(protos)
+}
+
+/**
+ * Check that an analysis returned by ```cacheAnalysis``` was computed from ```protos```
+ * by this version of the library, i.e. that it can be used in place of ```protos```.
+ *
+ * @param analysis - The analysis of a swarm protocol composition returned by ```cacheAnalysis```.
+ * @param protos - An array of swarm protocols representing a composition.
+ * @returns - Result containing true if the analysis is valid for ```protos``` and false if it has to be recomputed, or a list of error messages if it can not be read.
+ */
+export function isValidAnalysis(analysis: string, protos: InterfacingProtocols): DataResult<boolean> {
+  return is_valid_analysis(analysis, protos)
+}
+
+/**
+ * Same as ```exactWFSubscriptions```, using an analysis returned by ```cacheAnalysis```.
+ *
+ * @param analysis - The analysis of a swarm protocol composition returned by ```cacheAnalysis```, not checked against the protocols (see ```isValidAnalysis```).
+ * @param subscriptions - A subscription.
+ * @param maxStates - Optional limit on the number of states of the expanded composition, an error is returned if it is exceeded.
+ * @returns - Result containing the computed subscription or a list of error messages.
+ */
+export function exactWFSubscriptionsCached(analysis: string, subscriptions: Subscriptions, maxStates?: number): DataResult<Subscriptions> {
+  return exact_well_formed_sub_cached(analysis, subscriptions, maxStates)
+}
+
+/**
+ * Same as ```overapproxWFSubscriptions```, using an analysis returned by ```cacheAnalysis```.
+ *
+ * @param analysis - The analysis of a swarm protocol composition returned by ```cacheAnalysis```, not checked against the protocols (see ```isValidAnalysis```).
+ * @param subscriptions - A subscription.
+ * @param granularity - The precision of the approximation.
+ * @returns - Result containing the computed subscription or a list of error messages.
+ */
+export function overapproxWFSubscriptionsCached(analysis: string, subscriptions: Subscriptions, granularity: Granularity): DataResult<Subscriptions> {
+  return overapproximated_well_formed_sub_cached(analysis, subscriptions, granularity)
+}
+
+/**
+ * Same as ```project``` without expanding the composition, using an analysis returned by ```cacheAnalysis```.
+ *
+ * @param analysis - The analysis of a swarm protocol composition returned by ```cacheAnalysis```, not checked against the protocols (see ```isValidAnalysis```).
+ * @param subscriptions - A subscription.
+ * @param role - A role (given as a string).
+ * @param minimize - The projection is minimized if ```minimize``` is true and returned as is otherwise.
+ * @returns - Result containing the projection or a list of error messages.
+ */
+export function projectCached(analysis: string, subscriptions: Subscriptions, role: string, minimize: boolean): DataResult<MachineType> {
+  return project_cached(analysis, subscriptions, role, minimize)
+}
+
+/**
+ * Same as ```projectionInformation```, using an analysis returned by ```cacheAnalysis```.
+ *
+ * @param role - The role
+ * @param analysis - The analysis of a swarm protocol composition returned by ```cacheAnalysis```, not checked against the protocols (see ```isValidAnalysis```).
+ * @param k - The index of the protocol in the analyzed composition for which ```machine``` was implemented.
+ * @param subscriptions - A subscription.
+ * @param machine - The (unadapted) original machine.
+ * @param minimize - The projection is minimized if ```minimize``` is true and returned as is otherwise.
+ * @returns Result containing the projection information or a list of error messages.
+ */
+export function projectionInformationCached(role: Role, analysis: string, k: number, subscriptions: Subscriptions, machine: MachineType, minimize: boolean): DataResult<ProjectionInfo> {
+  return projection_information_cached(role, analysis, k, subscriptions, machine, minimize)
+}
+
+/**
+ * Check an event payload against a payload schema, e.g. one of the ```payloads``` of a swarm protocol.
+ *
+ * @param schema - The payload schema.
+ * @param payload - The payload, it is passed to the check as JSON.
+ * @returns - Result containing the paths of the parts of the payload that do not match the schema (empty if the payload is valid) or a list of error messages.
+ */
+export function validatePayload(schema: PayloadSchema, payload: unknown): DataResult<string[]> {
+  return validate_payload(schema, JSON.stringify(payload))
+}
+
+/**
+ * An analysis session over a swarm protocol composition that is edited over time, e.g. in an editor.
+ * The analysis of the protocols is kept between edits and only the edited protocol is analyzed again,
+ * results are the same as those of the corresponding functions on the current protocols.
+ * Call ```free``` once the session is no longer needed.
+ */
+export class Session {
+  private session: ProtocolSession
+
+  /**
+   * @param protos - An array of swarm protocols representing a composition.
+   * @param maxStates - Optional limit on the number of states of the expanded composition used by ```exactWFSubscriptions```, an error is returned if it is exceeded.
+   */
+  constructor(protos: InterfacingProtocols, maxStates?: number) {
+    this.session = new ProtocolSession(protos, maxStates)
+  }
+
+  /**
+   * @returns - The current protocols.
+   */
+  protocols(): InterfacingProtocols {
+    return this.session.protocols()
+  }
+
+  /**
+   * Add a protocol to the composition.
+   *
+   * @param proto - A swarm protocol.
+   */
+  addProtocol(proto: SwarmProtocolType): void {
+    this.session.add_protocol(proto)
+  }
+
+  /**
+   * Add a transition to a protocol of the composition.
+   *
+   * @param k - The index of the protocol.
+   * @param transition - The transition to add.
+   * @returns - Result containing the protocols after the edit or a list of error messages.
+   */
+  addTransition(k: number, transition: SwarmTransition): DataResult<InterfacingProtocols> {
+    return this.session.add_transition(k, transition)
+  }
+
+  /**
+   * Remove a transition from a protocol of the composition.
+   *
+   * @param k - The index of the protocol.
+   * @param transition - The transition to remove.
+   * @returns - Result containing the protocols after the edit or a list of error messages.
+   */
+  removeTransition(k: number, transition: SwarmTransition): DataResult<InterfacingProtocols> {
+    return this.session.remove_transition(k, transition)
+  }
+
+  /**
+   * Same as ```exactWFSubscriptions``` on the current protocols.
+   *
+   * @param subscriptions - A subscription.
+   * @returns - Result containing the computed subscription or a list of error messages.
+   */
+  exactWFSubscriptions(subscriptions: Subscriptions): DataResult<Subscriptions> {
+    return this.session.exact_well_formed_sub(subscriptions)
+  }
+
+  /**
+   * Same as ```overapproxWFSubscriptions``` on the current protocols.
+   *
+   * @param subscriptions - A subscription.
+   * @param granularity - The precision of the approximation.
+   * @returns - Result containing the computed subscription or a list of error messages.
+   */
+  overapproxWFSubscriptions(subscriptions: Subscriptions, granularity: Granularity): DataResult<Subscriptions> {
+    return this.session.overapproximated_well_formed_sub(subscriptions, granularity)
+  }
+
+  /**
+   * Release the memory held by the session.
+   */
+  free(): void {
+    this.session.free()
+  }
+}
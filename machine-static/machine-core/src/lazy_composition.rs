@@ -0,0 +1,720 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+
+use petgraph::{
+    Direction::{self, Incoming, Outgoing},
+    algo::tarjan_scc,
+    visit::{
+        Data, Dfs, EdgeRef, GraphBase, IntoEdgeReferences, IntoEdges, IntoEdgesDirected,
+        IntoNeighbors, IntoNeighborsDirected, IntoNodeIdentifiers, Visitable, Walker,
+    },
+};
+
+use crate::errors::ErrorReport;
+use crate::machine::projection;
+use crate::types::{
+    projection::Graph as MachineGraph,
+    proto_graph,
+    proto_graph::{EdgeId, Graph, NodeId},
+    proto_info::{self, ProtoInfo},
+    proto_label::ProtoLabel,
+    typescript_types::{EventLabel, EventType, Role, State, Subscriptions, SwarmLabel},
+    unordered_event_pair::UnordEventPair,
+};
+
+// A state of the composition, numbered in the order in which states are discovered.
+pub type ComposedNode = usize;
+
+// A transition of the composition. The label is borrowed from the protocol taking it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComposedEdge<'a> {
+    source: ComposedNode,
+    target: ComposedNode,
+    index: usize,
+    label: &'a SwarmLabel,
+}
+
+impl<'a> EdgeRef for ComposedEdge<'a> {
+    type NodeId = ComposedNode;
+    type EdgeId = (ComposedNode, usize);
+    type Weight = SwarmLabel;
+
+    fn source(&self) -> ComposedNode {
+        self.source
+    }
+
+    fn target(&self) -> ComposedNode {
+        self.target
+    }
+
+    fn weight(&self) -> &SwarmLabel {
+        self.label
+    }
+
+    fn id(&self) -> (ComposedNode, usize) {
+        (self.source, self.index)
+    }
+}
+
+// The composition of the protocols of a proto_info, with successors generated on demand
+// instead of building the product. Composes like explicit_composition_bounded: protocol k
+// synchronizes with the protocols before it on the event types of their shared roles.
+// Supports reachability, deadlock search, projection and, through the petgraph traits for
+// incoming edges, subscription inference and well-formedness checks (see exact::lazy_well_formed_sub).
+// Discovers at most max_states states, transitions to further states are dropped and
+// check_limit fails once this happened.
+pub struct LazyComposition<'a> {
+    proto_info: &'a ProtoInfo,
+    max_states: usize,
+    interfaces: Vec<BTreeSet<EventType>>,
+    // for each protocol the edges not on a cycle, if reducing.
+    acyclic_edges: Option<Vec<BTreeSet<EdgeId>>>,
+    nodes: RefCell<Nodes>,
+}
+
+#[derive(Default)]
+struct Nodes {
+    index: BTreeMap<Vec<NodeId>, ComposedNode>,
+    states: Vec<Vec<NodeId>>,
+    limit_exceeded: bool,
+}
+
+// A transition of the composition as the label and the edge taken by each protocol moving.
+type Move<'a> = (&'a SwarmLabel, Vec<(usize, EdgeId)>);
+
+impl<'a> LazyComposition<'a> {
+    // None if proto_info has no protocols or a protocol without an initial state.
+    pub fn new(proto_info: &'a ProtoInfo, max_states: usize) -> Option<Self> {
+        let initial = proto_info
+            .protocols
+            .iter()
+            .map(|p| p.initial)
+            .collect::<Option<Vec<NodeId>>>()?;
+        if initial.is_empty() || max_states == 0 {
+            return None;
+        }
+        let mut roles = BTreeSet::new();
+        let interfaces = proto_info
+            .protocols
+            .iter()
+            .map(|p| {
+                let protocol_roles = p.graph.get_roles();
+                let interface = proto_info::interface(proto_info, &roles, &protocol_roles);
+                roles.extend(protocol_roles);
+                interface
+            })
+            .collect();
+        let view = Self {
+            proto_info,
+            max_states,
+            interfaces,
+            acyclic_edges: None,
+            nodes: RefCell::new(Nodes::default()),
+        };
+        view.node(initial);
+        Some(view)
+    }
+
+    // Explore only an ample set of the transitions of each state: the transitions of a single
+    // protocol that can not synchronize in its current state, that are concurrent with all
+    // other enabled transitions and that do not close a cycle of the protocol.
+    // All deadlocks (states without successors) of the composition remain reachable,
+    // but other states may not be.
+    pub fn with_reduction(mut self) -> Self {
+        let acyclic_edges = self
+            .proto_info
+            .protocols
+            .iter()
+            .map(|p| {
+                let mut component = vec![0; p.graph.node_count()];
+                for (i, scc) in tarjan_scc(&p.graph).into_iter().enumerate() {
+                    for node in scc {
+                        component[node.index()] = i;
+                    }
+                }
+                p.graph
+                    .edge_references()
+                    .filter(|e| component[e.source().index()] != component[e.target().index()])
+                    .map(|e| e.id())
+                    .collect()
+            })
+            .collect();
+        self.acyclic_edges = Some(acyclic_edges);
+        self
+    }
+
+    // The initial state is the first discovered.
+    pub fn initial(&self) -> ComposedNode {
+        0
+    }
+
+    // The state of each protocol in node.
+    pub fn protocol_states(&self, node: ComposedNode) -> Vec<NodeId> {
+        self.nodes.borrow().states[node].clone()
+    }
+
+    pub fn state_name(&self, node: ComposedNode) -> State {
+        proto_info::composed_state_name(self.proto_info, &self.nodes.borrow().states[node])
+    }
+
+    // The number of states discovered so far.
+    pub fn discovered(&self) -> usize {
+        self.nodes.borrow().states.len()
+    }
+
+    // Fails with Error::StateSpaceLimitExceeded if a state was not discovered because of max_states.
+    pub fn check_limit(&self) -> Result<(), ErrorReport> {
+        if self.nodes.borrow().limit_exceeded {
+            Err(proto_info::state_space_limit_exceeded(self.max_states))
+        } else {
+            Ok(())
+        }
+    }
+
+    // The transitions out of node, restricted to an ample set if reducing.
+    pub fn successors(&self, node: ComposedNode) -> Vec<ComposedEdge<'a>> {
+        let state = self.protocol_states(node);
+        let mut moves = self.moves(&state, Outgoing);
+        if let Some(ample) = self.ample(&state, &moves) {
+            moves = ample;
+        }
+        self.to_edges(node, &state, moves)
+    }
+
+    // All transitions out of node, also if reducing.
+    pub fn all_successors(&self, node: ComposedNode) -> Vec<ComposedEdge<'a>> {
+        let state = self.protocol_states(node);
+        let moves = self.moves(&state, Outgoing);
+        self.to_edges(node, &state, moves)
+    }
+
+    // The transitions into node from discovered states, i.e. all of them once the
+    // composition has been explored. Restricted to ample sets if reducing.
+    pub fn predecessors(&self, node: ComposedNode) -> Vec<ComposedEdge<'a>> {
+        let state = self.protocol_states(node);
+        let sources: BTreeSet<ComposedNode> = self
+            .moves(&state, Incoming)
+            .into_iter()
+            .filter_map(|(_, moved)| {
+                let mut source = state.clone();
+                for (k, edge) in moved {
+                    source[k] = self.proto_info.protocols[k]
+                        .graph
+                        .edge_endpoints(edge)
+                        .unwrap()
+                        .0;
+                }
+                self.nodes.borrow().index.get(&source).copied()
+            })
+            .collect();
+        sources
+            .into_iter()
+            .flat_map(|source| self.successors(source))
+            .filter(|edge| edge.target == node)
+            .collect()
+    }
+
+    // The states reachable from the initial state.
+    pub fn reachable(&self) -> Vec<ComposedNode> {
+        Dfs::new(self, self.initial()).iter(self).collect()
+    }
+
+    // Projection of the composition over role, see projection::project_composition.
+    // Fails with Error::StateSpaceLimitExceeded if the projection needs more than max_states states.
+    pub fn project(
+        &self,
+        subs: &Subscriptions,
+        role: Role,
+        minimize: bool,
+    ) -> Result<(MachineGraph, NodeId), ErrorReport> {
+        let projection = projection::project_composition(self, subs, role, minimize);
+        self.check_limit()?;
+        Ok(projection)
+    }
+
+    // proto_info with the succeeding and infinitely looping event types of the composition,
+    // like explicit_composition_proto_info_bounded but keeping the protocols. Explores the
+    // whole composition, which must not be reduced since the reduction drops transitions.
+    pub(crate) fn composition_proto_info(&self) -> Result<ProtoInfo, ErrorReport> {
+        self.reachable();
+        self.check_limit()?;
+        let succeeding_events = proto_info::after_not_concurrent(
+            self,
+            self.initial(),
+            &self.proto_info.concurrent_events,
+        );
+        let infinitely_looping_events =
+            proto_graph::infinitely_looping_event_types(self, &succeeding_events);
+        Ok(ProtoInfo {
+            succeeding_events,
+            infinitely_looping_events,
+            ..self.proto_info.clone()
+        })
+    }
+
+    // Build the explored part of the composition.
+    pub fn to_graph(&self) -> (Graph, NodeId) {
+        let mut graph = Graph::new();
+        let mut nodes: BTreeMap<ComposedNode, NodeId> = BTreeMap::new();
+        let mut add_node = |graph: &mut Graph, node: ComposedNode| {
+            *nodes
+                .entry(node)
+                .or_insert_with(|| graph.add_node(self.state_name(node)))
+        };
+        let initial = add_node(&mut graph, self.initial());
+        for edge in self.edge_references() {
+            let source = add_node(&mut graph, edge.source());
+            let target = add_node(&mut graph, edge.target());
+            graph.add_edge(source, target, edge.weight().clone());
+        }
+        (graph, initial)
+    }
+
+    // None if state is new and max_states states have been discovered.
+    fn node(&self, state: Vec<NodeId>) -> Option<ComposedNode> {
+        let mut nodes = self.nodes.borrow_mut();
+        if let Some(node) = nodes.index.get(&state) {
+            return Some(*node);
+        }
+        if nodes.states.len() == self.max_states {
+            nodes.limit_exceeded = true;
+            return None;
+        }
+        let node = nodes.states.len();
+        nodes.index.insert(state.clone(), node);
+        nodes.states.push(state);
+        Some(node)
+    }
+
+    // The transitions of the composition leaving (Outgoing) or entering (Incoming) state.
+    fn moves(&self, state: &[NodeId], direction: Direction) -> Vec<Move<'a>> {
+        let outgoing = |k: usize| -> Vec<Move<'a>> {
+            self.proto_info.protocols[k]
+                .graph
+                .edges_directed(state[k], direction)
+                .map(|edge| (edge.weight(), vec![(k, edge.id())]))
+                .collect()
+        };
+        let mut moves = outgoing(0);
+        for k in 1..state.len() {
+            let interface = &self.interfaces[k];
+            let is_interfacing =
+                |(label, _): &Move<'a>| interface.contains(&label.get_event_type());
+            let (synchronizing, mut next): (Vec<_>, Vec<_>) =
+                moves.into_iter().partition(is_interfacing);
+            let (synchronizing_k, local_k): (Vec<_>, Vec<_>) =
+                outgoing(k).into_iter().partition(is_interfacing);
+            next.extend(local_k);
+            for (label, moved) in synchronizing {
+                let mut matching: Vec<_> = synchronizing_k
+                    .iter()
+                    .filter(|(l, _)| *l == label)
+                    .collect();
+                // several edges with the same label may enter a state, but only one leaves it.
+                if direction == Outgoing {
+                    matching.truncate(1);
+                }
+                for (_, moved_k) in matching {
+                    let mut moved = moved.clone();
+                    moved.extend(moved_k.iter().cloned());
+                    next.push((label, moved));
+                }
+            }
+            moves = next;
+        }
+        moves
+    }
+
+    fn ample(&self, state: &[NodeId], moves: &[Move<'a>]) -> Option<Vec<Move<'a>>> {
+        let acyclic_edges = self.acyclic_edges.as_ref()?;
+        (0..state.len())
+            .filter_map(|k| {
+                let (local, others): (Vec<_>, Vec<_>) = moves
+                    .iter()
+                    .cloned()
+                    .partition(|(_, moved)| moved.len() == 1 && moved[0].0 == k);
+                let outgoing = self.proto_info.protocols[k]
+                    .graph
+                    .edges_directed(state[k], Outgoing)
+                    .count();
+                let independent = local.iter().all(|(label, moved)| {
+                    acyclic_edges[k].contains(&moved[0].1)
+                        && others.iter().all(|(other, _)| {
+                            self.proto_info
+                                .concurrent_events
+                                .contains(&UnordEventPair::new(
+                                    label.get_event_type(),
+                                    other.get_event_type(),
+                                ))
+                        })
+                });
+                (!local.is_empty() && !others.is_empty() && local.len() == outgoing && independent)
+                    .then_some(local)
+            })
+            .min_by_key(|local| local.len())
+    }
+
+    fn to_edges(
+        &self,
+        node: ComposedNode,
+        state: &[NodeId],
+        moves: Vec<Move<'a>>,
+    ) -> Vec<ComposedEdge<'a>> {
+        moves
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, (label, moved))| {
+                let mut target = state.to_vec();
+                for (k, edge) in moved {
+                    target[k] = self.proto_info.protocols[k]
+                        .graph
+                        .edge_endpoints(edge)
+                        .unwrap()
+                        .1;
+                }
+                Some(ComposedEdge {
+                    source: node,
+                    target: self.node(target)?,
+                    index,
+                    label,
+                })
+            })
+            .collect()
+    }
+}
+
+impl GraphBase for LazyComposition<'_> {
+    type NodeId = ComposedNode;
+    type EdgeId = (ComposedNode, usize);
+}
+
+impl Data for LazyComposition<'_> {
+    type NodeWeight = State;
+    type EdgeWeight = SwarmLabel;
+}
+
+impl Visitable for LazyComposition<'_> {
+    type Map = HashSet<ComposedNode>;
+
+    fn visit_map(&self) -> Self::Map {
+        HashSet::new()
+    }
+
+    fn reset_map(&self, map: &mut Self::Map) {
+        map.clear();
+    }
+}
+
+impl<'a> IntoNeighbors for &LazyComposition<'a> {
+    type Neighbors = std::vec::IntoIter<ComposedNode>;
+
+    fn neighbors(self, node: ComposedNode) -> Self::Neighbors {
+        self.successors(node)
+            .into_iter()
+            .map(|edge| edge.target)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<'a> IntoEdges for &LazyComposition<'a> {
+    type Edges = std::vec::IntoIter<ComposedEdge<'a>>;
+
+    fn edges(self, node: ComposedNode) -> Self::Edges {
+        self.successors(node).into_iter()
+    }
+}
+
+impl<'a> IntoNeighborsDirected for &LazyComposition<'a> {
+    type NeighborsDirected = std::vec::IntoIter<ComposedNode>;
+
+    fn neighbors_directed(
+        self,
+        node: ComposedNode,
+        direction: Direction,
+    ) -> Self::NeighborsDirected {
+        self.edges_directed(node, direction)
+            .map(|edge| match direction {
+                Outgoing => edge.target,
+                Incoming => edge.source,
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+// Incoming edges are those from discovered states, see LazyComposition::predecessors.
+impl<'a> IntoEdgesDirected for &LazyComposition<'a> {
+    type EdgesDirected = std::vec::IntoIter<ComposedEdge<'a>>;
+
+    fn edges_directed(self, node: ComposedNode, direction: Direction) -> Self::EdgesDirected {
+        match direction {
+            Outgoing => self.successors(node).into_iter(),
+            Incoming => self.predecessors(node).into_iter(),
+        }
+    }
+}
+
+// The states reachable from the initial state. Explores the whole (reduced) composition.
+impl<'a> IntoNodeIdentifiers for &LazyComposition<'a> {
+    type NodeIdentifiers = std::vec::IntoIter<ComposedNode>;
+
+    fn node_identifiers(self) -> Self::NodeIdentifiers {
+        self.reachable().into_iter()
+    }
+}
+
+// All transitions reachable from the initial state. Explores the whole (reduced) composition.
+impl<'a> IntoEdgeReferences for &LazyComposition<'a> {
+    type EdgeRef = ComposedEdge<'a>;
+    type EdgeReferences = std::vec::IntoIter<ComposedEdge<'a>>;
+
+    fn edge_references(self) -> Self::EdgeReferences {
+        let mut edges = vec![];
+        let mut visited = BTreeSet::from([self.initial()]);
+        let mut queue = VecDeque::from([self.initial()]);
+        while let Some(node) = queue.pop_front() {
+            for edge in self.successors(node) {
+                if visited.insert(edge.target) {
+                    queue.push_back(edge.target);
+                }
+                edges.push(edge);
+            }
+        }
+        edges.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subscription::exact;
+    use crate::test_utils;
+    use crate::types::proto_info::DEFAULT_MAX_STATES;
+    use crate::types::typescript_types::{InterfacingProtocols, SwarmProtocolType, Transition};
+    use petgraph::algo::has_path_connecting;
+
+    fn transitions(graph: &Graph) -> BTreeSet<Transition<SwarmLabel>> {
+        graph
+            .edge_references()
+            .map(|edge| Transition {
+                label: edge.weight().clone(),
+                source: graph[edge.source()].clone(),
+                target: graph[edge.target()].clone(),
+            })
+            .collect()
+    }
+
+    fn deadlocks(view: &LazyComposition) -> BTreeSet<State> {
+        view.reachable()
+            .into_iter()
+            .filter(|node| view.all_successors(*node).is_empty())
+            .map(|node| view.state_name(node))
+            .collect()
+    }
+
+    #[test]
+    fn test_same_as_explicit() {
+        test_utils::setup_logger();
+        for protos in [
+            test_utils::get_interfacing_swarms_1(),
+            test_utils::get_interfacing_swarms_2(),
+            test_utils::get_interfacing_swarms_3(),
+            test_utils::get_interfacing_swarms_pat_4(),
+        ] {
+            let proto_info = proto_info::swarms_to_proto_info(protos.clone());
+            let view = LazyComposition::new(&proto_info, DEFAULT_MAX_STATES).unwrap();
+            let (lazy, lazy_initial) = view.to_graph();
+            let (explicit, explicit_initial) = proto_info::compose_protocols(protos).unwrap();
+            assert_eq!(lazy[lazy_initial], explicit[explicit_initial]);
+            assert_eq!(lazy.node_count(), explicit.node_count());
+            assert_eq!(transitions(&lazy), transitions(&explicit));
+            // the incoming edges of each state are those of the composition
+            for node in view.reachable() {
+                let incoming: BTreeSet<_> = view
+                    .edges_directed(node, Incoming)
+                    .map(|edge| (view.state_name(edge.source()), edge.weight().clone()))
+                    .collect();
+                let explicit_node = explicit
+                    .node_indices()
+                    .find(|n| explicit[*n] == view.state_name(node))
+                    .unwrap();
+                let expected: BTreeSet<_> = explicit
+                    .edges_directed(explicit_node, Incoming)
+                    .map(|edge| (explicit[edge.source()].clone(), edge.weight().clone()))
+                    .collect();
+                assert_eq!(incoming, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_state_limit() {
+        test_utils::setup_logger();
+        let protos = test_utils::get_interfacing_swarms_2();
+        let proto_info = proto_info::swarms_to_proto_info(protos.clone());
+        let states = LazyComposition::new(&proto_info, DEFAULT_MAX_STATES)
+            .unwrap()
+            .reachable()
+            .len();
+
+        let view = LazyComposition::new(&proto_info, states).unwrap();
+        assert_eq!(view.reachable().len(), states);
+        assert!(view.check_limit().is_ok());
+
+        let view = LazyComposition::new(&proto_info, states - 1).unwrap();
+        assert_eq!(view.reachable().len(), states - 1);
+        assert_eq!(view.discovered(), states - 1);
+        assert_eq!(
+            view.check_limit().unwrap_err().to_strings(),
+            vec![format!(
+                "composition exceeds the limit of {} states",
+                states - 1
+            )]
+        );
+        let subs =
+            exact::exact_well_formed_sub(protos.clone(), &BTreeMap::new(), usize::MAX).unwrap();
+        assert!(view.project(&subs, Role::new("T"), false).is_err());
+        assert!(exact::lazy_well_formed_sub(protos, &BTreeMap::new(), states - 1).is_err());
+    }
+
+    #[test]
+    fn test_well_formed_sub_same_as_explicit() {
+        test_utils::setup_logger();
+        let single = |proto: SwarmProtocolType| InterfacingProtocols(vec![proto]);
+        for protos in [
+            test_utils::get_interfacing_swarms_1(),
+            test_utils::get_interfacing_swarms_2(),
+            test_utils::get_interfacing_swarms_3(),
+            test_utils::get_interfacing_swarms_4(),
+            test_utils::get_interfacing_swarms_5(),
+            test_utils::get_interfacing_swarms_pat_4(),
+            test_utils::get_ref_pat_protos(),
+            test_utils::get_payload_protos(),
+            single(test_utils::get_looping_proto_1()),
+            single(test_utils::get_looping_proto_4()),
+            single(test_utils::get_looping_proto_6()),
+            single(test_utils::get_guarded_proto()),
+            single(test_utils::get_timed_proto()),
+        ] {
+            let explicit =
+                exact::exact_well_formed_sub(protos.clone(), &BTreeMap::new(), DEFAULT_MAX_STATES)
+                    .unwrap();
+            let lazy =
+                exact::lazy_well_formed_sub(protos.clone(), &BTreeMap::new(), DEFAULT_MAX_STATES)
+                    .unwrap();
+            assert_eq!(lazy, explicit);
+        }
+    }
+
+    #[test]
+    fn test_missing_subscriptions() {
+        test_utils::setup_logger();
+        let protos = test_utils::get_interfacing_swarms_1();
+        let mut subs =
+            exact::exact_well_formed_sub(protos.clone(), &BTreeMap::new(), usize::MAX).unwrap();
+        assert!(
+            exact::lazy_missing_subscriptions(protos.clone(), &subs, usize::MAX)
+                .unwrap()
+                .is_empty()
+        );
+
+        // roles subscribe to the event types they emit
+        subs.get_mut(&Role::new("FL"))
+            .unwrap()
+            .remove(&EventType::new("pos"));
+        assert_eq!(
+            exact::lazy_missing_subscriptions(protos, &subs, usize::MAX).unwrap(),
+            BTreeMap::from([(Role::new("FL"), BTreeSet::from([EventType::new("pos")]))])
+        );
+    }
+
+    #[test]
+    fn test_reachability_with_petgraph() {
+        test_utils::setup_logger();
+        let proto_info = proto_info::swarms_to_proto_info(test_utils::get_interfacing_swarms_1());
+        let view = LazyComposition::new(&proto_info, DEFAULT_MAX_STATES).unwrap();
+        // only the initial state has been explored
+        assert_eq!(view.discovered(), 1);
+        let successors: Vec<_> = view.neighbors(view.initial()).collect();
+        assert_eq!(successors.len(), 2);
+        assert!(view.discovered() <= 3);
+        let terminal = view
+            .reachable()
+            .into_iter()
+            .find(|node| view.state_name(*node) == State::new("3 || 3"))
+            .unwrap();
+        assert!(has_path_connecting(&view, view.initial(), terminal, None));
+        assert!(!has_path_connecting(&view, terminal, view.initial(), None));
+    }
+
+    #[test]
+    fn test_reduction_preserves_deadlocks() {
+        test_utils::setup_logger();
+        // three protocols with nothing in common, all interleavings lead to the same deadlock
+        let proto = |prefix: &str| {
+            let json = r#"{
+                "initial": "0",
+                "transitions": [
+                    { "source": "0", "target": "1", "label": { "cmd": "Pa", "logType": ["Pa"], "role": "PR" } },
+                    { "source": "1", "target": "2", "label": { "cmd": "Pb", "logType": ["Pb"], "role": "PR" } },
+                    { "source": "2", "target": "3", "label": { "cmd": "Pc", "logType": ["Pc"], "role": "PR" } },
+                    { "source": "0", "target": "3", "label": { "cmd": "Pd", "logType": ["Pd"], "role": "PR" } }
+                ]
+            }"#;
+            serde_json::from_str::<SwarmProtocolType>(&json.replace('P', prefix)).unwrap()
+        };
+        let protos = InterfacingProtocols(vec![proto("x"), proto("y"), proto("z")]);
+        let proto_info = proto_info::swarms_to_proto_info(protos);
+        assert!(
+            proto_info.no_errors(),
+            "{:?}",
+            proto_info.to_error_report().to_strings()
+        );
+
+        let full = LazyComposition::new(&proto_info, DEFAULT_MAX_STATES).unwrap();
+        let reduced = LazyComposition::new(&proto_info, DEFAULT_MAX_STATES)
+            .unwrap()
+            .with_reduction();
+        let full_states = full.reachable().len();
+        let reduced_states = reduced.reachable().len();
+        assert_eq!(full_states, 64);
+        assert!(reduced_states < full_states);
+        assert_eq!(deadlocks(&reduced), deadlocks(&full));
+        assert_eq!(
+            deadlocks(&full),
+            BTreeSet::from([State::new("3 || 3 || 3")])
+        );
+    }
+
+    #[test]
+    fn test_projection_without_composing() {
+        test_utils::setup_logger();
+        let protos = test_utils::get_interfacing_swarms_2();
+        let subs =
+            exact::exact_well_formed_sub(protos.clone(), &BTreeMap::new(), usize::MAX).unwrap();
+        let proto_info = proto_info::swarms_to_proto_info(protos.clone());
+        let (explicit, explicit_initial) = proto_info::compose_protocols(protos).unwrap();
+        for role in ["T", "FL", "D", "F", "TR", "QCR"] {
+            let role = Role::new(role);
+            let view = LazyComposition::new(&proto_info, DEFAULT_MAX_STATES).unwrap();
+            let (lazy, lazy_initial) = view.project(&subs, role.clone(), false).unwrap();
+            let (expected, expected_initial) =
+                projection::project(&explicit, explicit_initial, &subs, role, false);
+            assert_eq!(lazy[lazy_initial], expected[expected_initial]);
+            let machine_transitions = |graph: &MachineGraph| {
+                graph
+                    .edge_references()
+                    .map(|edge| {
+                        (
+                            graph[edge.source()].clone(),
+                            edge.weight().clone(),
+                            graph[edge.target()].clone(),
+                        )
+                    })
+                    .collect::<BTreeSet<_>>()
+            };
+            assert_eq!(machine_transitions(&lazy), machine_transitions(&expected));
+        }
+    }
+}
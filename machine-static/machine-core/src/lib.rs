@@ -1,31 +1,105 @@
+use itertools::Itertools;
+use std::collections::{BTreeMap, BTreeSet};
 use wasm_bindgen::prelude::*;
 
+use crate::cache::CachedAnalysis;
 use crate::machine::util::to_json_machine;
 use crate::machine::{adaptation, projection};
 use crate::types::typescript_types::{
-    DataResult, Granularity, InterfacingProtocols, MachineType, ProjectionInfo, Role,
-    Subscriptions, SubscriptionsWrapped, SwarmProtocolType,
+    CompositionEstimate, ConformanceReport, Counterexample, DataResult, DeadlockReport, Formula,
+    Granularity, InterfacingProtocols, LivenessReport, LocalConformanceReport, MachineType,
+    PathQuery, PayloadSchema, ProjectionInfo, ProjectionWithProvenance, ProtocolAnalysis,
+    ProtocolMachines, Role, RoleMachines, RoleProjectionInfo, Subscriptions, SubscriptionsWrapped,
+    SwarmLabel, SwarmProtocolType, SwarmTransition,
+};
+use crate::types::{
+    proto_info::{self, ProtoInfo},
+    typescript_types,
 };
-use crate::types::{proto_info, typescript_types};
 
+pub mod cache;
+pub mod codegen;
 mod composability_check;
 mod composition;
+pub mod conformance;
+pub mod deadlock;
 pub mod errors;
+pub mod lazy_composition;
+pub mod liveness;
 mod machine;
+pub mod model_checker;
+pub mod parallel;
+pub mod query;
+pub mod runtime;
+pub mod session;
+pub mod simulation;
 mod subscription;
+pub mod temporal;
 pub mod types;
 
+pub use crate::machine::validation;
+
 #[cfg(test)]
 mod test_utils;
 
 use crate::subscription::{exact, overapproximation};
 
+// The composition of protos is limited to max_states states, DEFAULT_MAX_STATES if not given.
 #[wasm_bindgen]
 pub fn exact_well_formed_sub(
     protos: InterfacingProtocols,
     subs: SubscriptionsWrapped,
+    max_states: Option<usize>,
+) -> DataResult<Subscriptions> {
+    let result = exact::exact_well_formed_sub(
+        protos,
+        &subs.0,
+        max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES),
+    );
+    match result {
+        Ok(subscriptions) => DataResult::OK {
+            data: subscriptions,
+        },
+        Err(error_report) => DataResult::ERROR {
+            errors: error_report.to_strings(),
+        },
+    }
+}
+
+// Like exact_well_formed_sub, but without building the composition of protos.
+#[wasm_bindgen]
+pub fn lazy_well_formed_sub(
+    protos: InterfacingProtocols,
+    subs: SubscriptionsWrapped,
+    max_states: Option<usize>,
 ) -> DataResult<Subscriptions> {
-    let result = exact::exact_well_formed_sub(protos, &subs.0);
+    let result = exact::lazy_well_formed_sub(
+        protos,
+        &subs.0,
+        max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES),
+    );
+    match result {
+        Ok(subscriptions) => DataResult::OK {
+            data: subscriptions,
+        },
+        Err(error_report) => DataResult::ERROR {
+            errors: error_report.to_strings(),
+        },
+    }
+}
+
+// The event types missing from subs for subs to be well-formed, without building the composition of protos.
+#[wasm_bindgen]
+pub fn missing_subscriptions(
+    protos: InterfacingProtocols,
+    subs: SubscriptionsWrapped,
+    max_states: Option<usize>,
+) -> DataResult<Subscriptions> {
+    let result = exact::lazy_missing_subscriptions(
+        protos,
+        &subs.0,
+        max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES),
+    );
     match result {
         Ok(subscriptions) => DataResult::OK {
             data: subscriptions,
@@ -53,6 +127,130 @@ pub fn overapproximated_well_formed_sub(
     }
 }
 
+#[wasm_bindgen]
+pub fn projection_information_machines(
+    role: Role,
+    protos: InterfacingProtocols,
+    subs: SubscriptionsWrapped,
+    machines: ProtocolMachines,
+    minimize: bool,
+) -> DataResult<ProjectionInfo> {
+    let proto_info = proto_info::swarms_to_proto_info(protos);
+    if !proto_info.no_errors() {
+        return DataResult::ERROR {
+            errors: proto_info.to_error_report().to_strings(),
+        };
+    }
+    let mut errors = vec![];
+    let mut graphs = BTreeMap::new();
+    let mut seen = BTreeSet::new();
+    for protocol_machine in machines.0 {
+        if !seen.insert(protocol_machine.k) {
+            errors.push(format!(
+                "duplicate machine for role {}, index {}",
+                role, protocol_machine.k
+            ));
+            continue;
+        }
+        errors.extend(
+            validation::validate_machine(&protocol_machine.machine, &role, &subs.0)
+                .iter()
+                .map(|e| e.to_string()),
+        );
+        let (machine, initial, m_errors) = machine::util::from_json(protocol_machine.machine);
+        let machine_problem = !m_errors.is_empty();
+        errors.extend(m_errors);
+        let Some(initial) = initial else {
+            errors.push(format!(
+                "initial machine state has no transitions (index {})",
+                protocol_machine.k
+            ));
+            continue;
+        };
+        if !machine_problem {
+            graphs.insert(protocol_machine.k, (machine, initial));
+        }
+    }
+    if graphs.is_empty() {
+        errors.push(format!("no machines given for role {}", role));
+    }
+    if !errors.is_empty() {
+        return DataResult::ERROR { errors };
+    }
+    let indices = graphs.keys().join(", ");
+    match adaptation::projection_information_machines(&proto_info, &subs.0, role, graphs, minimize)
+    {
+        Some(projection_info) => DataResult::OK {
+            data: projection_info,
+        },
+        None => DataResult::ERROR {
+            errors: vec![format!("invalid indices {}", indices)],
+        },
+    }
+}
+
+#[wasm_bindgen]
+pub fn projection_information_batch(
+    protos: InterfacingProtocols,
+    subs: SubscriptionsWrapped,
+    machines: RoleMachines,
+    minimize: bool,
+) -> DataResult<Vec<RoleProjectionInfo>> {
+    let proto_info = proto_info::swarms_to_proto_info(protos);
+    if !proto_info.no_errors() {
+        return DataResult::ERROR {
+            errors: proto_info.to_error_report().to_strings(),
+        };
+    }
+    let mut errors = vec![];
+    let mut graphs = BTreeMap::new();
+    let mut keys = BTreeSet::new();
+    for role_machine in machines.0 {
+        if !keys.insert((role_machine.k, role_machine.role.clone())) {
+            errors.push(format!(
+                "duplicate machine for role {}, index {}",
+                role_machine.role, role_machine.k
+            ));
+            continue;
+        }
+        errors.extend(
+            validation::validate_machine(&role_machine.machine, &role_machine.role, &subs.0)
+                .iter()
+                .map(|e| e.to_string()),
+        );
+        let (machine, initial, m_errors) = machine::util::from_json(role_machine.machine);
+        let machine_problem = !m_errors.is_empty();
+        errors.extend(m_errors);
+        let Some(initial) = initial else {
+            errors.push(format!(
+                "initial machine state has no transitions (role {}, index {})",
+                role_machine.role, role_machine.k
+            ));
+            continue;
+        };
+        if !machine_problem {
+            graphs.insert((role_machine.k, role_machine.role), (machine, initial));
+        }
+    }
+    if !errors.is_empty() {
+        return DataResult::ERROR { errors };
+    }
+    match adaptation::projection_information_batch(&proto_info, &subs.0, graphs, minimize) {
+        Ok(infos) => DataResult::OK {
+            data: infos
+                .into_iter()
+                .map(|((k, role), projection_info)| RoleProjectionInfo {
+                    k,
+                    role,
+                    projection_info,
+                })
+                .collect(),
+        },
+        Err(errors) => DataResult::ERROR { errors },
+    }
+}
+
+// The composition is limited to max_states states (DEFAULT_MAX_STATES if not given) if expand_protos.
 #[wasm_bindgen]
 pub fn project(
     protos: InterfacingProtocols,
@@ -60,14 +258,76 @@ pub fn project(
     role: Role,
     minimize: bool,
     expand_protos: bool,
+    max_states: Option<usize>,
 ) -> DataResult<MachineType> {
     // Expand the protocol composition if expand_protos, otherwise project each protocol and compose machines.
-    let machine = if expand_protos {
-        match proto_info::compose_protocols(protos) {
+    if !expand_protos {
+        return project_proto_info(
+            proto_info::swarms_to_proto_info(protos),
+            &subs.0,
+            role,
+            minimize,
+        );
+    }
+    let payloads = proto_info::payloads(&protos);
+    match proto_info::compose_protocols_bounded(
+        protos,
+        max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES),
+    ) {
+        Ok((swarm, initial)) => {
+            let (proj, proj_initial) =
+                projection::project(&swarm, initial, &subs.0, role, minimize);
+            DataResult::OK {
+                data: machine::util::with_payloads(to_json_machine(proj, proj_initial), &payloads),
+            }
+        }
+        Err(error_report) => DataResult::ERROR {
+            errors: error_report.to_strings(),
+        },
+    }
+}
+
+// Project each protocol of proto_info and compose the projections.
+fn project_proto_info(
+    proto_info: ProtoInfo,
+    subs: &Subscriptions,
+    role: Role,
+    minimize: bool,
+) -> DataResult<MachineType> {
+    if !proto_info.no_errors() {
+        return DataResult::ERROR {
+            errors: proto_info.to_error_report().to_strings(),
+        };
+    }
+    let (proj, proj_initial) = projection::project_combine(&proto_info, subs, role, minimize);
+    DataResult::OK {
+        data: machine::util::with_payloads(
+            machine::util::option_to_json_machine(proj, proj_initial.unwrap()),
+            &proto_info.payloads,
+        ),
+    }
+}
+
+#[wasm_bindgen]
+pub fn project_with_provenance(
+    protos: InterfacingProtocols,
+    subs: SubscriptionsWrapped,
+    role: Role,
+    minimize: bool,
+    expand_protos: bool,
+    max_states: Option<usize>,
+) -> DataResult<ProjectionWithProvenance> {
+    // Same as project, but keep track of the protocol states represented by each state of the projection.
+    // If expand_protos the protocol states are states of the expanded composition, otherwise states of each protocol in protos.
+    // The composition is limited to max_states states (DEFAULT_MAX_STATES if not given) if expand_protos.
+    let payloads = proto_info::payloads(&protos);
+    let (proj, proj_initial) = if expand_protos {
+        match proto_info::compose_protocols_bounded(
+            protos,
+            max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES),
+        ) {
             Ok((swarm, initial)) => {
-                let (proj, proj_initial) =
-                    projection::project(&swarm, initial, &subs.0, role, minimize);
-                to_json_machine(proj, proj_initial)
+                projection::project_with_provenance(&swarm, initial, &subs.0, role, minimize)
             }
             Err(error_report) => {
                 return DataResult::ERROR {
@@ -79,9 +339,13 @@ pub fn project(
         let proto_info = proto_info::swarms_to_proto_info(protos);
         match proto_info.no_errors() {
             true => {
-                let (proj, proj_initial) =
-                    projection::project_combine(&proto_info, &subs.0, role, minimize);
-                machine::util::option_to_json_machine(proj, proj_initial.unwrap())
+                let (proj, proj_initial) = projection::project_combine_with_provenance(
+                    &proto_info,
+                    &subs.0,
+                    role,
+                    minimize,
+                );
+                (proj, proj_initial.unwrap())
             }
             false => {
                 return DataResult::ERROR {
@@ -90,7 +354,15 @@ pub fn project(
             }
         }
     };
-    DataResult::OK { data: machine }
+    DataResult::OK {
+        data: ProjectionWithProvenance {
+            projection: machine::util::with_payloads(
+                to_json_machine(projection::from_provenance_graph(&proj), proj_initial),
+                &payloads,
+            ),
+            proj_to_proto_states: projection::to_proj_to_proto_states(&proj),
+        },
+    }
 }
 
 #[wasm_bindgen]
@@ -102,15 +374,35 @@ pub fn projection_information(
     machine: MachineType,
     minimize: bool,
 ) -> DataResult<ProjectionInfo> {
-    let proto_info = proto_info::swarms_to_proto_info(protos);
+    projection_information_proto_info(
+        role,
+        proto_info::swarms_to_proto_info(protos),
+        k,
+        &subs.0,
+        machine,
+        minimize,
+    )
+}
+
+fn projection_information_proto_info(
+    role: Role,
+    proto_info: ProtoInfo,
+    k: usize,
+    subs: &Subscriptions,
+    machine: MachineType,
+    minimize: bool,
+) -> DataResult<ProjectionInfo> {
     if !proto_info.no_errors() {
         return DataResult::ERROR {
             errors: proto_info.to_error_report().to_strings(),
         };
     }
+    let mut errors: Vec<String> = validation::validate_machine(&machine, &role, subs)
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
     let (machine, initial, m_errors) = machine::util::from_json(machine);
-    let machine_problem = !m_errors.is_empty();
-    let mut errors = vec![];
+    let machine_problem = !errors.is_empty() || !m_errors.is_empty();
     errors.extend(m_errors);
     let Some(initial) = initial else {
         errors.push(format!("initial machine state has no transitions"));
@@ -121,7 +413,7 @@ pub fn projection_information(
     }
     match adaptation::projection_information(
         &proto_info,
-        &subs.0,
+        subs,
         role,
         (machine, initial),
         k,
@@ -136,16 +428,376 @@ pub fn projection_information(
     }
 }
 
+// Fails if the composition has more than max_states states, DEFAULT_MAX_STATES if not given.
 #[wasm_bindgen]
-pub fn compose_protocols(protos: InterfacingProtocols) -> DataResult<SwarmProtocolType> {
-    let composition = proto_info::compose_protocols(protos);
+pub fn compose_protocols(
+    protos: InterfacingProtocols,
+    max_states: Option<usize>,
+) -> DataResult<SwarmProtocolType> {
+    let payloads = proto_info::payloads(&protos);
+    let composition = proto_info::compose_protocols_bounded(
+        protos,
+        max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES),
+    );
 
     match composition {
         Ok((graph, initial)) => DataResult::OK {
-            data: typescript_types::to_json_swarm(graph, initial),
+            data: SwarmProtocolType {
+                payloads,
+                ..typescript_types::to_json_swarm(graph, initial)
+            },
         },
         Err(errors) => DataResult::ERROR {
             errors: errors.to_strings(),
         },
     }
 }
+
+// Check a recorded event log given as JSON lines against the composition of protos.
+// Fails if the composition has more than max_states states, DEFAULT_MAX_STATES if not given.
+#[wasm_bindgen]
+pub fn check_event_log(
+    protos: InterfacingProtocols,
+    log: String,
+    max_states: Option<usize>,
+) -> DataResult<ConformanceReport> {
+    let max_states = max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES);
+    let result = conformance::parse_event_log(&log)
+        .and_then(|entries| conformance::check_conformance(protos, &entries, max_states));
+    match result {
+        Ok(report) => DataResult::OK { data: report },
+        Err(errors) => DataResult::ERROR { errors },
+    }
+}
+
+// Check the local event log of a peer playing role, given as JSON lines, against its projection.
+// Fails if the composition has more than max_states states, DEFAULT_MAX_STATES if not given.
+#[wasm_bindgen]
+pub fn check_local_event_log(
+    role: Role,
+    protos: InterfacingProtocols,
+    subs: SubscriptionsWrapped,
+    log: String,
+    max_states: Option<usize>,
+) -> DataResult<LocalConformanceReport> {
+    let max_states = max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES);
+    let result = conformance::parse_event_log(&log).and_then(|entries| {
+        conformance::check_local_conformance(protos, &subs.0, role, &entries, max_states)
+    });
+    match result {
+        Ok(report) => DataResult::OK { data: report },
+        Err(errors) => DataResult::ERROR { errors },
+    }
+}
+
+// Check a payload given as JSON against schema. Returns the paths of the parts of
+// the payload that do not match the schema, empty if the payload is valid.
+#[wasm_bindgen]
+pub fn validate_payload(schema: PayloadSchema, payload: String) -> DataResult<Vec<String>> {
+    match serde_json::from_str::<serde_json::Value>(&payload) {
+        Ok(value) => DataResult::OK {
+            data: schema.validate(&value),
+        },
+        Err(e) => DataResult::ERROR {
+            errors: vec![format!("payload: {e}")],
+        },
+    }
+}
+
+// Check a temporal property against the composition of protos. Returns a counterexample if it does not hold.
+// Fails if the composition has more than max_states states, DEFAULT_MAX_STATES if not given.
+#[wasm_bindgen]
+pub fn check_temporal_property(
+    protos: InterfacingProtocols,
+    property: Formula,
+    max_states: Option<usize>,
+) -> DataResult<Option<Counterexample>> {
+    match temporal::check_property(
+        protos,
+        &property,
+        max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES),
+    ) {
+        Ok(counterexample) => DataResult::OK {
+            data: counterexample,
+        },
+        Err(errors) => DataResult::ERROR { errors },
+    }
+}
+
+// Report deadlocks and livelocks of the composition of protos.
+// Fails if the composition has more than max_states states, DEFAULT_MAX_STATES if not given.
+#[wasm_bindgen]
+pub fn deadlock_report(
+    protos: InterfacingProtocols,
+    max_states: Option<usize>,
+) -> DataResult<DeadlockReport> {
+    match deadlock::deadlock_report(protos, max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES)) {
+        Ok(report) => DataResult::OK { data: report },
+        Err(errors) => DataResult::ERROR { errors },
+    }
+}
+
+// Report transitions, commands and roles of protos that do not survive composition.
+// Fails if the composition has more than max_states states, DEFAULT_MAX_STATES if not given.
+#[wasm_bindgen]
+pub fn liveness_report(
+    protos: InterfacingProtocols,
+    max_states: Option<usize>,
+) -> DataResult<LivenessReport> {
+    match liveness::liveness_report(protos, max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES)) {
+        Ok(report) => DataResult::OK { data: report },
+        Err(errors) => DataResult::ERROR { errors },
+    }
+}
+
+// Query paths of the composition of protos. Paths are returned as sequences of labels.
+// Fails if the composition has more than max_states states, DEFAULT_MAX_STATES if not given.
+#[wasm_bindgen]
+pub fn query_paths(
+    protos: InterfacingProtocols,
+    query: PathQuery,
+    max_states: Option<usize>,
+) -> DataResult<Vec<Vec<SwarmLabel>>> {
+    match query::query_protocols(
+        protos,
+        &query,
+        max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES),
+    ) {
+        Ok(paths) => DataResult::OK { data: paths },
+        Err(errors) => DataResult::ERROR { errors },
+    }
+}
+
+// Estimate the number of states and transitions of the composition of protos without building it.
+// Use it to choose max_states before calling the analyses on the composition.
+#[wasm_bindgen]
+pub fn estimate_composition(protos: InterfacingProtocols) -> DataResult<CompositionEstimate> {
+    let proto_info = proto_info::swarms_to_proto_info(protos);
+    if !proto_info.no_errors() {
+        return DataResult::ERROR {
+            errors: proto_info.to_error_report().to_strings(),
+        };
+    }
+    DataResult::OK {
+        data: proto_info::estimate_composition(&proto_info),
+    }
+}
+
+// Concurrency, branching, joining and other analysis results for protos.
+#[wasm_bindgen]
+pub fn analyze_protocols(protos: InterfacingProtocols) -> DataResult<ProtocolAnalysis> {
+    let proto_info = proto_info::swarms_to_proto_info(protos);
+    if !proto_info.no_errors() {
+        return DataResult::ERROR {
+            errors: proto_info.to_error_report().to_strings(),
+        };
+    }
+    DataResult::OK {
+        data: proto_info::analyze_protocols(&proto_info),
+    }
+}
+
+// Generate a machine-runner TypeScript skeleton for role from its projection of protos.
+// subs should be well-formed, e.g. computed with overapproximated_well_formed_sub.
+#[wasm_bindgen]
+pub fn generate_machine_runner(
+    swarm_name: String,
+    protos: InterfacingProtocols,
+    subs: SubscriptionsWrapped,
+    role: Role,
+    minimize: bool,
+) -> DataResult<String> {
+    let proto_info = proto_info::swarms_to_proto_info(protos);
+    if !proto_info.no_errors() {
+        return DataResult::ERROR {
+            errors: proto_info.to_error_report().to_strings(),
+        };
+    }
+    match codegen::machine_runner_ts(&swarm_name, &proto_info, &subs.0, role.clone(), minimize) {
+        Some(ts) => DataResult::OK { data: ts },
+        None => DataResult::ERROR {
+            errors: vec![format!("could not project role {role}")],
+        },
+    }
+}
+
+// Analyze protos once. The returned JSON can be passed to the *_cached functions
+// instead of the protocols, and stored together with the protocols to be reused across runs.
+#[wasm_bindgen]
+pub fn cache_analysis(protos: InterfacingProtocols) -> DataResult<String> {
+    DataResult::OK {
+        data: CachedAnalysis::new(protos).to_json(),
+    }
+}
+
+// True if analysis was returned by cache_analysis for protos by this version. The functions taking
+// an analysis do not check this, they only have the analysis and not the protocols.
+#[wasm_bindgen]
+pub fn is_valid_analysis(analysis: String, protos: InterfacingProtocols) -> DataResult<bool> {
+    match serde_json::from_str::<CachedAnalysis>(&analysis) {
+        Ok(cached) => DataResult::OK {
+            data: cached.is_valid_for(&protos),
+        },
+        Err(e) => DataResult::ERROR {
+            errors: vec![format!("invalid analysis: {e}")],
+        },
+    }
+}
+
+fn cached_proto_info(analysis: &str) -> Result<ProtoInfo, Vec<String>> {
+    let proto_info = CachedAnalysis::from_json(analysis)
+        .map_err(|e| vec![format!("invalid analysis: {e}")])?
+        .proto_info;
+    if !proto_info.no_errors() {
+        return Err(proto_info.to_error_report().to_strings());
+    }
+    Ok(proto_info)
+}
+
+// Like exact_well_formed_sub, using an analysis returned by cache_analysis.
+#[wasm_bindgen]
+pub fn exact_well_formed_sub_cached(
+    analysis: String,
+    subs: SubscriptionsWrapped,
+    max_states: Option<usize>,
+) -> DataResult<Subscriptions> {
+    let composition = cached_proto_info(&analysis).and_then(|cached| {
+        proto_info::explicit_composition_proto_info_bounded(
+            cached,
+            max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES),
+        )
+        .map_err(|errors| errors.to_strings())
+    });
+    match composition {
+        Ok(composition) => DataResult::OK {
+            data: exact::exact_wf_sub(composition, 0, &subs.0),
+        },
+        Err(errors) => DataResult::ERROR { errors },
+    }
+}
+
+// Like overapproximated_well_formed_sub, using an analysis returned by cache_analysis.
+#[wasm_bindgen]
+pub fn overapproximated_well_formed_sub_cached(
+    analysis: String,
+    subs: SubscriptionsWrapped,
+    granularity: Granularity,
+) -> DataResult<Subscriptions> {
+    match cached_proto_info(&analysis) {
+        Ok(mut proto_info) => DataResult::OK {
+            data: overapproximation::overapprox_wf_sub(&mut proto_info, &subs.0, granularity),
+        },
+        Err(errors) => DataResult::ERROR { errors },
+    }
+}
+
+// Like project without expand_protos, using an analysis returned by cache_analysis.
+#[wasm_bindgen]
+pub fn project_cached(
+    analysis: String,
+    subs: SubscriptionsWrapped,
+    role: Role,
+    minimize: bool,
+) -> DataResult<MachineType> {
+    match cached_proto_info(&analysis) {
+        Ok(proto_info) => project_proto_info(proto_info, &subs.0, role, minimize),
+        Err(errors) => DataResult::ERROR { errors },
+    }
+}
+
+// Like projection_information, using an analysis returned by cache_analysis.
+#[wasm_bindgen]
+pub fn projection_information_cached(
+    role: Role,
+    analysis: String,
+    k: usize,
+    subs: SubscriptionsWrapped,
+    machine: MachineType,
+    minimize: bool,
+) -> DataResult<ProjectionInfo> {
+    match cached_proto_info(&analysis) {
+        Ok(proto_info) => {
+            projection_information_proto_info(role, proto_info, k, &subs.0, machine, minimize)
+        }
+        Err(errors) => DataResult::ERROR { errors },
+    }
+}
+
+// An analysis session over protocols edited over time, for editors. See session::Session.
+#[wasm_bindgen]
+pub struct ProtocolSession(session::Session);
+
+#[wasm_bindgen]
+impl ProtocolSession {
+    // Exact subscriptions are limited to compositions of max_states states, DEFAULT_MAX_STATES if not given.
+    #[wasm_bindgen(constructor)]
+    pub fn new(protos: InterfacingProtocols, max_states: Option<usize>) -> Self {
+        Self(session::Session::new(
+            protos,
+            max_states.unwrap_or(proto_info::DEFAULT_MAX_STATES),
+        ))
+    }
+
+    pub fn protocols(&self) -> InterfacingProtocols {
+        self.0.protocols()
+    }
+
+    pub fn add_protocol(&mut self, proto: SwarmProtocolType) {
+        self.0.add_protocol(proto)
+    }
+
+    pub fn add_transition(
+        &mut self,
+        proto_pointer: usize,
+        transition: SwarmTransition,
+    ) -> DataResult<InterfacingProtocols> {
+        match self.0.add_transition(proto_pointer, transition) {
+            Ok(()) => DataResult::OK {
+                data: self.0.protocols(),
+            },
+            Err(error) => DataResult::ERROR {
+                errors: vec![error],
+            },
+        }
+    }
+
+    pub fn remove_transition(
+        &mut self,
+        proto_pointer: usize,
+        transition: SwarmTransition,
+    ) -> DataResult<InterfacingProtocols> {
+        match self.0.remove_transition(proto_pointer, &transition) {
+            Ok(()) => DataResult::OK {
+                data: self.0.protocols(),
+            },
+            Err(error) => DataResult::ERROR {
+                errors: vec![error],
+            },
+        }
+    }
+
+    pub fn exact_well_formed_sub(
+        &mut self,
+        subs: SubscriptionsWrapped,
+    ) -> DataResult<Subscriptions> {
+        match self.0.exact_well_formed_sub(&subs.0) {
+            Ok(subscriptions) => DataResult::OK {
+                data: subscriptions,
+            },
+            Err(errors) => DataResult::ERROR { errors },
+        }
+    }
+
+    pub fn overapproximated_well_formed_sub(
+        &mut self,
+        subs: SubscriptionsWrapped,
+        granularity: Granularity,
+    ) -> DataResult<Subscriptions> {
+        match self.0.overapprox_well_formed_sub(&subs.0, granularity) {
+            Ok(subscriptions) => DataResult::OK {
+                data: subscriptions,
+            },
+            Err(errors) => DataResult::ERROR { errors },
+        }
+    }
+}
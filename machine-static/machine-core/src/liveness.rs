@@ -0,0 +1,248 @@
+use std::collections::BTreeSet;
+
+use petgraph::{Direction::Outgoing, visit::EdgeRef};
+
+use crate::types::{
+    proto_info::{self, ProtoInfo},
+    typescript_types::{
+        DeadCommand, EventLabel, InterfacingProtocols, LivenessReport, ProtocolState, Role,
+        StateName, Transition, VanishedTransition,
+    },
+};
+
+// Compare the transitions of each protocol of protos with the explicit composition.
+// Reports the transitions that do not survive composition, the commands that are
+// never enabled in the composition and the roles that never act in it.
+// Fails if the composition has more than max_states states.
+pub fn liveness_report(
+    protos: InterfacingProtocols,
+    max_states: usize,
+) -> Result<LivenessReport, Vec<String>> {
+    let _span = tracing::info_span!("liveness_report").entered();
+    let proto_info = proto_info::swarms_to_proto_info(protos);
+    if !proto_info.no_errors() {
+        return Err(proto_info.to_error_report().to_strings());
+    }
+    let (tracked, _) = proto_info::tracked_composition(&proto_info, max_states)
+        .map_err(|errors| errors.to_strings())?;
+
+    let composed_commands: BTreeSet<DeadCommand> = tracked
+        .edge_weights()
+        .map(|label| DeadCommand {
+            cmd: label.cmd.clone(),
+            role: label.role.clone(),
+        })
+        .collect();
+    let commands: BTreeSet<DeadCommand> = proto_info
+        .protocols
+        .iter()
+        .flat_map(|p| p.graph.edge_weights())
+        .map(|label| DeadCommand {
+            cmd: label.cmd.clone(),
+            role: label.role.clone(),
+        })
+        .collect();
+    let composed_roles: BTreeSet<Role> = composed_commands.iter().map(|c| c.role.clone()).collect();
+    let roles: BTreeSet<Role> = proto_info
+        .protocols
+        .iter()
+        .flat_map(|p| p.roles.iter().cloned())
+        .collect();
+
+    Ok(LivenessReport {
+        vanished_transitions: vanished_transitions(&proto_info, &tracked),
+        dead_commands: commands.difference(&composed_commands).cloned().collect(),
+        inactive_roles: roles.difference(&composed_roles).cloned().collect(),
+    })
+}
+
+fn vanished_transitions(
+    proto_info: &ProtoInfo,
+    tracked: &proto_info::TrackedGraph,
+) -> Vec<VanishedTransition> {
+    let mut vanished = vec![];
+    for (k, protocol) in proto_info.protocols.iter().enumerate() {
+        let graph = &protocol.graph;
+        for edge in graph.edge_references() {
+            // the composed states where protocol k is in the source state of edge
+            let sources: Vec<_> = tracked
+                .node_indices()
+                .filter(|node| tracked[*node][k] == edge.source())
+                .collect();
+            let taken = sources.iter().any(|node| {
+                tracked.edges_directed(*node, Outgoing).any(|composed| {
+                    composed.weight() == edge.weight()
+                        && tracked[composed.target()][k] == edge.target()
+                })
+            });
+            if taken {
+                continue;
+            }
+
+            let transition = Transition {
+                label: edge.weight().clone(),
+                source: graph[edge.source()].state_name().clone(),
+                target: graph[edge.target()].state_name().clone(),
+            };
+            if sources.is_empty() {
+                vanished.push(VanishedTransition {
+                    k,
+                    transition,
+                    interface_event: None,
+                    blocked_by: vec![],
+                });
+                continue;
+            }
+            // the other protocols with the event type did not offer it alongside
+            let event_type = edge.weight().get_event_type();
+            let blocked_by: BTreeSet<ProtocolState> = sources
+                .iter()
+                .flat_map(|node| {
+                    tracked[*node]
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != k)
+                        .filter(|(j, _)| {
+                            proto_info.protocols[*j]
+                                .graph
+                                .edge_weights()
+                                .any(|label| label.get_event_type() == event_type)
+                        })
+                        .filter(|(j, local)| {
+                            !proto_info.protocols[*j]
+                                .graph
+                                .edges_directed(**local, Outgoing)
+                                .any(|e| e.weight() == edge.weight())
+                        })
+                        .map(|(j, local)| ProtocolState {
+                            k: j,
+                            state: proto_info.protocols[j].graph[*local].state_name().clone(),
+                        })
+                })
+                .collect();
+            vanished.push(VanishedTransition {
+                k,
+                transition,
+                interface_event: Some(event_type),
+                blocked_by: blocked_by.into_iter().collect(),
+            });
+        }
+    }
+    vanished
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::types::proto_info::DEFAULT_MAX_STATES;
+    use crate::types::typescript_types::{Command, EventType, State, SwarmProtocolType};
+
+    #[test]
+    fn test_liveness_composition() {
+        test_utils::setup_logger();
+        let report =
+            liveness_report(test_utils::get_interfacing_swarms_2(), DEFAULT_MAX_STATES).unwrap();
+        assert_eq!(
+            report,
+            LivenessReport {
+                vanished_transitions: vec![],
+                dead_commands: vec![],
+                inactive_roles: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_liveness_blocked_interface() {
+        test_utils::setup_logger();
+        // expects part before partID, the warehouse never emits part first
+        let proto = serde_json::from_str::<SwarmProtocolType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "source": "0", "target": "1", "label": { "cmd": "deliver", "logType": ["part"], "role": "T" } },
+                    { "source": "1", "target": "2", "label": { "cmd": "request", "logType": ["partID"], "role": "T" } },
+                    { "source": "2", "target": "3", "label": { "cmd": "build", "logType": ["car"], "role": "F" } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let protos = InterfacingProtocols(vec![test_utils::get_proto1(), proto]);
+        let report = liveness_report(protos, DEFAULT_MAX_STATES).unwrap();
+
+        let blocked: Vec<_> = report
+            .vanished_transitions
+            .iter()
+            .filter(|v| v.interface_event.is_some())
+            .map(|v| (v.k, v.transition.label.cmd.clone(), v.blocked_by.clone()))
+            .collect();
+        assert_eq!(
+            blocked,
+            vec![
+                (
+                    0,
+                    Command::new("request"),
+                    vec![ProtocolState {
+                        k: 1,
+                        state: State::new("0")
+                    }]
+                ),
+                (
+                    1,
+                    Command::new("deliver"),
+                    vec![
+                        ProtocolState {
+                            k: 0,
+                            state: State::new("0")
+                        },
+                        ProtocolState {
+                            k: 0,
+                            state: State::new("3")
+                        }
+                    ]
+                ),
+            ]
+        );
+        let unreached: BTreeSet<_> = report
+            .vanished_transitions
+            .iter()
+            .filter(|v| v.interface_event.is_none())
+            .map(|v| (v.k, v.transition.label.get_event_type()))
+            .collect();
+        assert_eq!(
+            unreached,
+            BTreeSet::from([
+                (0, EventType::new("pos")),
+                (0, EventType::new("part")),
+                (1, EventType::new("partID")),
+                (1, EventType::new("car")),
+            ])
+        );
+        assert_eq!(
+            report.dead_commands.into_iter().collect::<BTreeSet<_>>(),
+            BTreeSet::from([
+                DeadCommand {
+                    cmd: Command::new("request"),
+                    role: Role::new("T")
+                },
+                DeadCommand {
+                    cmd: Command::new("get"),
+                    role: Role::new("FL")
+                },
+                DeadCommand {
+                    cmd: Command::new("deliver"),
+                    role: Role::new("T")
+                },
+                DeadCommand {
+                    cmd: Command::new("build"),
+                    role: Role::new("F")
+                },
+            ])
+        );
+        assert_eq!(
+            report.inactive_roles.into_iter().collect::<BTreeSet<_>>(),
+            BTreeSet::from([Role::new("T"), Role::new("FL"), Role::new("F")])
+        );
+    }
+}
@@ -2,3 +2,4 @@ pub mod adaptation;
 pub mod minimize;
 pub mod projection;
 pub mod util;
+pub mod validation;
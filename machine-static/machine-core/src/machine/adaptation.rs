@@ -1,16 +1,16 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
-use crate::machine::{projection, util};
+use crate::machine::{minimize, projection, util};
 use crate::types::unordered_event_pair::UnordEventPair;
 use crate::{
-    composition,
+    composition, parallel,
     types::{
-        projection::{Graph, OptionGraph},
+        projection::{ChainedProtos, Graph, OptionGraph},
         proto_graph::NodeId,
         proto_info::{self, ProtoInfo},
         typescript_types::{
-            BranchMap, EventLabel, EventType, MachineLabel, ProjToMachineStates, ProjectionInfo,
-            Role, State, StateName, Subscriptions,
+            BranchMap, EventLabel, EventType, MachineLabel, PayloadSchema, ProjToMachineStates,
+            ProjectionInfo, Role, State, StateName, Subscriptions,
         },
     },
 };
@@ -21,15 +21,14 @@ use petgraph::{
 };
 
 // Used for creating adapted machine.
-// A composed state in an adapted machine contains some
-// state(s) from the original machine to be adapted.
-// The field machine_states points to the(se) state(s).
-// A set of states because seems more general, maybe
-// we need that in the future.
+// A composed state in an adapted machine contains, for each
+// protocol in the composition, the state of the original machine
+// implemented for that protocol or None if the role is given by
+// the projection of the protocol.
 #[derive(Clone, PartialEq, PartialOrd, Ord, Eq, Hash, Debug)]
 struct AdaptationNode {
     state: State,
-    machine_states: Option<BTreeSet<State>>,
+    machine_states: Vec<Option<State>>,
 }
 type AdaptationGraph = petgraph::Graph<AdaptationNode, MachineLabel>;
 
@@ -45,107 +44,341 @@ pub fn projection_information(
     k: usize,
     minimize: bool,
 ) -> Option<ProjectionInfo> {
-    let (proj, proj_initial) =
-        match adapted_projection(&proto_info, subs, role, machine, k, minimize) {
-            Some((proj, Some(proj_initial))) => (proj, proj_initial),
-            _ => return None,
-        };
+    projection_information_machines(
+        proto_info,
+        subs,
+        role,
+        BTreeMap::from([(k, machine)]),
+        minimize,
+    )
+}
+
+// Like projection_information, but for a role implemented by one machine
+// per protocol in `machines` (keyed by protocol index). The role is given
+// by the projection of the protocols that have no machine.
+pub fn projection_information_machines(
+    proto_info: &ProtoInfo,
+    subs: &Subscriptions,
+    role: Role,
+    machines: BTreeMap<usize, (OptionGraph, NodeId)>,
+    minimize: bool,
+) -> Option<ProjectionInfo> {
+    let (proj, proj_initial) = match adapted_projection(proto_info, subs, role, machines, minimize)
+    {
+        Some((proj, Some(proj_initial))) => (proj, proj_initial),
+        _ => return None,
+    };
+
+    let updating_event_types = proto_info::get_updating_event_types(&proto_info, &subs);
+    let concurrent_events = branch_concurrent_events(proto_info);
+
+    Some(to_projection_info(
+        &proj,
+        proj_initial,
+        updating_event_types,
+        &concurrent_events,
+        &proto_info.payloads,
+    ))
+}
+
+// The projection information of the projection of role itself, without adapting a machine.
+// Each state of the projection is mapped to itself, the projection is its own machine
+// for every protocol.
+pub fn projection_information_projection(
+    proto_info: &ProtoInfo,
+    subs: &Subscriptions,
+    role: Role,
+    minimize: bool,
+) -> Option<ProjectionInfo> {
+    let _span = tracing::info_span!("projection_information_projection", %role).entered();
+    if proto_info.protocols.is_empty() {
+        return None;
+    }
+    let projections = to_adaptation_projections(
+        projection::to_chained_protos(proto_info),
+        subs,
+        role,
+        minimize,
+    );
+    let (proj, proj_initial) = adapt(&projections, &BTreeMap::new())?;
+    let proj = proj.map(
+        |_, n| AdaptationNode {
+            state: n.state.clone(),
+            machine_states: vec![Some(n.state.clone()); proto_info.protocols.len()],
+        },
+        |_, label| label.clone(),
+    );
 
+    let updating_event_types = proto_info::get_updating_event_types(proto_info, subs);
+    let concurrent_events = branch_concurrent_events(proto_info);
+
+    Some(to_projection_info(
+        &proj,
+        proj_initial,
+        updating_event_types,
+        &concurrent_events,
+        &proto_info.payloads,
+    ))
+}
+
+// Obtain the projection information for a number of (protocol index, role) pairs at once.
+// The analysis of the composition, the updating event types and the projections of the
+// protocols are computed once and shared between the adaptations.
+// Returns an error for each invalid index and for each role that was given machines
+// for several protocols whose adaptations do not behave the same.
+pub fn projection_information_batch(
+    proto_info: &ProtoInfo,
+    subs: &Subscriptions,
+    machines: BTreeMap<(usize, Role), (OptionGraph, NodeId)>,
+    minimize: bool,
+) -> Result<BTreeMap<(usize, Role), ProjectionInfo>, Vec<String>> {
+    let _span = tracing::info_span!("projection_information_batch").entered();
+    let mut errors = vec![];
+    for (k, role) in machines.keys() {
+        if *k >= proto_info.protocols.len() {
+            errors.push(format!("invalid index {} for role {}", k, role));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let chained_protos = projection::to_chained_protos(proto_info);
+    // the projections of each role are computed once, independently of the other roles
+    let roles: Vec<Role> = machines
+        .keys()
+        .map(|(_, role)| role.clone())
+        .unique()
+        .collect();
+    let role_projections: BTreeMap<Role, Vec<(AdaptationGraph, NodeId, BTreeSet<EventType>)>> =
+        roles
+            .clone()
+            .into_iter()
+            .zip(parallel::map(roles, |role| {
+                to_adaptation_projections(chained_protos.clone(), subs, role, minimize)
+            }))
+            .collect();
+    let mut adapted: BTreeMap<(usize, Role), (AdaptationGraph, NodeId)> = BTreeMap::new();
+    for ((k, role), machine) in machines {
+        match adapt(&role_projections[&role], &BTreeMap::from([(k, machine)])) {
+            Some(adaptation) => {
+                adapted.insert((k, role), adaptation);
+            }
+            None => errors.push(format!(
+                "could not adapt machine for role {} in protocol {}",
+                role, k
+            )),
+        }
+    }
+
+    // a role implemented in several protocols should end up with the same behaviour each time
+    let mut by_role: BTreeMap<&Role, Vec<(usize, OptionGraph, NodeId)>> = BTreeMap::new();
+    for ((k, role), (proj, proj_initial)) in &adapted {
+        by_role.entry(role).or_default().push((
+            *k,
+            from_adaptation_graph_to_option_graph(proj),
+            *proj_initial,
+        ));
+    }
+    for (role, adaptations) in by_role {
+        let (k1, proj1, initial1) = &adaptations[0];
+        for (k2, proj2, initial2) in adaptations[1..].iter() {
+            if let Some(difference) = adaptation_difference(proj1, *initial1, proj2, *initial2) {
+                errors.push(format!(
+                    "inconsistent machines for role {} in protocols {} and {}: {}",
+                    role, k1, k2, difference
+                ));
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let updating_event_types = proto_info::get_updating_event_types(proto_info, subs);
+    let concurrent_events = branch_concurrent_events(proto_info);
+
+    Ok(adapted
+        .into_iter()
+        .map(|(key, (proj, proj_initial))| {
+            let projection_info = to_projection_info(
+                &proj,
+                proj_initial,
+                updating_event_types.clone(),
+                &concurrent_events,
+                &proto_info.payloads,
+            );
+            (key, projection_info)
+        })
+        .collect())
+}
+
+fn to_projection_info(
+    proj: &AdaptationGraph,
+    proj_initial: NodeId,
+    updating_event_types: BTreeSet<EventType>,
+    concurrent_events: &BTreeSet<UnordEventPair>,
+    payloads: &BTreeMap<EventType, PayloadSchema>,
+) -> ProjectionInfo {
+    // the states of the original machines, one entry per protocol, None for protocols without a machine
     let proj_to_machine_states: ProjToMachineStates = proj
         .node_references()
-        .map(|(_, n_ref)| {
-            (
-                n_ref.state.clone(),
-                n_ref.machine_states.clone().unwrap().into_iter().collect(),
-            )
-        })
+        .map(|(_, n_ref)| (n_ref.state.clone(), n_ref.machine_states.clone()))
         .collect();
 
-    let proj = from_adaptation_graph_to_option_graph(&proj);
-
-    let updating_event_types = proto_info::get_updating_event_types(&proto_info, &subs);
-    let branches = paths_from_event_types(&proj, &proto_info, &updating_event_types);
+    let proj = from_adaptation_graph_to_option_graph(proj);
+    let branches = paths_from_event_types(&proj, &updating_event_types, concurrent_events);
 
-    Some(ProjectionInfo {
-        projection: util::option_to_json_machine(proj, proj_initial),
+    ProjectionInfo {
+        projection: util::with_payloads(util::option_to_json_machine(proj, proj_initial), payloads),
         branches,
         special_event_types: updating_event_types,
         proj_to_machine_states,
-    })
+    }
 }
 
 fn adapted_projection(
     proto_info: &ProtoInfo,
     subs: &Subscriptions,
     role: Role,
-    machine: (OptionGraph, NodeId),
-    k: usize,
+    machines: BTreeMap<usize, (OptionGraph, NodeId)>,
     minimize: bool,
 ) -> Option<(AdaptationGraph, Option<NodeId>)> {
     let _span = tracing::info_span!("adapted_projection", %role).entered();
-    if proto_info.protocols.is_empty() || k >= proto_info.protocols.len() {
+    if proto_info.protocols.is_empty()
+        || machines.is_empty()
+        || machines.keys().any(|k| *k >= proto_info.protocols.len())
+    {
         return None;
     }
 
-    // project a protocol and turn the projection into an AdaptationGraph
+    let projections = to_adaptation_projections(
+        projection::to_chained_protos(proto_info),
+        subs,
+        role,
+        minimize,
+    );
+
+    adapt(&projections, &machines).map(|(proj, proj_initial)| (proj, Some(proj_initial)))
+}
+
+// project each protocol and turn the projections into AdaptationGraphs
+fn to_adaptation_projections(
+    chained_protos: ChainedProtos,
+    subs: &Subscriptions,
+    role: Role,
+    minimize: bool,
+) -> Vec<(AdaptationGraph, NodeId, BTreeSet<EventType>)> {
     let mapper = |(proj, proj_initial, interface): (Graph, NodeId, BTreeSet<EventType>)| {
         let proj = proj.map(
             |_, n| AdaptationNode {
                 state: n.clone(),
-                machine_states: None,
+                machine_states: vec![None],
             },
             |_, label| label.clone(),
         );
         (proj, proj_initial, interface)
     };
 
-    let gen_node = |n1: &AdaptationNode, n2: &AdaptationNode| -> AdaptationNode {
-        let name = format!("{} || {}", n1.state.state_name(), n2.state.state_name());
-        match (n1.machine_states.clone(), n2.machine_states.clone()) {
-            (None, None) => AdaptationNode {
-                state: State::from(name),
-                machine_states: None,
-            },
-            (Some(ms), None) => AdaptationNode {
-                state: State::from(name),
-                machine_states: Some(ms),
-            },
-            (None, Some(ms)) => AdaptationNode {
-                state: State::from(name),
-                machine_states: Some(ms),
-            },
-            (Some(ms1), Some(ms2)) => AdaptationNode {
-                state: State::from(name),
-                machine_states: Some(ms1.intersection(&ms2).cloned().collect()),
-            },
-        }
-    };
-
-    let projections: Vec<(AdaptationGraph, NodeId, BTreeSet<EventType>)> =
-        projection::to_chained_projections(
-            projection::to_chained_protos(proto_info),
-            subs,
-            role,
-            minimize,
-        )
+    projection::to_chained_projections(chained_protos, subs, role, minimize)
         .into_iter()
         .map(mapper)
+        .collect()
+}
+
+// Node in the composition of states from different protocols.
+fn gen_node(n1: &AdaptationNode, n2: &AdaptationNode) -> AdaptationNode {
+    AdaptationNode {
+        state: State::from(format!(
+            "{} || {}",
+            n1.state.state_name(),
+            n2.state.state_name()
+        )),
+        machine_states: n1
+            .machine_states
+            .iter()
+            .chain(n2.machine_states.iter())
+            .cloned()
+            .collect(),
+    }
+}
+
+// Node in the composition of a machine and the projection of the protocol it implements.
+fn gen_machine_node(n1: &AdaptationNode, n2: &AdaptationNode) -> AdaptationNode {
+    AdaptationNode {
+        state: State::from(format!(
+            "{} || {}",
+            n1.state.state_name(),
+            n2.state.state_name()
+        )),
+        machine_states: n1
+            .machine_states
+            .iter()
+            .zip(n2.machine_states.iter())
+            .map(|(m1, m2)| m1.clone().or_else(|| m2.clone()))
+            .collect(),
+    }
+}
+
+// Compose each machine with the projection of the protocol it implements and
+// combine the results with the projections of the other protocols.
+fn adapt(
+    projections: &[(AdaptationGraph, NodeId, BTreeSet<EventType>)],
+    machines: &BTreeMap<usize, (OptionGraph, NodeId)>,
+) -> Option<(AdaptationGraph, NodeId)> {
+    if machines.keys().any(|k| *k >= projections.len()) {
+        return None;
+    }
+
+    let projections = projections
+        .iter()
+        .enumerate()
+        .map(|(k, projection)| match machines.get(&k) {
+            Some(machine) => compose_machine_and_projection(machine, projection),
+            None => projection.clone(),
+        })
         .collect();
 
+    // should we minimize here? not done to keep original shape of input machine as much as possible?
+    projection::combine_projections(projections, gen_node)
+}
+
+fn compose_machine_and_projection(
+    machine: &(OptionGraph, NodeId),
+    projection: &(AdaptationGraph, NodeId, BTreeSet<EventType>),
+) -> (AdaptationGraph, NodeId, BTreeSet<EventType>) {
     let (machine, machine_initial) = (from_option_graph_to_graph(&machine.0), machine.1);
+    // commands are matched on their command and event types. the guard and timeout
+    // are those of the protocol, also if the machine leaves them out.
+    let projection_commands: BTreeMap<_, _> = projection
+        .0
+        .edge_weights()
+        .filter_map(|label| match label {
+            MachineLabel::Execute { cmd, log_type, .. } => {
+                Some(((cmd.clone(), log_type.clone()), label.clone()))
+            }
+            MachineLabel::Input { .. } => None,
+        })
+        .collect();
     let machine = machine.map(
         |_, n| AdaptationNode {
             state: n.clone(),
-            machine_states: Some(BTreeSet::from([n.clone()])),
+            machine_states: vec![Some(n.clone())],
+        },
+        |_, label| match label {
+            MachineLabel::Execute { cmd, log_type, .. } => projection_commands
+                .get(&(cmd.clone(), log_type.clone()))
+                .unwrap_or(label)
+                .clone(),
+            MachineLabel::Input { .. } => label.clone(),
         },
-        |_, label| label.clone(),
     );
     let machine_proj_intersect = machine
         .edge_references()
         .map(|e_ref| e_ref.weight().get_event_type())
         .collect::<BTreeSet<EventType>>()
         .intersection(
-            &projections[k]
+            &projection
                 .0
                 .edge_references()
                 .map(|e_ref| e_ref.weight().get_event_type())
@@ -154,16 +387,13 @@ fn adapted_projection(
         .cloned()
         .collect();
 
-    let ((machine_and_proj, machine_and_proj_initial), kth_interface) = (
-        composition::compose(
-            machine,
-            machine_initial,
-            projections[k].0.clone(),
-            projections[k].1,
-            machine_proj_intersect,
-            gen_node,
-        ),
-        projections[k].2.clone(),
+    let (machine_and_proj, machine_and_proj_initial) = composition::compose(
+        machine,
+        machine_initial,
+        projection.0.clone(),
+        projection.1,
+        machine_proj_intersect,
+        gen_machine_node,
     );
     let machine_and_proj = machine_and_proj.map(
         |_, n| AdaptationNode {
@@ -173,29 +403,15 @@ fn adapted_projection(
         |_, label| label.clone(),
     );
 
-    let projections = projections[..k]
-        .iter()
-        .cloned()
-        .chain([(machine_and_proj, machine_and_proj_initial, kth_interface)])
-        .chain(projections[k + 1..].iter().cloned())
-        .collect();
-
-    match projection::combine_projections(projections, gen_node) {
-        Some((combined_projection, combined_initial)) => {
-            Some((combined_projection, Some(combined_initial)))
-        } // should we minimize here? not done to keep original shape of input machine as much as possible?
-        None => None,
-    }
+    (
+        machine_and_proj,
+        machine_and_proj_initial,
+        projection.2.clone(),
+    )
 }
 
-fn paths_from_event_types(
-    proj: &OptionGraph,
-    proto_info: &ProtoInfo,
-    updating_event_types: &BTreeSet<EventType>,
-) -> BranchMap {
-    let _span = tracing::info_span!("paths_from_event_types").entered();
-    let mut m: BTreeMap<EventType, BTreeSet<EventType>> = BTreeMap::new();
-
+// The concurrent event types used when computing the `branches` function.
+fn branch_concurrent_events(proto_info: &ProtoInfo) -> BTreeSet<UnordEventPair> {
     // The reason for making set of concurrent events smaller is?
     let after_pairs: BTreeSet<UnordEventPair> =
         proto_info::transitive_closure_succeeding(proto_info.succeeding_events.clone())
@@ -208,11 +424,20 @@ fn paths_from_event_types(
             })
             .flatten()
             .collect();
-    let concurrent_events: BTreeSet<UnordEventPair> = proto_info
+    proto_info
         .concurrent_events
         .difference(&after_pairs)
         .cloned()
-        .collect();
+        .collect()
+}
+
+fn paths_from_event_types(
+    proj: &OptionGraph,
+    updating_event_types: &BTreeSet<EventType>,
+    concurrent_events: &BTreeSet<UnordEventPair>,
+) -> BranchMap {
+    let _span = tracing::info_span!("paths_from_event_types").entered();
+    let mut m: BTreeMap<EventType, BTreeSet<EventType>> = BTreeMap::new();
 
     for node in proj.node_indices() {
         for edge in proj.edges_directed(node, Outgoing) {
@@ -223,8 +448,8 @@ fn paths_from_event_types(
                         proj,
                         edge.target(),
                         &edge.weight().get_event_type(),
-                        &updating_event_types,
-                        &concurrent_events,
+                        updating_event_types,
+                        concurrent_events,
                     );
                     m.entry(edge.weight().get_event_type())
                         .and_modify(|s| s.append(&mut paths_this_edge))
@@ -270,6 +495,63 @@ fn visit_successors_stop_on_branch(
     event_types
 }
 
+// Compare the behaviour of two adapted machines by walking their determinized
+// versions in lockstep. Returns a description of the first difference found, if any.
+fn adaptation_difference(
+    left: &OptionGraph,
+    li: NodeId,
+    right: &OptionGraph,
+    ri: NodeId,
+) -> Option<String> {
+    let (left, li, _) = minimize::nfa_to_dfa(from_option_graph_to_graph(left), li);
+    let (right, ri, _) = minimize::nfa_to_dfa(from_option_graph_to_graph(right), ri);
+    let outgoing = |graph: &Graph, node: NodeId| -> BTreeMap<MachineLabel, NodeId> {
+        graph
+            .edges_directed(node, Outgoing)
+            .map(|e| (e.weight().clone(), e.target()))
+            .collect()
+    };
+    let trace_to_string = |trace: &Vec<MachineLabel>| {
+        if trace.is_empty() {
+            "in the initial state".to_string()
+        } else {
+            format!("after {}", trace.iter().join(", "))
+        }
+    };
+
+    // breadth first to report the shortest trace leading to a difference
+    let mut visited = BTreeSet::from([(li, ri)]);
+    let mut queue = VecDeque::from([(li, ri, vec![])]);
+    while let Some((l, r, trace)) = queue.pop_front() {
+        let l_out = outgoing(&left, l);
+        let r_out = outgoing(&right, r);
+        if let Some(label) = l_out.keys().find(|label| !r_out.contains_key(label)) {
+            return Some(format!(
+                "{} {} is only possible in the first",
+                trace_to_string(&trace),
+                label
+            ));
+        }
+        if let Some(label) = r_out.keys().find(|label| !l_out.contains_key(label)) {
+            return Some(format!(
+                "{} {} is only possible in the second",
+                trace_to_string(&trace),
+                label
+            ));
+        }
+        for (label, lt) in l_out {
+            let rt = r_out[&label];
+            if visited.insert((lt, rt)) {
+                let mut trace = trace.clone();
+                trace.push(label);
+                queue.push_back((lt, rt, trace));
+            }
+        }
+    }
+
+    None
+}
+
 fn from_option_graph_to_graph(graph: &OptionGraph) -> Graph {
     graph.map(
         |_, n| n.clone().unwrap_or_else(|| State::new("")),
@@ -289,7 +571,8 @@ mod tests {
     use crate::test_utils;
     use crate::types::proto_info;
     use crate::types::typescript_types::{
-        Command, Granularity, InterfacingProtocols, MachineType, State, Transition,
+        Command, Granularity, InterfacingProtocols, MachineType, State, SwarmProtocolType,
+        Transition,
     };
 
     #[test]
@@ -310,6 +593,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("1"),
                     target: State::new("1"),
@@ -343,6 +628,7 @@ mod tests {
                     target: State::new("3"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let mut expected_adapted_fl_m_1 = MachineType {
             initial: State::new("(0 || { { 0 } }) || { { 0 } }"),
@@ -358,6 +644,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("(1 || { { 1 } }) || { { 1 } }"),
                     target: State::new("(1 || { { 1 } }) || { { 1 } }"),
@@ -391,6 +679,7 @@ mod tests {
                     target: State::new("(3 || { { 3 } }) || { { 0 } }"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let mut expected_adapted_fl_m_2 = MachineType {
             initial: State::new("(0 || { { 0 } }) || { { 0 } } || { { 0 } }"),
@@ -406,6 +695,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("(1 || { { 1 } }) || { { 1 } } || { { 0 } }"),
                     target: State::new("(1 || { { 1 } }) || { { 1 } } || { { 0 } }"),
@@ -439,6 +730,7 @@ mod tests {
                     target: State::new("(3 || { { 3 } }) || { { 0 } } || { { 0 } }"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
 
         let (fl_m_graph, fl_m_graph_initial, _) = util::from_json(fl_m);
@@ -459,8 +751,7 @@ mod tests {
             &proto_info,
             &subs1,
             role.clone(),
-            (fl_m_graph.clone(), fl_m_graph_initial.unwrap()),
-            0,
+            BTreeMap::from([(0, (fl_m_graph.clone(), fl_m_graph_initial.unwrap()))]),
             true,
         );
         let (adapted_proj, adapted_proj_initial) = adapted.unwrap();
@@ -491,8 +782,7 @@ mod tests {
             &proto_info,
             &subs2,
             role.clone(),
-            (fl_m_graph.clone(), fl_m_graph_initial.unwrap()),
-            0,
+            BTreeMap::from([(0, (fl_m_graph.clone(), fl_m_graph_initial.unwrap()))]),
             true,
         );
         let (adapted_proj, adapted_proj_initial) = adapted.unwrap();
@@ -526,6 +816,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("build"),
                         log_type: vec![EventType::new("car")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("1"),
                     target: State::new("1"),
@@ -538,6 +830,7 @@ mod tests {
                     target: State::new("2"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let mut expected_adapted_f_m_1 = MachineType {
             initial: State::new("{ { 0 } } || (0 || { { 0 } })"),
@@ -560,6 +853,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("build"),
                         log_type: vec![EventType::new("car")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("{ { 0 } } || (1 || { { 2 } })"),
                     target: State::new("{ { 0 } } || (1 || { { 2 } })"),
@@ -589,6 +884,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("build"),
                         log_type: vec![EventType::new("car")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("{ { 3 } } || (1 || { { 2 } })"),
                     target: State::new("{ { 3 } } || (1 || { { 2 } })"),
@@ -608,6 +905,7 @@ mod tests {
                     target: State::new("{ { 3 } } || (0 || { { 0 } })"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let mut expected_adapted_f_m_2 = MachineType {
             initial: State::new("{ { 0 } } || (0 || { { 0 } }) || { { 0 } }"),
@@ -665,6 +963,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("build"),
                         log_type: vec![EventType::new("car")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("{ { 0 } } || (1 || { { 2 } }) || { { 1 } }"),
                     target: State::new("{ { 0 } } || (1 || { { 2 } }) || { { 1 } }"),
@@ -687,6 +987,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("build"),
                         log_type: vec![EventType::new("car")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("{ { 3 } } || (1 || { { 2 } }) || { { 1 } }"),
                     target: State::new("{ { 3 } } || (1 || { { 2 } }) || { { 1 } }"),
@@ -741,6 +1043,7 @@ mod tests {
                     target: State::new("{ { 3 } } || (1 || { { 2 } }) || { { 1 } }"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
 
         let (f_m_graph, f_m_graph_initial, _) = util::from_json(f_m);
@@ -761,8 +1064,7 @@ mod tests {
             &proto_info,
             &subs1,
             role.clone(),
-            (f_m_graph.clone(), f_m_graph_initial.unwrap()),
-            1,
+            BTreeMap::from([(1, (f_m_graph.clone(), f_m_graph_initial.unwrap()))]),
             true,
         );
         let (adapted_proj, adapted_proj_initial) = adapted.unwrap();
@@ -793,8 +1095,7 @@ mod tests {
             &proto_info,
             &subs2,
             role.clone(),
-            (f_m_graph.clone(), f_m_graph_initial.unwrap()),
-            1,
+            BTreeMap::from([(1, (f_m_graph.clone(), f_m_graph_initial.unwrap()))]),
             true,
         );
         let (adapted_proj, adapted_proj_initial) = adapted.unwrap();
@@ -828,6 +1129,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("1"),
                     target: State::new("1"),
@@ -861,6 +1164,7 @@ mod tests {
                     target: State::new("3"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
 
         let mut expected_proj = MachineType {
@@ -891,6 +1195,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("(1 || { { 1 } }) || { { 1 } }"),
                     target: State::new("(1 || { { 1 } }) || { { 1 } }"),
@@ -910,6 +1216,7 @@ mod tests {
                     target: State::new("(3 || { { 3 } }) || { { 2 } }"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
 
         let (fl_m_graph, fl_m_graph_initial, _) = util::from_json(fl_m);
@@ -940,27 +1247,27 @@ mod tests {
         let expected_proj_to_machine_states = BTreeMap::from([
             (
                 State::new("(0 || { { 0 } }) || { { 0 } }"),
-                vec![State::new("0")],
+                vec![Some(State::new("0")), None],
             ),
             (
                 State::new("(1 || { { 1 } }) || { { 1 } }"),
-                vec![State::new("1")],
+                vec![Some(State::new("1")), None],
             ),
             (
                 State::new("(2 || { { 0 } }) || { { 2 } }"),
-                vec![State::new("2")],
+                vec![Some(State::new("2")), None],
             ),
             (
                 State::new("(2 || { { 2 } }) || { { 1 } }"),
-                vec![State::new("2")],
+                vec![Some(State::new("2")), None],
             ),
             (
                 State::new("(3 || { { 3 } }) || { { 0 } }"),
-                vec![State::new("3")],
+                vec![Some(State::new("3")), None],
             ),
             (
                 State::new("(3 || { { 3 } }) || { { 2 } }"),
-                vec![State::new("3")],
+                vec![Some(State::new("3")), None],
             ),
         ]);
         let expected_branches = BTreeMap::from([
@@ -1015,6 +1322,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("1"),
                     target: State::new("1"),
@@ -1034,6 +1343,7 @@ mod tests {
                     target: State::new("3"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
 
         let mut expected_proj = MachineType {
@@ -1064,6 +1374,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("(1 || { { 1 } })"),
                     target: State::new("(1 || { { 1 } })"),
@@ -1076,6 +1388,7 @@ mod tests {
                     target: State::new("(0 || { { 0 } })"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
 
         let (fl_m_graph, fl_m_graph_initial, _) = util::from_json(fl_m.clone());
@@ -1104,10 +1417,10 @@ mod tests {
             Some(projection_info) => projection_info,
         };
         let expected_proj_to_machine_states = BTreeMap::from([
-            (State::new("(0 || { { 0 } })"), vec![State::new("0")]),
-            (State::new("(0 || { { 2 } })"), vec![State::new("0")]),
-            (State::new("(1 || { { 1 } })"), vec![State::new("1")]),
-            (State::new("(3 || { { 3 } })"), vec![State::new("3")]),
+            (State::new("(0 || { { 0 } })"), vec![Some(State::new("0"))]),
+            (State::new("(0 || { { 2 } })"), vec![Some(State::new("0"))]),
+            (State::new("(1 || { { 1 } })"), vec![Some(State::new("1"))]),
+            (State::new("(3 || { { 3 } })"), vec![Some(State::new("3"))]),
         ]);
         let expected_branches = BTreeMap::from([
             (
@@ -1150,4 +1463,339 @@ mod tests {
             projection_info.special_event_types
         );
     }
+
+    // project a single protocol over a role, giving a machine that correctly implements the role
+    fn machine_for(
+        proto: SwarmProtocolType,
+        subs: &Subscriptions,
+        role: Role,
+    ) -> (OptionGraph, NodeId) {
+        let proto_info = proto_info::swarms_to_proto_info(InterfacingProtocols(vec![proto]));
+        let (proj, proj_initial) = projection::project(
+            &proto_info.protocols[0].graph,
+            proto_info.protocols[0].initial.unwrap(),
+            subs,
+            role,
+            true,
+        );
+        (
+            proj.map(|_, n| Some(n.clone()), |_, l| l.clone()),
+            proj_initial,
+        )
+    }
+
+    #[test]
+    fn test_projection_information_batch_1() {
+        test_utils::setup_logger();
+        let swarms = test_utils::get_interfacing_swarms_1();
+        let subs = overapproximation::overapprox_well_formed_sub(
+            swarms.clone(),
+            &BTreeMap::new(),
+            Granularity::TwoStep,
+        )
+        .unwrap();
+        let proto_info = proto_info::swarms_to_proto_info(swarms);
+
+        let machines = BTreeMap::from([
+            (
+                (0, Role::new("T")),
+                machine_for(test_utils::get_proto1(), &subs, Role::new("T")),
+            ),
+            (
+                (1, Role::new("T")),
+                machine_for(test_utils::get_proto2(), &subs, Role::new("T")),
+            ),
+            (
+                (0, Role::new("FL")),
+                machine_for(test_utils::get_proto1(), &subs, Role::new("FL")),
+            ),
+            (
+                (1, Role::new("F")),
+                machine_for(test_utils::get_proto2(), &subs, Role::new("F")),
+            ),
+        ]);
+
+        let batch =
+            projection_information_batch(&proto_info, &subs, machines.clone(), true).unwrap();
+        assert_eq!(batch.len(), 4);
+        for ((k, role), machine) in machines {
+            let expected =
+                projection_information(&proto_info, &subs, role.clone(), machine, k, true).unwrap();
+            assert_eq!(batch[&(k, role)], expected);
+        }
+    }
+
+    #[test]
+    fn test_projection_information_batch_fail_1() {
+        test_utils::setup_logger();
+        let swarms = test_utils::get_interfacing_swarms_1();
+        let subs = overapproximation::overapprox_well_formed_sub(
+            swarms.clone(),
+            &BTreeMap::new(),
+            Granularity::TwoStep,
+        )
+        .unwrap();
+        let proto_info = proto_info::swarms_to_proto_info(swarms);
+
+        // transport machine for the second protocol that never delivers
+        let t_m = MachineType {
+            initial: State::new("0"),
+            transitions: vec![
+                Transition {
+                    label: MachineLabel::Execute {
+                        cmd: Command::new("request"),
+                        log_type: vec![EventType::new("partID")],
+                        guard: None,
+                        timeout: None,
+                    },
+                    source: State::new("0"),
+                    target: State::new("0"),
+                },
+                Transition {
+                    label: MachineLabel::Input {
+                        event_type: EventType::new("partID"),
+                    },
+                    source: State::new("0"),
+                    target: State::new("1"),
+                },
+                Transition {
+                    label: MachineLabel::Input {
+                        event_type: EventType::new("part"),
+                    },
+                    source: State::new("1"),
+                    target: State::new("2"),
+                },
+            ],
+            payloads: BTreeMap::new(),
+        };
+        let (t_m_graph, t_m_graph_initial, _) = util::from_json(t_m);
+
+        let machines = BTreeMap::from([
+            (
+                (0, Role::new("T")),
+                machine_for(test_utils::get_proto1(), &subs, Role::new("T")),
+            ),
+            ((1, Role::new("T")), (t_m_graph, t_m_graph_initial.unwrap())),
+        ]);
+        let errors = projection_information_batch(&proto_info, &subs, machines, true).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("inconsistent machines for role T in protocols 0 and 1:"));
+
+        let machines = BTreeMap::from([(
+            (2, Role::new("T")),
+            machine_for(test_utils::get_proto1(), &subs, Role::new("T")),
+        )]);
+        let errors = projection_information_batch(&proto_info, &subs, machines, true).unwrap_err();
+        assert_eq!(errors, vec!["invalid index 2 for role T".to_string()]);
+    }
+
+    #[test]
+    fn test_projection_information_machines_1() {
+        test_utils::setup_logger();
+        let swarms = test_utils::get_interfacing_swarms_1();
+        let subs = overapproximation::overapprox_well_formed_sub(
+            swarms.clone(),
+            &BTreeMap::new(),
+            Granularity::TwoStep,
+        )
+        .unwrap();
+        let proto_info = proto_info::swarms_to_proto_info(swarms);
+        let role = Role::new("T");
+        let (t_m_0, t_m_0_initial) = machine_for(test_utils::get_proto1(), &subs, role.clone());
+        let (t_m_1, t_m_1_initial) = machine_for(test_utils::get_proto2(), &subs, role.clone());
+
+        let (adapted, adapted_initial) = adapted_projection(
+            &proto_info,
+            &subs,
+            role.clone(),
+            BTreeMap::from([
+                (0, (t_m_0.clone(), t_m_0_initial)),
+                (1, (t_m_1.clone(), t_m_1_initial)),
+            ]),
+            true,
+        )
+        .unwrap();
+        let adapted_initial = adapted_initial.unwrap();
+
+        // every state tracks one original state per protocol
+        assert!(
+            adapted.node_weights().all(
+                |n| n.machine_states.len() == 2 && n.machine_states.iter().all(|m| m.is_some())
+            )
+        );
+        assert_eq!(
+            adapted[adapted_initial].machine_states,
+            vec![t_m_0[t_m_0_initial].clone(), t_m_1[t_m_1_initial].clone()]
+        );
+
+        // same behaviour as when only one of the machines is plugged in
+        let (adapted_0, adapted_0_initial) = adapted_projection(
+            &proto_info,
+            &subs,
+            role.clone(),
+            BTreeMap::from([(0, (t_m_0.clone(), t_m_0_initial))]),
+            true,
+        )
+        .unwrap();
+        assert!(
+            adapted_0
+                .node_weights()
+                .all(|n| n.machine_states.len() == 2 && n.machine_states[1].is_none())
+        );
+        assert_eq!(
+            adaptation_difference(
+                &from_adaptation_graph_to_option_graph(&adapted),
+                adapted_initial,
+                &from_adaptation_graph_to_option_graph(&adapted_0),
+                adapted_0_initial.unwrap()
+            ),
+            None
+        );
+
+        let projection_info = projection_information_machines(
+            &proto_info,
+            &subs,
+            role.clone(),
+            BTreeMap::from([
+                (0, (t_m_0.clone(), t_m_0_initial)),
+                (1, (t_m_1.clone(), t_m_1_initial)),
+            ]),
+            true,
+        )
+        .unwrap();
+        assert!(
+            projection_info
+                .proj_to_machine_states
+                .values()
+                .all(|states| states.len() == 2 && states.iter().all(|s| s.is_some()))
+        );
+
+        // the entries stay positional if only the second protocol has a machine
+        let projection_info = projection_information_machines(
+            &proto_info,
+            &subs,
+            role,
+            BTreeMap::from([(1, (t_m_1.clone(), t_m_1_initial))]),
+            true,
+        )
+        .unwrap();
+        assert!(
+            projection_info
+                .proj_to_machine_states
+                .values()
+                .all(|states| states.len() == 2 && states[0].is_none() && states[1].is_some())
+        );
+    }
+
+    #[test]
+    fn test_projection_information_projection_positional() {
+        test_utils::setup_logger();
+        let swarms = test_utils::get_interfacing_swarms_1();
+        let subs = overapproximation::overapprox_well_formed_sub(
+            swarms.clone(),
+            &BTreeMap::new(),
+            Granularity::TwoStep,
+        )
+        .unwrap();
+        let proto_info = proto_info::swarms_to_proto_info(swarms);
+        let projection_info =
+            projection_information_projection(&proto_info, &subs, Role::new("T"), true).unwrap();
+
+        // the projection is the machine of both protocols
+        assert!(
+            projection_info
+                .proj_to_machine_states
+                .iter()
+                .all(|(state, states)| *states == vec![Some(state.clone()); 2])
+        );
+    }
+
+    // machine_for with the guards and timeouts left out of the commands.
+    fn plain_machine_for(
+        proto: SwarmProtocolType,
+        subs: &Subscriptions,
+        role: Role,
+    ) -> (OptionGraph, NodeId) {
+        let (machine, initial) = machine_for(proto, subs, role);
+        let machine = machine.map(
+            |_, n| n.clone(),
+            |_, label| match label {
+                MachineLabel::Execute { cmd, log_type, .. } => MachineLabel::Execute {
+                    cmd: cmd.clone(),
+                    log_type: log_type.clone(),
+                    guard: None,
+                    timeout: None,
+                },
+                MachineLabel::Input { .. } => label.clone(),
+            },
+        );
+        (machine, initial)
+    }
+
+    #[test]
+    fn test_projection_information_guard_and_timeout() {
+        test_utils::setup_logger();
+        for (proto, role, cmd) in [
+            (test_utils::get_guarded_proto(), "S", "select"),
+            (test_utils::get_timed_proto(), "W", "cancel"),
+        ] {
+            let protos = InterfacingProtocols(vec![proto.clone()]);
+            let subs = overapproximation::overapprox_well_formed_sub(
+                protos.clone(),
+                &BTreeMap::new(),
+                Granularity::TwoStep,
+            )
+            .unwrap();
+            let proto_info = proto_info::swarms_to_proto_info(protos);
+            let role = Role::new(role);
+            let expected = proto
+                .transitions
+                .iter()
+                .find(|t| t.label.cmd == Command::new(cmd))
+                .unwrap();
+            let machine = plain_machine_for(proto.clone(), &subs, role.clone());
+
+            // The command keeps the guard or timeout of the protocol.
+            let projection_info =
+                projection_information(&proto_info, &subs, role, machine, 0, true).unwrap();
+            let commands: Vec<_> = projection_info
+                .projection
+                .transitions
+                .into_iter()
+                .filter_map(|t| match t.label {
+                    MachineLabel::Execute {
+                        cmd: c,
+                        guard,
+                        timeout,
+                        ..
+                    } if c == Command::new(cmd) => Some((guard, timeout)),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(
+                commands,
+                vec![(expected.label.guard.clone(), expected.label.timeout)]
+            );
+        }
+    }
+
+    #[test]
+    fn test_projection_information_payloads() {
+        test_utils::setup_logger();
+        let protos = test_utils::get_payload_protos();
+        let subs = overapproximation::overapprox_well_formed_sub(
+            protos.clone(),
+            &BTreeMap::new(),
+            Granularity::TwoStep,
+        )
+        .unwrap();
+        let proto_info = proto_info::swarms_to_proto_info(protos.clone());
+        let role = Role::new("W");
+        let machine = machine_for(protos.0[1].clone(), &subs, role.clone());
+
+        // The adapted machine of W only carries the schemas of the event types it handles.
+        let projection_info =
+            projection_information(&proto_info, &subs, role, machine, 1, true).unwrap();
+        assert_eq!(projection_info.projection.payloads, protos.0[1].payloads);
+    }
 }
@@ -13,7 +13,8 @@ use crate::types::{
 };
 
 // Minimize a machine using partition refinement.
-pub fn minimal_machine(graph: &Graph, i: NodeId) -> (Graph, NodeId) {
+// Also returns, for each node of the minimal machine (by index), the nodes of graph that were merged into it.
+pub fn minimal_machine(graph: &Graph, i: NodeId) -> (Graph, NodeId, Vec<BTreeSet<NodeId>>) {
     let _span = tracing::info_span!("minimal_machine").entered();
     let partition = partition_refinement(graph);
     let mut minimal = Graph::new();
@@ -35,8 +36,10 @@ pub fn minimal_machine(graph: &Graph, i: NodeId) -> (Graph, NodeId) {
         );
     }
 
+    let mut origin = Vec::new();
     for block in &partition {
         partition_to_minimal_graph_node.insert(block, minimal.add_node(state_name(block)));
+        origin.push(block.clone());
     }
     for node in graph.node_indices() {
         for edge in graph.edges_directed(node, Outgoing) {
@@ -49,7 +52,7 @@ pub fn minimal_machine(graph: &Graph, i: NodeId) -> (Graph, NodeId) {
         }
     }
     let initial = partition_to_minimal_graph_node[node_to_partition[&i]];
-    (minimal, initial)
+    (minimal, initial, origin)
 }
 
 fn partition_refinement(graph: &Graph) -> BTreeSet<BTreeSet<NodeId>> {
@@ -117,7 +120,8 @@ fn refine_block(
 
 // Nfa to dfa using subset construction. Hopcroft, Motwani and Ullman section 2.3.5.
 // Not strictly related to minimizing. But here anyway. Transforms a projection.
-pub fn nfa_to_dfa(nfa: Graph, i: NodeId) -> (Graph, NodeId) {
+// Also returns, for each node of the dfa (by index), the set of nfa nodes it was constructed from.
+pub fn nfa_to_dfa(nfa: Graph, i: NodeId) -> (Graph, NodeId, Vec<BTreeSet<NodeId>>) {
     let _span = tracing::info_span!("nfa_to_dfa").entered();
     let mut dfa = Graph::new();
     // maps vectors of NodeIds from the nfa to a NodeId in the new dfa
@@ -173,5 +177,10 @@ pub fn nfa_to_dfa(nfa: Graph, i: NodeId) -> (Graph, NodeId) {
         }
     }
 
-    (dfa, dfa_nodes[&BTreeSet::from([i])])
+    let mut origin = vec![BTreeSet::new(); dfa.node_count()];
+    for (nfa_nodes, dfa_node) in &dfa_nodes {
+        origin[dfa_node.index()] = nfa_nodes.clone();
+    }
+
+    (dfa, dfa_nodes[&BTreeSet::from([i])], origin)
 }
@@ -1,25 +1,29 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 use petgraph::{
     Direction::{Incoming, Outgoing},
-    graph::EdgeReference,
     visit::{EdgeFiltered, EdgeRef, IntoEdgeReferences, IntoEdgesDirected, IntoNodeReferences},
 };
 
 use crate::{
     machine::minimize,
     types::{
-        projection::{ChainedProjections, ChainedProtos, OptionGraph},
+        projection::{
+            ChainedProjections, ChainedProtos, OptionGraph, ProvenanceGraph, ProvenanceNode,
+        },
         proto_info::{ProtoInfo, ProtoStruct},
-        typescript_types::EventType,
+        typescript_types::{EventType, ProjToProtoStates},
     },
 };
 
 use crate::composition;
+use crate::lazy_composition::LazyComposition;
 use crate::types::{
     projection::Graph,
     proto_graph::NodeId,
-    typescript_types::{EventLabel, MachineLabel, Role, StateName, Subscriptions, SwarmLabel},
+    typescript_types::{
+        EventLabel, MachineLabel, Role, State, StateName, Subscriptions, SwarmLabel,
+    },
 };
 
 // Edge reference for graphs representing protocols. Used when filtering out edges that are not to be included in projection.
@@ -34,12 +38,47 @@ pub fn project(
     role: Role,
     minimize: bool,
 ) -> (Graph, NodeId) {
+    let (machine, machine_initial, _) = project_with_origin(swarm, initial, subs, role, minimize);
+    (machine, machine_initial)
+}
+
+// Same as project, but each state of the projection is annotated with the protocol states it represents:
+// the states it was projected from and the states reachable from these through transitions not subscribed to by role.
+pub fn project_with_provenance(
+    swarm: &crate::types::proto_graph::Graph,
+    initial: NodeId,
+    subs: &Subscriptions,
+    role: Role,
+    minimize: bool,
+) -> (ProvenanceGraph, NodeId) {
+    let (machine, machine_initial, origin) =
+        project_with_origin(swarm, initial, subs, role, minimize);
+    let machine = machine.map(
+        |n, state| ProvenanceNode {
+            state: state.clone(),
+            proto_states: vec![
+                origin[n.index()]
+                    .iter()
+                    .map(|swarm_node| swarm[*swarm_node].state_name().clone())
+                    .collect(),
+            ],
+        },
+        |_, label| label.clone(),
+    );
+    (machine, machine_initial)
+}
+
+// Projection of swarm over role. Also returns, for each node of the projection (by index), the nodes of swarm it represents.
+pub(crate) fn project_with_origin(
+    swarm: &crate::types::proto_graph::Graph,
+    initial: NodeId,
+    subs: &Subscriptions,
+    role: Role,
+    minimize: bool,
+) -> (Graph, NodeId, Vec<BTreeSet<NodeId>>) {
     let _span = tracing::info_span!("project", %role).entered();
-    let mut machine = Graph::new();
     let sub = BTreeSet::new();
     let sub = subs.get(&role).unwrap_or(&sub);
-    // need to keep track of corresponding machine node for each swarm node. maps nodes in protocol to nodes in projection
-    let mut m_nodes: Vec<NodeId> = vec![NodeId::end(); swarm.node_count()];
 
     let interested = |edge: ERef| sub.contains(&edge.weight().get_event_type());
     let filtered = EdgeFiltered(swarm, interested);
@@ -51,61 +90,131 @@ pub fn project(
         .map(|(ni, _)| ni)
         .collect();
 
-    // add the nodes identified above
-    for node in nodes_in_proj.iter() {
-        m_nodes[node.index()] = machine.add_node(swarm[*node].state_name().clone());
+    let (machine, m_nodes, origin) = project_edges(
+        nodes_in_proj,
+        |node| swarm.edges_directed(node, Outgoing),
+        |node| swarm[node].state_name().clone(),
+        sub,
+        &role,
+    );
+
+    if minimize {
+        // make deterministic.
+        let (dfa, dfa_initial, dfa_origin) = minimize::nfa_to_dfa(machine, m_nodes[&initial]);
+        // when minimizing we get a machine that is a equivalent to the one prescribed by the projection operator formally, but minimal.
+        let (minimal, minimal_initial, minimal_origin) =
+            minimize::minimal_machine(&dfa, dfa_initial);
+        // trace the nodes of the minimal machine back to nodes in the protocol.
+        let origin = minimal_origin
+            .into_iter()
+            .map(|dfa_nodes| {
+                dfa_nodes
+                    .into_iter()
+                    .flat_map(|dfa_node| dfa_origin[dfa_node.index()].iter())
+                    .flat_map(|nfa_node| origin[nfa_node.index()].iter().cloned())
+                    .collect()
+            })
+            .collect();
+        (minimal, minimal_initial, origin)
+    } else {
+        (machine, m_nodes[&initial], origin)
     }
+}
 
-    let find_interesting_edges = |node: NodeId| -> Vec<EdgeReference<'_, SwarmLabel>> {
-        let mut stack: Vec<NodeId> = vec![node];
-        let mut visited: BTreeSet<NodeId> = BTreeSet::from([node]);
-        let mut interesting_edges: Vec<EdgeReference<'_, SwarmLabel>> = vec![];
+// Projection over role of the composition explored by view. Explores the composition from
+// the states of the projection only, without building the composed graph. Ignores any
+// reduction of view since the projection needs all interleavings.
+pub fn project_composition(
+    view: &LazyComposition,
+    subs: &Subscriptions,
+    role: Role,
+    minimize: bool,
+) -> (Graph, NodeId) {
+    let _span = tracing::info_span!("project_composition", %role).entered();
+    let sub = BTreeSet::new();
+    let sub = subs.get(&role).unwrap_or(&sub);
+    let (machine, m_nodes, _) = project_edges(
+        vec![view.initial()],
+        |node| view.all_successors(node),
+        |node| view.state_name(node),
+        sub,
+        &role,
+    );
+    let initial = m_nodes[&view.initial()];
+
+    if minimize {
+        let (dfa, dfa_initial, _) = minimize::nfa_to_dfa(machine, initial);
+        let (minimal, minimal_initial, _) = minimize::minimal_machine(&dfa, dfa_initial);
+        (minimal, minimal_initial)
+    } else {
+        (machine, initial)
+    }
+}
+
+// The projection over role, before minimizing, of a protocol whose transitions out of a state are given by edges.
+// Contains a node for each of nodes and for the target of each transition subscribed to by role that is
+// reachable from a node of the projection through transitions not subscribed to by role.
+// Also returns the node of the projection of each protocol state projected and, for each node
+// of the projection (by index), the protocol states it represents.
+fn project_edges<N, E, I>(
+    nodes: Vec<N>,
+    edges: impl Fn(N) -> I,
+    state_name: impl Fn(N) -> State,
+    sub: &BTreeSet<EventType>,
+    role: &Role,
+) -> (Graph, BTreeMap<N, NodeId>, Vec<BTreeSet<N>>)
+where
+    N: Copy + Ord,
+    E: EdgeRef<NodeId = N, Weight = SwarmLabel>,
+    I: IntoIterator<Item = E>,
+{
+    let mut machine = Graph::new();
+    // need to keep track of corresponding machine node for each protocol node.
+    let mut m_nodes: BTreeMap<N, NodeId> = BTreeMap::new();
+    let mut worklist: VecDeque<N> = VecDeque::new();
+    let mut add_node = |machine: &mut Graph, worklist: &mut VecDeque<N>, node: N| {
+        *m_nodes.entry(node).or_insert_with(|| {
+            worklist.push_back(node);
+            machine.add_node(state_name(node))
+        })
+    };
+    for node in nodes {
+        add_node(&mut machine, &mut worklist, node);
+    }
 
+    let mut origin = vec![];
+    while let Some(node) = worklist.pop_front() {
+        let source = add_node(&mut machine, &mut worklist, node);
+        // the edges subscribed to by role that are reachable from node through edges not subscribed to by role.
+        // the nodes visited on the way are the nodes represented by node in the projection.
+        let mut stack = vec![node];
+        let mut visited = BTreeSet::from([node]);
         while let Some(n) = stack.pop() {
-            for edge in swarm.edges_directed(n, Outgoing) {
-                if sub.contains(&edge.weight().get_event_type()) {
-                    interesting_edges.push(edge);
-                } else {
-                    if !visited.contains(&edge.target()) {
+            for edge in edges(n) {
+                let event_type = edge.weight().get_event_type();
+                if !sub.contains(&event_type) {
+                    if visited.insert(edge.target()) {
                         stack.push(edge.target());
-                        visited.insert(edge.target());
                     }
+                    continue;
                 }
+                if edge.weight().role == *role {
+                    let execute_label = MachineLabel::Execute {
+                        cmd: edge.weight().cmd.clone(),
+                        log_type: vec![event_type.clone()],
+                        guard: edge.weight().guard.clone(),
+                        timeout: edge.weight().timeout,
+                    };
+                    machine.add_edge(source, source, execute_label);
+                }
+                let target = add_node(&mut machine, &mut worklist, edge.target());
+                machine.add_edge(source, target, MachineLabel::Input { event_type });
             }
         }
-
-        interesting_edges
-    };
-
-    for node in nodes_in_proj {
-        let interesting_edges: Vec<_> = find_interesting_edges(node);
-        for edge in interesting_edges {
-            if edge.weight().role == role {
-                let execute_label = MachineLabel::Execute {
-                    cmd: edge.weight().cmd.clone(),
-                    log_type: vec![edge.weight().get_event_type()],
-                };
-                machine.add_edge(m_nodes[node.index()], m_nodes[node.index()], execute_label);
-            }
-            let input_label = MachineLabel::Input {
-                event_type: edge.weight().get_event_type(),
-            };
-            machine.add_edge(
-                m_nodes[node.index()],
-                m_nodes[edge.target().index()],
-                input_label,
-            );
-        }
+        origin.push(visited);
     }
 
-    if minimize {
-        // make deterministic.
-        let (dfa, dfa_initial) = minimize::nfa_to_dfa(machine, m_nodes[initial.index()]);
-        // when minimizing we get a machine that is a equivalent to the one prescribed by the projection operator formally, but minimal.
-        minimize::minimal_machine(&dfa, dfa_initial)
-    } else {
-        (machine, m_nodes[initial.index()])
-    }
+    (machine, m_nodes, origin)
 }
 
 // Map the protocols of a proto_info to a ChainedProtos
@@ -181,6 +290,53 @@ pub fn project_combine(
     }
 }
 
+// Same as project_combine, but each state of the combined projection is annotated with
+// the states of each protocol in proto_info it represents.
+pub fn project_combine_with_provenance(
+    proto_info: &ProtoInfo,
+    subs: &Subscriptions,
+    role: Role,
+    minimize: bool,
+) -> (ProvenanceGraph, Option<NodeId>) {
+    let _span = tracing::info_span!("project_combine_with_provenance", %role).entered();
+
+    let projections: Vec<_> = to_chained_protos(proto_info)
+        .into_iter()
+        .map(|(graph, initial, interface)| {
+            let (projection, projection_initial) =
+                project_with_provenance(&graph, initial, subs, role.clone(), minimize);
+            (projection, projection_initial, interface)
+        })
+        .collect();
+
+    match combine_projections(projections, gen_provenance_node) {
+        Some((combined_projection, combined_initial)) => {
+            (combined_projection, Some(combined_initial))
+        }
+        None => (ProvenanceGraph::new(), Some(NodeId::end())),
+    }
+}
+
+fn gen_provenance_node(n1: &ProvenanceNode, n2: &ProvenanceNode) -> ProvenanceNode {
+    ProvenanceNode {
+        state: composition::gen_state_name(&n1.state, &n2.state),
+        proto_states: [n1.proto_states.clone(), n2.proto_states.clone()].concat(),
+    }
+}
+
+// Map each state of a projection to the protocol states it represents.
+pub fn to_proj_to_proto_states(graph: &ProvenanceGraph) -> ProjToProtoStates {
+    graph
+        .node_weights()
+        .map(|n| (n.state.clone(), n.proto_states.clone()))
+        .collect()
+}
+
+// Forget the protocol states of a projection.
+pub fn from_provenance_graph(graph: &ProvenanceGraph) -> Graph {
+    graph.map(|_, n| n.state.clone(), |_, label| label.clone())
+}
+
 pub(crate) fn combine_projections<N: Clone, E: Clone + EventLabel>(
     projections: Vec<(petgraph::Graph<N, E>, NodeId, BTreeSet<EventType>)>,
     gen_node: fn(&N, &N) -> N,
@@ -211,7 +367,7 @@ mod tests {
     use crate::machine::util;
     use crate::subscription::{exact, overapproximation};
     use crate::types::typescript_types::{
-        Command, Granularity, InterfacingProtocols, MachineType, State, Transition,
+        Command, DataGuard, Granularity, InterfacingProtocols, MachineType, State, Transition,
     };
     use crate::types::{proto_graph, proto_info};
     use crate::{test_utils, types::typescript_types::SwarmProtocolType};
@@ -270,6 +426,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("build"),
                         log_type: vec![EventType::new("car")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("3"),
                     target: State::new("3"),
@@ -282,6 +440,7 @@ mod tests {
                     target: State::new("4"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         proj_machine.transitions.sort();
         expected_machine.transitions.sort();
@@ -296,6 +455,7 @@ mod tests {
         let result_subs = exact::exact_well_formed_sub(
             InterfacingProtocols(vec![proto.clone()]),
             &BTreeMap::new(),
+            usize::MAX,
         );
         assert!(result_subs.is_ok());
         let subs = result_subs.unwrap();
@@ -317,6 +477,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("get"),
                         log_type: vec![EventType::new("pos")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("1"),
                     target: State::new("1"),
@@ -350,6 +512,7 @@ mod tests {
                     target: State::new("3"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         proj_machine.transitions.sort();
         expected_machine.transitions.sort();
@@ -364,6 +527,7 @@ mod tests {
         let result_subs = exact::exact_well_formed_sub(
             InterfacingProtocols(vec![proto.clone()]),
             &BTreeMap::new(),
+            usize::MAX,
         );
         assert!(result_subs.is_ok());
         let subs = result_subs.unwrap();
@@ -385,6 +549,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("build"),
                         log_type: vec![EventType::new("car")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("2"),
                     target: State::new("2"),
@@ -397,12 +563,85 @@ mod tests {
                     target: State::new("3"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         proj_machine.transitions.sort();
         expected_machine.transitions.sort();
         assert_eq!(proj_machine, expected_machine);
     }
 
+    // The commands a machine executes together with their guards and timeouts.
+    fn executes(machine: MachineType) -> BTreeSet<(Command, Option<DataGuard>, Option<u64>)> {
+        machine
+            .transitions
+            .into_iter()
+            .filter_map(|t| match t.label {
+                MachineLabel::Execute {
+                    cmd,
+                    guard,
+                    timeout,
+                    ..
+                } => Some((cmd, guard, timeout)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_projection_data_guard() {
+        test_utils::setup_logger();
+        let proto = test_utils::get_guarded_proto();
+        let select = (
+            Command::new("select"),
+            proto.transitions[2].label.guard.clone(),
+            None,
+        );
+        let protos = InterfacingProtocols(vec![proto.clone()]);
+        let subs =
+            exact::exact_well_formed_sub(protos.clone(), &BTreeMap::new(), usize::MAX).unwrap();
+        let role = Role::new("S");
+        let (g, i, _) = proto_graph::from_json(proto);
+        let (proj, proj_initial) = project(&g, i.unwrap(), &subs, role.clone(), true);
+        assert!(executes(util::to_json_machine(proj, proj_initial)).contains(&select));
+
+        let proto_info = proto_info::swarms_to_proto_info(protos);
+        let (proj, proj_initial) = project_combine(&proto_info, &subs, role, true);
+        assert!(
+            executes(util::option_to_json_machine(proj, proj_initial.unwrap())).contains(&select)
+        );
+    }
+
+    #[test]
+    fn test_projection_timeout() {
+        test_utils::setup_logger();
+        let proto = test_utils::get_timed_proto();
+        let protos = InterfacingProtocols(vec![proto.clone()]);
+        let subs =
+            exact::exact_well_formed_sub(protos.clone(), &BTreeMap::new(), usize::MAX).unwrap();
+        // The timeout becomes a command of W carrying the timeout, T only reacts to the cancellation.
+        let proto_info = proto_info::swarms_to_proto_info(protos);
+        let (proj, proj_initial) = project_combine(&proto_info, &subs, Role::new("W"), true);
+        let w = util::option_to_json_machine(proj, proj_initial.unwrap());
+        assert_eq!(
+            executes(w.clone()),
+            BTreeSet::from([
+                (Command::new("cancel"), None, Some(300000)),
+                (Command::new("request"), None, None),
+                (Command::new("store"), None, None),
+            ])
+        );
+        assert!(
+            w.transitions
+                .iter()
+                .any(|t| t.label.to_string() == "cancel/cancelled after 300000ms")
+        );
+        let (proj, proj_initial) = project_combine(&proto_info, &subs, Role::new("T"), true);
+        assert_eq!(
+            executes(util::option_to_json_machine(proj, proj_initial.unwrap())),
+            BTreeSet::from([(Command::new("deliver"), None, None)])
+        );
+    }
+
     #[test]
     fn test_projection_4() {
         test_utils::setup_logger();
@@ -427,6 +666,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("request"),
                         log_type: vec![EventType::new("partID")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("0 || 0"),
                     target: State::new("0 || 0"),
@@ -456,6 +697,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("deliver"),
                         log_type: vec![EventType::new("part")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("2 || 1"),
                     target: State::new("2 || 1"),
@@ -496,12 +739,109 @@ mod tests {
                     target: State::new("3 || 3"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         proj_machine.transitions.sort();
         expected_machine.transitions.sort();
         assert_eq!(proj_machine, expected_machine);
     }
 
+    #[test]
+    fn test_projection_provenance_1() {
+        test_utils::setup_logger();
+        let proto = serde_json::from_str::<SwarmProtocolType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "source": "0", "target": "1", "label": { "cmd": "request", "logType": ["tireID"], "role": "C" } },
+                    { "source": "1", "target": "2", "label": { "cmd": "retrieve", "logType": ["position"], "role": "W" } },
+                    { "source": "2", "target": "3", "label": { "cmd": "receive", "logType": ["tire"], "role": "C" } },
+                    { "source": "3", "target": "4", "label": { "cmd": "build", "logType": ["car"], "role": "F" } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let subs = serde_json::from_str::<Subscriptions>(
+            r#"{
+            "C":["tireID","position","tire","car"],
+            "W":["tireID","position","tire"],
+            "F":["tireID","tire","car"]
+        }"#,
+        )
+        .unwrap();
+
+        let states =
+            |names: &[&str]| -> BTreeSet<State> { names.iter().map(|n| State::new(n)).collect() };
+        let (g, i, _) = proto_graph::from_json(proto);
+
+        // F does not subscribe to position, so the state 1 of the projection also represents protocol state 2.
+        let (proj, _) = project_with_provenance(&g, i.unwrap(), &subs, Role::new("F"), false);
+        let expected = BTreeMap::from([
+            (State::new("0"), vec![states(&["0"])]),
+            (State::new("1"), vec![states(&["1", "2"])]),
+            (State::new("3"), vec![states(&["3"])]),
+            (State::new("4"), vec![states(&["4"])]),
+        ]);
+        assert_eq!(to_proj_to_proto_states(&proj), expected);
+
+        // Minimization merges the states of the projection and their protocol states.
+        let (proj, proj_initial) =
+            project_with_provenance(&g, i.unwrap(), &subs, Role::new("F"), true);
+        let proj_to_proto_states = to_proj_to_proto_states(&proj);
+        assert_eq!(
+            proj_to_proto_states[&proj[proj_initial].state],
+            vec![states(&["0"])]
+        );
+        let all_proto_states: BTreeSet<State> = proj_to_proto_states
+            .values()
+            .flat_map(|sets| sets[0].iter().cloned())
+            .collect();
+        assert_eq!(all_proto_states, states(&["0", "1", "2", "3", "4"]));
+    }
+
+    #[test]
+    fn test_projection_provenance_2() {
+        test_utils::setup_logger();
+        let protos = test_utils::get_interfacing_swarms_1();
+        let subs = overapproximation::overapprox_well_formed_sub(
+            protos.clone(),
+            &BTreeMap::from([(Role::new("T"), BTreeSet::from([EventType::new("car")]))]),
+            Granularity::TwoStep,
+        )
+        .unwrap();
+        let proto_info = proto_info::swarms_to_proto_info(protos);
+        let (proj, proj_initial) =
+            project_combine_with_provenance(&proto_info, &subs, Role::new("T"), false);
+        let (option_proj, _) = project_combine(&proto_info, &subs, Role::new("T"), false);
+
+        // Same machine as project_combine.
+        let mut proj_machine =
+            util::to_json_machine(from_provenance_graph(&proj), proj_initial.unwrap());
+        let mut expected_machine = util::option_to_json_machine(option_proj, proj_initial.unwrap());
+        proj_machine.transitions.sort();
+        expected_machine.transitions.sort();
+        assert_eq!(proj_machine, expected_machine);
+
+        // One set of protocol states per protocol in the composition.
+        let proj_to_proto_states = to_proj_to_proto_states(&proj);
+        assert_eq!(proj_to_proto_states.len(), proj.node_count());
+        assert!(proj_to_proto_states.values().all(|sets| sets.len() == 2));
+        assert_eq!(
+            proj_to_proto_states[&State::new("0 || 0")],
+            vec![
+                BTreeSet::from([State::new("0")]),
+                BTreeSet::from([State::new("0")])
+            ]
+        );
+        assert_eq!(
+            proj_to_proto_states[&State::new("3 || 2")],
+            vec![
+                BTreeSet::from([State::new("3")]),
+                BTreeSet::from([State::new("2")])
+            ]
+        );
+    }
+
     #[test]
     fn test_compose_zero() {
         let left = MachineType {
@@ -518,6 +858,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("cmd_a"),
                         log_type: vec![EventType::new("a")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("left_0"),
                     target: State::new("left_0"),
@@ -533,11 +875,14 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("cmd_b"),
                         log_type: vec![EventType::new("b")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("left_1"),
                     target: State::new("left_1"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let right = MachineType {
             initial: State::new("right_0"),
@@ -553,6 +898,8 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("cmd_b"),
                         log_type: vec![EventType::new("b")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("right_0"),
                     target: State::new("right_0"),
@@ -568,11 +915,14 @@ mod tests {
                     label: MachineLabel::Execute {
                         cmd: Command::new("cmd_a"),
                         log_type: vec![EventType::new("a")],
+                        guard: None,
+                        timeout: None,
                     },
                     source: State::new("right_1"),
                     target: State::new("right_1"),
                 },
             ],
+            payloads: BTreeMap::new(),
         };
         let from_option_graph_to_graph = |graph: &OptionGraph| -> Graph {
             graph.map(
@@ -598,6 +948,7 @@ mod tests {
         let expected = MachineType {
             initial: State::new("right_0 || left_0"),
             transitions: vec![],
+            payloads: BTreeMap::new(),
         };
 
         assert_eq!(combined, expected);
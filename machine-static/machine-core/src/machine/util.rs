@@ -1,9 +1,9 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::types::{
     projection::{Graph, OptionGraph},
     proto_graph::NodeId,
-    typescript_types::{MachineLabel, MachineType, State, Transition},
+    typescript_types::{EventType, MachineLabel, MachineType, PayloadSchema, State, Transition},
 };
 use petgraph::{graph::EdgeReference, visit::EdgeRef};
 
@@ -52,6 +52,7 @@ pub fn to_json_machine(graph: Graph, initial: NodeId) -> MachineType {
     MachineType {
         initial: graph[initial].clone(),
         transitions,
+        payloads: BTreeMap::new(),
     }
 }
 
@@ -81,5 +82,27 @@ pub fn option_to_json_machine(
     MachineType {
         initial: graph[initial].clone().unwrap_or(State::from("")),
         transitions,
+        payloads: BTreeMap::new(),
     }
 }
+
+// Attach the payload schemas of the event types machine emits or reacts to.
+pub fn with_payloads(
+    mut machine: MachineType,
+    payloads: &BTreeMap<EventType, PayloadSchema>,
+) -> MachineType {
+    let event_types: BTreeSet<EventType> = machine
+        .transitions
+        .iter()
+        .flat_map(|t| match &t.label {
+            MachineLabel::Execute { log_type, .. } => log_type.clone(),
+            MachineLabel::Input { event_type } => vec![event_type.clone()],
+        })
+        .collect();
+    machine.payloads = payloads
+        .iter()
+        .filter(|(t, _)| event_types.contains(*t))
+        .map(|(t, schema)| (t.clone(), schema.clone()))
+        .collect();
+    machine
+}
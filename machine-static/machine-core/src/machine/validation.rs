@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::errors::MachineError;
+use crate::types::typescript_types::{
+    EventLabel, EventType, MachineLabel, MachineType, Role, State, Subscriptions,
+};
+
+// Check the shape of a machine implementing role before it is used for
+// projection, adaptation or equivalence checking.
+// Reports non-deterministic reactions and commands, duplicate transitions,
+// states that can not be reached from the initial state and event types
+// the machine reacts to but that are not in the subscription of role.
+pub fn validate_machine(
+    machine: &MachineType,
+    role: &Role,
+    subs: &Subscriptions,
+) -> Vec<MachineError> {
+    let _span = tracing::info_span!("validate_machine", %role).entered();
+    let mut errors = validate_machine_structure(machine);
+
+    let empty = BTreeSet::new();
+    let sub = subs.get(role).unwrap_or(&empty);
+    let not_subscribed: BTreeSet<EventType> = machine
+        .transitions
+        .iter()
+        .filter(|t| matches!(t.label, MachineLabel::Input { .. }))
+        .map(|t| t.label.get_event_type())
+        .filter(|event_type| !sub.contains(event_type))
+        .collect();
+    errors.extend(
+        not_subscribed
+            .into_iter()
+            .map(|event_type| MachineError::EventTypeNotSubscribed(event_type, role.clone())),
+    );
+
+    errors
+}
+
+// The checks of validate_machine that do not depend on a role and its subscription.
+pub fn validate_machine_structure(machine: &MachineType) -> Vec<MachineError> {
+    let mut errors = vec![];
+
+    let mut seen: BTreeSet<(&State, &MachineLabel, &State)> = BTreeSet::new();
+    let mut reactions: BTreeMap<(&State, &EventType), BTreeSet<&State>> = BTreeMap::new();
+    let mut commands: BTreeMap<&State, BTreeMap<_, BTreeSet<&MachineLabel>>> = BTreeMap::new();
+    let mut successors: BTreeMap<&State, BTreeSet<&State>> = BTreeMap::new();
+    for t in &machine.transitions {
+        if !seen.insert((&t.source, &t.label, &t.target)) {
+            errors.push(MachineError::DuplicateTransition(
+                t.source.clone(),
+                t.label.clone(),
+                t.target.clone(),
+            ));
+            continue;
+        }
+        successors.entry(&t.source).or_default().insert(&t.target);
+        successors.entry(&t.target).or_default();
+        match &t.label {
+            MachineLabel::Execute { cmd, .. } => {
+                commands
+                    .entry(&t.source)
+                    .or_default()
+                    .entry(cmd)
+                    .or_default()
+                    .insert(&t.label);
+            }
+            MachineLabel::Input { event_type } => {
+                reactions
+                    .entry((&t.source, event_type))
+                    .or_default()
+                    .insert(&t.target);
+            }
+        }
+    }
+
+    for ((state, event_type), targets) in reactions.iter() {
+        if targets.len() > 1 {
+            errors.push(MachineError::NonDeterministicInput(
+                (*state).clone(),
+                (*event_type).clone(),
+            ));
+        }
+    }
+    for (state, cmds) in commands {
+        for (cmd, labels) in cmds {
+            if labels.len() > 1 {
+                errors.push(MachineError::NonDeterministicCommand(
+                    state.clone(),
+                    cmd.clone(),
+                ));
+            }
+        }
+    }
+
+    // an initial state without transitions is reported when the machine is read
+    if successors.contains_key(&machine.initial) {
+        let mut visited = BTreeSet::from([&machine.initial]);
+        let mut stack = vec![&machine.initial];
+        while let Some(state) = stack.pop() {
+            for target in &successors[state] {
+                if visited.insert(*target) {
+                    stack.push(*target);
+                }
+            }
+        }
+        errors.extend(
+            successors
+                .keys()
+                .filter(|state| !visited.contains(*state))
+                .map(|state| MachineError::StateUnreachable((*state).clone())),
+        );
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn get_machine() -> MachineType {
+        serde_json::from_str::<MachineType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "label": { "tag": "Execute", "cmd": "request", "logType": ["partID"] }, "source": "0", "target": "0" },
+                    { "label": { "tag": "Input", "eventType": "partID" }, "source": "0", "target": "1" },
+                    { "label": { "tag": "Input", "eventType": "pos" }, "source": "1", "target": "2" },
+                    { "label": { "tag": "Execute", "cmd": "deliver", "logType": ["part"] }, "source": "2", "target": "2" },
+                    { "label": { "tag": "Input", "eventType": "part" }, "source": "2", "target": "0" },
+                    { "label": { "tag": "Input", "eventType": "time" }, "source": "0", "target": "3" }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn get_subs() -> Subscriptions {
+        BTreeMap::from([(
+            Role::new("T"),
+            BTreeSet::from([
+                EventType::new("partID"),
+                EventType::new("pos"),
+                EventType::new("part"),
+                EventType::new("time"),
+            ]),
+        )])
+    }
+
+    #[test]
+    fn test_validate_machine_ok() {
+        test_utils::setup_logger();
+        assert_eq!(
+            validate_machine(&get_machine(), &Role::new("T"), &get_subs()),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_validate_machine_fail() {
+        test_utils::setup_logger();
+        let mut machine = get_machine();
+        let extra = serde_json::from_str::<MachineType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "label": { "tag": "Input", "eventType": "pos" }, "source": "1", "target": "2" },
+                    { "label": { "tag": "Input", "eventType": "pos" }, "source": "1", "target": "3" },
+                    { "label": { "tag": "Execute", "cmd": "deliver", "logType": ["done"] }, "source": "2", "target": "2" },
+                    { "label": { "tag": "Input", "eventType": "car" }, "source": "4", "target": "0" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        machine.transitions.extend(extra.transitions);
+
+        let mut subs = get_subs();
+        subs.get_mut(&Role::new("T"))
+            .unwrap()
+            .remove(&EventType::new("time"));
+
+        let errors = validate_machine(&machine, &Role::new("T"), &subs);
+        assert_eq!(
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            vec![
+                "duplicate transition (1)--[pos?]-->(2)",
+                "non-deterministic reaction to event type pos in state 1",
+                "non-deterministic command deliver in state 2",
+                "machine state 4 is unreachable from initial state",
+                "machine reacts to event type car but role T does not subscribe to it",
+                "machine reacts to event type time but role T does not subscribe to it",
+            ]
+        );
+    }
+}
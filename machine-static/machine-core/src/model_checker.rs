@@ -0,0 +1,748 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use petgraph::{Direction::Outgoing, visit::EdgeRef};
+
+use crate::machine::{projection, util};
+use crate::runtime::{Event, MachineRunner, PushResult};
+use crate::types::{
+    proto_graph::{Graph, NodeId},
+    proto_info,
+    typescript_types::{
+        Command, EventLabel, EventType, InterfacingProtocols, MachineType, Role, State, StateName,
+        Subscriptions,
+    },
+    unordered_event_pair::UnordEventPair,
+};
+
+// Bounds of the exploration.
+#[derive(Debug, Clone)]
+pub struct ModelCheckConfig {
+    // no more commands are executed once this many events have been emitted
+    pub max_events: usize,
+    // exploration stops after visiting this many states
+    pub max_states: usize,
+    // explore only one order of independent actions
+    pub partial_order_reduction: bool,
+}
+
+impl Default for ModelCheckConfig {
+    fn default() -> Self {
+        Self {
+            max_events: 6,
+            max_states: 100_000,
+            partial_order_reduction: true,
+        }
+    }
+}
+
+// An event in the swarm. Identified by its emitter and the emitter's sequence number.
+// Logs are ordered by Lamport timestamp, ties broken by emitter.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ModelEvent {
+    pub lamport: u64,
+    pub peer: usize,
+    pub seq: u64,
+    pub event_type: EventType,
+}
+
+// A step of the swarm. A sequence of actions can be replayed with SwarmModel::replay.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    Execute { peer: usize, cmd: Command },
+    Deliver { peer: usize, event: ModelEvent },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ViolationKind {
+    // the machine of the role reacted to an event the protocol ignores or vice versa
+    Disagreement {
+        role: Role,
+        event: ModelEvent,
+    },
+    // the protocol waits for a command of the role that its machine does not offer
+    Stuck {
+        role: Role,
+        machine_state: State,
+        protocol_state: State,
+    },
+    // nothing can happen anymore but the protocol has not terminated
+    NonTermination {
+        protocol_state: State,
+    },
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViolationKind::Disagreement { role, event } => write!(
+                f,
+                "role {role} disagrees with the protocol on event {} (lamport {}, peer {}, seq {})",
+                event.event_type, event.lamport, event.peer, event.seq
+            ),
+            ViolationKind::Stuck {
+                role,
+                machine_state,
+                protocol_state,
+            } => write!(
+                f,
+                "role {role} is stuck in machine state {machine_state} while the protocol in state {protocol_state} waits for it"
+            ),
+            ViolationKind::NonTermination { protocol_state } => write!(
+                f,
+                "swarm can not make progress in non-terminal protocol state {protocol_state}"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub trace: Vec<Action>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelCheckReport {
+    pub violations: Vec<Violation>,
+    pub states: usize,
+    // actions taken during the exploration, reduced by partial order reduction
+    pub transitions: usize,
+    // false if max_states cut the exploration short
+    pub complete: bool,
+}
+
+struct Peer {
+    role: Role,
+    // runner of the machine of the peer in its initial state
+    runner: MachineRunner,
+    subscription: BTreeSet<EventType>,
+}
+
+// The logs of all peers
+type GlobalState = Vec<BTreeSet<ModelEvent>>;
+
+// A swarm of one peer per role of a (composed) swarm protocol.
+pub struct SwarmModel {
+    graph: Graph,
+    initial: NodeId,
+    concurrent_events: BTreeSet<UnordEventPair>,
+    peers: Vec<Peer>,
+}
+
+impl SwarmModel {
+    // Roles without a machine in machines run the projection of the composition.
+    // Fails if the composition has more than max_states states.
+    pub fn new(
+        protos: InterfacingProtocols,
+        subs: &Subscriptions,
+        mut machines: BTreeMap<Role, MachineType>,
+        max_states: usize,
+    ) -> Result<Self, Vec<String>> {
+        let _span = tracing::info_span!("SwarmModel::new").entered();
+        let proto_info = proto_info::swarms_to_proto_info(protos);
+        if !proto_info.no_errors() {
+            return Err(proto_info.to_error_report().to_strings());
+        }
+        let roles: BTreeSet<Role> = proto_info
+            .protocols
+            .iter()
+            .flat_map(|p| p.roles.iter().cloned())
+            .collect();
+        let explicit = proto_info::explicit_composition_proto_info_bounded(proto_info, max_states)
+            .map_err(|errors| errors.to_strings())?;
+        let (graph, initial) = match explicit.protocols[0].initial {
+            Some(initial) => (explicit.protocols[0].graph.clone(), initial),
+            None => {
+                return Err(vec![
+                    "initial swarm protocol state has no transitions".to_string(),
+                ]);
+            }
+        };
+        if let Some(role) = machines.keys().find(|role| !roles.contains(*role)) {
+            return Err(vec![format!(
+                "role {role} does not take part in the protocol"
+            )]);
+        }
+
+        let mut errors = vec![];
+        let mut peers = vec![];
+        for role in roles {
+            let machine = machines.remove(&role).unwrap_or_else(|| {
+                let (proj, proj_initial) =
+                    projection::project(&graph, initial, subs, role.clone(), true);
+                util::to_json_machine(proj, proj_initial)
+            });
+            match MachineRunner::new(machine) {
+                Ok(runner) => peers.push(Peer {
+                    subscription: subs.get(&role).cloned().unwrap_or_default(),
+                    role,
+                    runner,
+                }),
+                Err(e) => errors.extend(e.into_iter().map(|e| format!("role {role}: {e}"))),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(Self {
+            graph,
+            initial,
+            concurrent_events: explicit.concurrent_events,
+            peers,
+        })
+    }
+
+    // Explore all interleavings of command execution and event delivery within the bounds.
+    pub fn check(&self, config: &ModelCheckConfig) -> ModelCheckReport {
+        let _span = tracing::info_span!("model_check").entered();
+        let mut exploration = Exploration {
+            model: self,
+            config,
+            visited: HashMap::new(),
+            trace: vec![],
+            violations: BTreeMap::new(),
+            transitions: 0,
+            complete: true,
+        };
+        let initial_state: GlobalState = vec![BTreeSet::new(); self.peers.len()];
+        exploration.explore(initial_state, BTreeSet::new());
+
+        ModelCheckReport {
+            states: exploration.visited.len(),
+            transitions: exploration.transitions,
+            complete: exploration.complete,
+            violations: exploration
+                .violations
+                .into_iter()
+                .map(|(kind, trace)| Violation { kind, trace })
+                .collect(),
+        }
+    }
+
+    // Replay a trace from the initial state. Returns the state of each machine
+    // afterwards or an error if some action of the trace is not enabled.
+    pub fn replay(&self, trace: &[Action]) -> Result<Vec<State>, String> {
+        let mut state: GlobalState = vec![BTreeSet::new(); self.peers.len()];
+        for (i, action) in trace.iter().enumerate() {
+            if !self.enabled(&state, usize::MAX).contains(action) {
+                return Err(format!("action {i} of trace is not enabled"));
+            }
+            state = self.apply(&state, action);
+        }
+
+        Ok((0..self.peers.len())
+            .map(|peer| self.run(peer, &state[peer]).0.state().clone())
+            .collect())
+    }
+
+    // Run the machine of peer on a log. Returns the runner and the events it reacted to.
+    fn run(&self, peer: usize, log: &BTreeSet<ModelEvent>) -> (MachineRunner, Vec<ModelEvent>) {
+        let mut runner = self.peers[peer].runner.clone();
+        runner.reset();
+        let mut reacted = vec![];
+        for e in log {
+            let result = runner.push(&Event {
+                event_id: format!("{}-{}", e.peer, e.seq),
+                event_type: e.event_type.clone(),
+                lbj: None,
+            });
+            if let PushResult::React { .. } = result {
+                reacted.push(e.clone());
+            }
+        }
+        (runner, reacted)
+    }
+
+    fn enabled(&self, state: &GlobalState, max_events: usize) -> Vec<Action> {
+        let emitted: BTreeSet<&ModelEvent> = state.iter().flatten().collect();
+        let mut actions = vec![];
+        if emitted.len() < max_events {
+            for (peer, log) in state.iter().enumerate() {
+                let (runner, _) = self.run(peer, log);
+                actions.extend(
+                    runner
+                        .enabled_commands()
+                        .into_iter()
+                        .map(|(cmd, _)| Action::Execute { peer, cmd }),
+                );
+            }
+        }
+        for (peer, p) in self.peers.iter().enumerate() {
+            actions.extend(
+                emitted
+                    .iter()
+                    .filter(|e| p.subscription.contains(&e.event_type) && !state[peer].contains(*e))
+                    .map(|e| Action::Deliver {
+                        peer,
+                        event: (*e).clone(),
+                    }),
+            );
+        }
+        actions
+    }
+
+    fn apply(&self, state: &GlobalState, action: &Action) -> GlobalState {
+        let mut next = state.clone();
+        match action {
+            Action::Execute { peer, cmd } => {
+                let (runner, _) = self.run(*peer, &state[*peer]);
+                let log_type = runner
+                    .enabled_commands()
+                    .into_iter()
+                    .find(|(c, _)| c == cmd)
+                    .map(|(_, log_type)| log_type)
+                    .unwrap_or_default();
+                let lamport = state[*peer].iter().map(|e| e.lamport).max().unwrap_or(0);
+                let seq = state[*peer].iter().filter(|e| e.peer == *peer).count() as u64;
+                for (i, event_type) in log_type.into_iter().enumerate() {
+                    next[*peer].insert(ModelEvent {
+                        lamport: lamport + 1 + i as u64,
+                        peer: *peer,
+                        seq: seq + i as u64,
+                        event_type,
+                    });
+                }
+            }
+            Action::Deliver { peer, event } => {
+                next[*peer].insert(event.clone());
+            }
+        }
+        next
+    }
+
+    fn event_types(&self, state: &GlobalState, action: &Action) -> Vec<EventType> {
+        match action {
+            Action::Execute { peer, cmd } => self
+                .run(*peer, &state[*peer])
+                .0
+                .enabled_commands()
+                .into_iter()
+                .filter(|(c, _)| c == cmd)
+                .flat_map(|(_, log_type)| log_type)
+                .collect(),
+            Action::Deliver { event, .. } => vec![event.event_type.clone()],
+        }
+    }
+
+    // Whether the order of two actions enabled in state does not matter.
+    // Only actions of different peers are independent, they touch different logs,
+    // and only if the event types they emit or deliver are concurrent in the protocol.
+    // Two commands do not commute if executing one of them reaches the max_events
+    // bound and disables the other.
+    fn independent(&self, state: &GlobalState, a: &Action, b: &Action, max_events: usize) -> bool {
+        let peer = |action: &Action| match action {
+            Action::Execute { peer, .. } | Action::Deliver { peer, .. } => *peer,
+        };
+        if peer(a) == peer(b) {
+            return false;
+        }
+        let concurrent = self.event_types(state, a).iter().all(|t1| {
+            self.event_types(state, b).iter().all(|t2| {
+                self.concurrent_events
+                    .contains(&UnordEventPair::new(t1.clone(), t2.clone()))
+            })
+        });
+        if !concurrent {
+            return false;
+        }
+        match (a, b) {
+            (Action::Execute { .. }, Action::Execute { .. }) => {
+                let emitted = state.iter().flatten().collect::<BTreeSet<_>>().len();
+                [a, b]
+                    .iter()
+                    .all(|action| emitted + self.event_types(state, action).len() < max_events)
+            }
+            _ => true,
+        }
+    }
+
+    // Interpret the union of the logs in the protocol. Events not valid in
+    // the state reached so far are ignored. Returns the state reached and the valid events.
+    fn protocol_state(&self, state: &GlobalState) -> (NodeId, BTreeSet<ModelEvent>) {
+        let log: BTreeSet<&ModelEvent> = state.iter().flatten().collect();
+        let mut current = self.initial;
+        let mut valid = BTreeSet::new();
+        for event in log {
+            let role = &self.peers[event.peer].role;
+            let next = self.graph.edges_directed(current, Outgoing).find(|edge| {
+                edge.weight().get_event_type() == event.event_type && edge.weight().role == *role
+            });
+            if let Some(edge) = next {
+                current = edge.target();
+                valid.insert(event.clone());
+            }
+        }
+        (current, valid)
+    }
+
+    fn violations(&self, state: &GlobalState, enabled: &[Action]) -> Vec<ViolationKind> {
+        let fully_delivered = !enabled.iter().any(|a| matches!(a, Action::Deliver { .. }));
+        if !fully_delivered {
+            return vec![];
+        }
+        let (protocol_state, valid) = self.protocol_state(state);
+        let protocol_state_name = self.graph[protocol_state].state_name().clone();
+        let mut violations = vec![];
+        for (peer, p) in self.peers.iter().enumerate() {
+            let (runner, reacted) = self.run(peer, &state[peer]);
+            let expected: Vec<ModelEvent> = state[peer]
+                .iter()
+                .filter(|e| valid.contains(*e))
+                .cloned()
+                .collect();
+            if let Some(event) = reacted
+                .iter()
+                .zip(expected.iter())
+                .find(|(r, e)| r != e)
+                .map(|(r, e)| r.min(e).clone())
+                .or_else(|| reacted.get(expected.len()).cloned())
+                .or_else(|| expected.get(reacted.len()).cloned())
+            {
+                violations.push(ViolationKind::Disagreement {
+                    role: p.role.clone(),
+                    event,
+                });
+                continue;
+            }
+            let commands: BTreeSet<Command> = runner
+                .enabled_commands()
+                .into_iter()
+                .map(|(cmd, _)| cmd)
+                .collect();
+            let waits_for_role = self
+                .graph
+                .edges_directed(protocol_state, Outgoing)
+                .any(|edge| edge.weight().role == p.role && !commands.contains(&edge.weight().cmd));
+            let offers_some = self
+                .graph
+                .edges_directed(protocol_state, Outgoing)
+                .any(|edge| edge.weight().role == p.role && commands.contains(&edge.weight().cmd));
+            if waits_for_role && !offers_some {
+                violations.push(ViolationKind::Stuck {
+                    role: p.role.clone(),
+                    machine_state: runner.state().clone(),
+                    protocol_state: protocol_state_name.clone(),
+                });
+            }
+        }
+        let terminal = self
+            .graph
+            .edges_directed(protocol_state, Outgoing)
+            .next()
+            .is_none();
+        // quiescent because of the bound is not a violation
+        if enabled.is_empty() && !terminal && self.enabled(state, usize::MAX).is_empty() {
+            violations.push(ViolationKind::NonTermination {
+                protocol_state: protocol_state_name,
+            });
+        }
+        violations
+    }
+}
+
+struct Exploration<'a> {
+    model: &'a SwarmModel,
+    config: &'a ModelCheckConfig,
+    // sleep set each state was explored with
+    visited: HashMap<GlobalState, BTreeSet<Action>>,
+    trace: Vec<Action>,
+    // first trace found for each violation
+    violations: BTreeMap<ViolationKind, Vec<Action>>,
+    transitions: usize,
+    complete: bool,
+}
+
+impl Exploration<'_> {
+    // Depth first search with sleep sets. When a state is visited again,
+    // only the actions it slept before and does not sleep now are explored.
+    fn explore(&mut self, state: GlobalState, sleep: BTreeSet<Action>) {
+        let (sleep, previous) = match self.visited.get(&state) {
+            Some(previous) if previous.is_subset(&sleep) => return,
+            Some(previous) => (
+                previous.intersection(&sleep).cloned().collect(),
+                Some(previous.clone()),
+            ),
+            None => (sleep, None),
+        };
+        if !self.visited.contains_key(&state) && self.visited.len() >= self.config.max_states {
+            self.complete = false;
+            return;
+        }
+        self.visited.insert(state.clone(), sleep.clone());
+
+        let enabled = self.model.enabled(&state, self.config.max_events);
+        if previous.is_none() {
+            for violation in self.model.violations(&state, &enabled) {
+                tracing::debug!(%violation, "found violation");
+                self.violations
+                    .entry(violation)
+                    .or_insert_with(|| self.trace.clone());
+            }
+        }
+
+        let mut sleep = sleep;
+        for action in enabled {
+            let explored = previous
+                .as_ref()
+                .is_some_and(|previous| !previous.contains(&action));
+            if sleep.contains(&action) || explored {
+                continue;
+            }
+            let next_sleep = if self.config.partial_order_reduction {
+                sleep
+                    .iter()
+                    .filter(|b| {
+                        self.model
+                            .independent(&state, &action, b, self.config.max_events)
+                    })
+                    .cloned()
+                    .collect()
+            } else {
+                BTreeSet::new()
+            };
+            let next = self.model.apply(&state, &action);
+            self.transitions += 1;
+            self.trace.push(action.clone());
+            self.explore(next, next_sleep);
+            self.trace.pop();
+            if self.config.partial_order_reduction {
+                sleep.insert(action);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subscription::overapproximation;
+    use crate::test_utils;
+    use crate::types::proto_info::DEFAULT_MAX_STATES;
+    use crate::types::typescript_types::{Granularity, SwarmProtocolType};
+
+    fn get_branching_proto() -> SwarmProtocolType {
+        serde_json::from_str::<SwarmProtocolType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "source": "0", "target": "1", "label": { "cmd": "a", "logType": ["a"], "role": "R1" } },
+                    { "source": "1", "target": "2", "label": { "cmd": "c", "logType": ["c"], "role": "R2" } },
+                    { "source": "0", "target": "3", "label": { "cmd": "b", "logType": ["b"], "role": "R1" } },
+                    { "source": "3", "target": "4", "label": { "cmd": "d", "logType": ["d"], "role": "R2" } }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn subs_for(protos: &InterfacingProtocols) -> Subscriptions {
+        overapproximation::overapprox_well_formed_sub(
+            protos.clone(),
+            &BTreeMap::new(),
+            Granularity::TwoStep,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_model_check_well_formed() {
+        test_utils::setup_logger();
+        let protos = InterfacingProtocols(vec![get_branching_proto()]);
+        let subs = subs_for(&protos);
+        let model = SwarmModel::new(protos, &subs, BTreeMap::new(), DEFAULT_MAX_STATES).unwrap();
+        let report = model.check(&ModelCheckConfig::default());
+        assert!(report.complete);
+        assert_eq!(report.violations, vec![]);
+    }
+
+    #[test]
+    fn test_model_check_bad_subscription() {
+        test_utils::setup_logger();
+        let protos = InterfacingProtocols(vec![get_branching_proto()]);
+        let subs = BTreeMap::from([
+            (
+                Role::new("R1"),
+                BTreeSet::from([
+                    EventType::new("a"),
+                    EventType::new("b"),
+                    EventType::new("c"),
+                    EventType::new("d"),
+                ]),
+            ),
+            (
+                Role::new("R2"),
+                BTreeSet::from([EventType::new("c"), EventType::new("d")]),
+            ),
+        ]);
+        let model = SwarmModel::new(protos, &subs, BTreeMap::new(), DEFAULT_MAX_STATES).unwrap();
+        let report = model.check(&ModelCheckConfig::default());
+        assert!(report.complete);
+        assert!(
+            report
+                .violations
+                .iter()
+                .any(|v| matches!(&v.kind, ViolationKind::Disagreement { role, .. } if *role == Role::new("R2")))
+        );
+        // traces lead to the reported violations
+        for violation in report.violations {
+            assert!(model.replay(&violation.trace).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_model_check_stuck_machine() {
+        test_utils::setup_logger();
+        let protos = InterfacingProtocols(vec![test_utils::get_proto1()]);
+        let subs = subs_for(&protos);
+        // forklift that never gets the position
+        let fl_m = serde_json::from_str::<MachineType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "label": { "tag": "Input", "eventType": "partID" }, "source": "0", "target": "1" },
+                    { "label": { "tag": "Input", "eventType": "time" }, "source": "0", "target": "2" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let model = SwarmModel::new(
+            protos,
+            &subs,
+            BTreeMap::from([(Role::new("FL"), fl_m)]),
+            DEFAULT_MAX_STATES,
+        )
+        .unwrap();
+        let report = model.check(&ModelCheckConfig::default());
+        let stuck = report
+            .violations
+            .iter()
+            .find(|v| matches!(&v.kind, ViolationKind::Stuck { role, .. } if *role == Role::new("FL")))
+            .unwrap();
+        assert_eq!(
+            stuck.kind.to_string(),
+            "role FL is stuck in machine state 1 while the protocol in state 1 waits for it"
+        );
+        assert!(
+            report
+                .violations
+                .iter()
+                .any(|v| matches!(v.kind, ViolationKind::NonTermination { .. }))
+        );
+        assert_eq!(
+            model.replay(&stuck.trace).unwrap()[model
+                .peers
+                .iter()
+                .position(|p| p.role == Role::new("FL"))
+                .unwrap()],
+            State::new("1")
+        );
+    }
+
+    // Two protocols of different roles whose event types are all concurrent.
+    fn get_concurrent_protos() -> InterfacingProtocols {
+        let proto = |role: &str, event_type: &str| {
+            serde_json::from_str::<SwarmProtocolType>(&format!(
+                r#"{{
+                    "initial": "0",
+                    "transitions": [
+                        {{ "source": "0", "target": "1", "label": {{ "cmd": "{event_type}1", "logType": ["{event_type}1"], "role": "{role}" }} }},
+                        {{ "source": "1", "target": "2", "label": {{ "cmd": "{event_type}2", "logType": ["{event_type}2"], "role": "{role}" }} }},
+                        {{ "source": "2", "target": "3", "label": {{ "cmd": "{event_type}3", "logType": ["{event_type}3"], "role": "{role}" }} }}
+                    ]
+                }}"#
+            ))
+            .unwrap()
+        };
+        InterfacingProtocols(vec![proto("R1", "a"), proto("R2", "b")])
+    }
+
+    // Commands of R1 and R2 commute, so only one order of them is explored.
+    #[test]
+    fn test_model_check_partial_order_reduction() {
+        test_utils::setup_logger();
+        let protos = get_concurrent_protos();
+        let subs = subs_for(&protos);
+        let model = SwarmModel::new(protos, &subs, BTreeMap::new(), DEFAULT_MAX_STATES).unwrap();
+        let config = ModelCheckConfig {
+            max_events: 6,
+            ..ModelCheckConfig::default()
+        };
+        let reduced = model.check(&config);
+        let full = model.check(&ModelCheckConfig {
+            partial_order_reduction: false,
+            ..config
+        });
+        assert!(reduced.complete && full.complete);
+        assert_eq!(reduced.violations, vec![]);
+        assert_eq!(full.violations, vec![]);
+        assert_eq!(reduced.states, full.states);
+        assert_eq!((reduced.transitions, full.transitions), (15, 24));
+    }
+
+    // Events of the warehouse and the factory are hardly concurrent, so there is little to reduce.
+    #[test]
+    fn test_model_check_partial_order_reduction_dependent() {
+        test_utils::setup_logger();
+        let protos = test_utils::get_interfacing_swarms_1();
+        let subs = subs_for(&protos);
+        let model = SwarmModel::new(protos, &subs, BTreeMap::new(), DEFAULT_MAX_STATES).unwrap();
+        let config = ModelCheckConfig {
+            max_events: 4,
+            ..ModelCheckConfig::default()
+        };
+        let reduced = model.check(&config);
+        let full = model.check(&ModelCheckConfig {
+            partial_order_reduction: false,
+            ..config
+        });
+        assert!(reduced.complete && full.complete);
+        assert_eq!(reduced.violations, vec![]);
+        assert_eq!(full.violations, vec![]);
+        assert!(reduced.states <= full.states);
+        assert!(reduced.transitions <= full.transitions);
+    }
+
+    // Reduction only drops orders of independent actions, so it finds the same
+    // violations, also for user machines and when the bound cuts off commands.
+    #[test]
+    fn test_model_check_partial_order_reduction_violations() {
+        test_utils::setup_logger();
+        let protos = InterfacingProtocols(vec![test_utils::get_proto1()]);
+        let subs = subs_for(&protos);
+        let fl_m = serde_json::from_str::<MachineType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "label": { "tag": "Input", "eventType": "partID" }, "source": "0", "target": "1" },
+                    { "label": { "tag": "Input", "eventType": "time" }, "source": "0", "target": "2" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let model = SwarmModel::new(
+            protos,
+            &subs,
+            BTreeMap::from([(Role::new("FL"), fl_m)]),
+            DEFAULT_MAX_STATES,
+        )
+        .unwrap();
+        let kinds = |report: ModelCheckReport| -> BTreeSet<ViolationKind> {
+            report.violations.into_iter().map(|v| v.kind).collect()
+        };
+        for max_events in 1..5 {
+            let config = ModelCheckConfig {
+                max_events,
+                ..ModelCheckConfig::default()
+            };
+            let reduced = model.check(&config);
+            let full = model.check(&ModelCheckConfig {
+                partial_order_reduction: false,
+                ..config
+            });
+            assert!(reduced.complete && full.complete);
+            assert_eq!(kinds(reduced), kinds(full));
+        }
+    }
+}
@@ -0,0 +1,31 @@
+// Helpers for independent work that runs in parallel with the parallel feature.
+// Results are always in the order of the input, so output does not depend on scheduling.
+
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+use rayon::prelude::*;
+
+// Apply f to every item of items.
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+pub fn map<T: Send, R: Send>(items: Vec<T>, f: impl Fn(T) -> R + Send + Sync) -> Vec<R> {
+    items.into_par_iter().map(f).collect()
+}
+
+// Apply f to every item of items.
+#[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+pub fn map<T: Send, R: Send>(items: Vec<T>, f: impl Fn(T) -> R + Send + Sync) -> Vec<R> {
+    items.into_iter().map(f).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn test_map_keeps_order() {
+        test_utils::setup_logger();
+        let items: Vec<usize> = (0..1000).collect();
+        let squares = map(items, |i| i * i);
+        assert_eq!(squares, (0..1000).map(|i| i * i).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use petgraph::{Direction::Outgoing, visit::EdgeRef};
+
+use crate::types::{
+    proto_graph::{EdgeId, Graph, NodeId},
+    proto_info,
+    typescript_types::{
+        EventLabel, EventType, InterfacingProtocols, PathQuery, PathTarget, StateName, SwarmLabel,
+    },
+};
+
+// Answer query on the composition of protos. Fails if the composition has more than max_states states.
+pub fn query_protocols(
+    protos: InterfacingProtocols,
+    query: &PathQuery,
+    max_states: usize,
+) -> Result<Vec<Vec<SwarmLabel>>, Vec<String>> {
+    let (graph, initial) = proto_info::compose_protocols_bounded(protos, max_states)
+        .map_err(|errors| errors.to_strings())?;
+    query_paths(&graph, initial, query).map_err(|e| vec![e])
+}
+
+// Answer query on graph. Paths are given as the labels of the transitions taken.
+pub fn query_paths(
+    graph: &Graph,
+    initial: NodeId,
+    query: &PathQuery,
+) -> Result<Vec<Vec<SwarmLabel>>, String> {
+    let _span = tracing::info_span!("query_paths").entered();
+    let find = |state| {
+        graph
+            .node_indices()
+            .find(|node| graph[*node].state_name() == state)
+            .ok_or_else(|| format!("state {state} not in protocol"))
+    };
+    let from = match &query.from {
+        Some(state) => find(state)?,
+        None => initial,
+    };
+    let target = match &query.target {
+        PathTarget::State { state } => Target::Node(find(state)?),
+        PathTarget::EventType { event_type } => Target::Emitting(event_type),
+    };
+    let allowed = |edge: EdgeId| {
+        let label = &graph[edge];
+        !query.avoid_roles.contains(&label.role)
+            && !query.avoid_commands.contains(&label.cmd)
+            && !query.avoid_event_types.contains(&label.get_event_type())
+    };
+    let search = Search {
+        graph,
+        target,
+        allowed: &allowed,
+    };
+
+    let paths = match query.limit {
+        Some(limit) => search.all_paths(from, limit),
+        None => search.shortest_path(from).into_iter().collect(),
+    };
+    Ok(paths
+        .into_iter()
+        .map(|path| path.into_iter().map(|edge| graph[edge].clone()).collect())
+        .collect())
+}
+
+enum Target<'a> {
+    Node(NodeId),
+    Emitting(&'a EventType),
+}
+
+struct Search<'a> {
+    graph: &'a Graph,
+    target: Target<'a>,
+    allowed: &'a dyn Fn(EdgeId) -> bool,
+}
+
+impl Search<'_> {
+    fn emits_target(&self, edge: EdgeId) -> bool {
+        matches!(self.target, Target::Emitting(event_type) if self.graph[edge].get_event_type() == *event_type)
+    }
+
+    fn shortest_path(&self, from: NodeId) -> Option<Vec<EdgeId>> {
+        let mut predecessor: BTreeMap<NodeId, EdgeId> = BTreeMap::new();
+        let mut visited = BTreeSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+        let path_to = |mut node: NodeId, predecessor: &BTreeMap<NodeId, EdgeId>| {
+            let mut path = vec![];
+            while node != from {
+                let edge = predecessor[&node];
+                path.push(edge);
+                node = self.graph.edge_endpoints(edge).unwrap().0;
+            }
+            path.reverse();
+            path
+        };
+        while let Some(node) = queue.pop_front() {
+            if matches!(self.target, Target::Node(target) if target == node) {
+                return Some(path_to(node, &predecessor));
+            }
+            for edge in self.graph.edges_directed(node, Outgoing) {
+                if !(self.allowed)(edge.id()) {
+                    continue;
+                }
+                if self.emits_target(edge.id()) {
+                    let mut path = path_to(node, &predecessor);
+                    path.push(edge.id());
+                    return Some(path);
+                }
+                if visited.insert(edge.target()) {
+                    predecessor.insert(edge.target(), edge.id());
+                    queue.push_back(edge.target());
+                }
+            }
+        }
+        None
+    }
+
+    // The limit shortest paths without repeated states, shortest first. A path emitting
+    // the target event type may end in a state visited before. Paths are extended breadth
+    // first and a path found is only taken when dequeued, so paths are taken in order of length.
+    fn all_paths(&self, from: NodeId, limit: usize) -> Vec<Vec<EdgeId>> {
+        let mut paths = vec![];
+        // partial paths with the states on them and whether they reached the target
+        let mut queue = VecDeque::from([(from, vec![], BTreeSet::from([from]), false)]);
+        while let Some((node, path, on_path, found)) = queue.pop_front() {
+            if paths.len() >= limit {
+                break;
+            }
+            if found || matches!(self.target, Target::Node(target) if target == node) {
+                paths.push(path);
+                continue;
+            }
+            for edge in self.graph.edges_directed(node, Outgoing) {
+                if !(self.allowed)(edge.id()) {
+                    continue;
+                }
+                let mut next = path.clone();
+                next.push(edge.id());
+                if self.emits_target(edge.id()) {
+                    queue.push_back((edge.target(), next.clone(), on_path.clone(), true));
+                }
+                if !on_path.contains(&edge.target()) {
+                    let mut next_on_path = on_path.clone();
+                    next_on_path.insert(edge.target());
+                    queue.push_back((edge.target(), next, next_on_path, false));
+                }
+            }
+        }
+        paths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::types::proto_info::DEFAULT_MAX_STATES;
+    use crate::types::typescript_types::{Command, SwarmProtocolType};
+
+    fn query(json: &str) -> PathQuery {
+        serde_json::from_str::<PathQuery>(json).unwrap()
+    }
+
+    fn commands(paths: Vec<Vec<SwarmLabel>>) -> Vec<Vec<Command>> {
+        paths
+            .into_iter()
+            .map(|path| path.into_iter().map(|label| label.cmd).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_reachable_avoiding_role() {
+        test_utils::setup_logger();
+        let protos = InterfacingProtocols(vec![test_utils::get_proto1()]);
+        let paths = query_protocols(
+            protos.clone(),
+            &query(r#"{ "target": { "type": "state", "state": "3" }, "avoidRoles": ["T"] }"#),
+            DEFAULT_MAX_STATES,
+        )
+        .unwrap();
+        assert_eq!(commands(paths), vec![vec![Command::new("close")]]);
+
+        let paths = query_protocols(
+            protos.clone(),
+            &query(r#"{ "target": { "type": "state", "state": "2" }, "avoidRoles": ["FL"] }"#),
+            DEFAULT_MAX_STATES,
+        )
+        .unwrap();
+        assert!(paths.is_empty());
+
+        let errors = query_protocols(
+            protos,
+            &query(r#"{ "target": { "type": "state", "state": "7" } }"#),
+            DEFAULT_MAX_STATES,
+        )
+        .unwrap_err();
+        assert_eq!(errors, vec!["state 7 not in protocol".to_string()]);
+    }
+
+    #[test]
+    fn test_shortest_path_emitting() {
+        test_utils::setup_logger();
+        let protos = InterfacingProtocols(vec![test_utils::get_proto1()]);
+        let paths = query_protocols(
+            protos,
+            &query(r#"{ "from": "1", "target": { "type": "eventType", "eventType": "partID" } }"#),
+            DEFAULT_MAX_STATES,
+        )
+        .unwrap();
+        assert_eq!(
+            commands(paths),
+            vec![vec![
+                Command::new("get"),
+                Command::new("deliver"),
+                Command::new("request")
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_all_paths_composition() {
+        test_utils::setup_logger();
+        let protos = test_utils::get_interfacing_swarms_1();
+        let paths = query_protocols(
+            protos.clone(),
+            &query(r#"{ "target": { "type": "eventType", "eventType": "car" }, "limit": 10 }"#),
+            DEFAULT_MAX_STATES,
+        )
+        .unwrap();
+        assert_eq!(
+            commands(paths),
+            vec![
+                vec![
+                    Command::new("request"),
+                    Command::new("get"),
+                    Command::new("deliver"),
+                    Command::new("build")
+                ],
+                vec![
+                    Command::new("request"),
+                    Command::new("get"),
+                    Command::new("deliver"),
+                    Command::new("close"),
+                    Command::new("build")
+                ],
+            ]
+        );
+
+        let paths = query_protocols(
+            protos,
+            &query(
+                r#"{ "from": "0 || 2", "target": { "type": "state", "state": "3 || 3" }, "avoidEventTypes": ["time"], "limit": 10 }"#,
+            ),
+            DEFAULT_MAX_STATES,
+        )
+        .unwrap();
+        assert!(paths.is_empty());
+    }
+
+    #[test]
+    fn test_all_paths_shortest_within_limit() {
+        test_utils::setup_logger();
+        let proto = serde_json::from_str::<SwarmProtocolType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "source": "0", "target": "3", "label": { "cmd": "d", "logType": ["d"], "role": "R" } },
+                    { "source": "0", "target": "1", "label": { "cmd": "a", "logType": ["a"], "role": "R" } },
+                    { "source": "1", "target": "2", "label": { "cmd": "b", "logType": ["b"], "role": "R" } },
+                    { "source": "2", "target": "3", "label": { "cmd": "c", "logType": ["c"], "role": "R" } },
+                    { "source": "1", "target": "3", "label": { "cmd": "e", "logType": ["e"], "role": "R" } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let protos = InterfacingProtocols(vec![proto]);
+        let paths = query_protocols(
+            protos.clone(),
+            &query(r#"{ "target": { "type": "state", "state": "3" }, "limit": 2 }"#),
+            DEFAULT_MAX_STATES,
+        )
+        .unwrap();
+        assert_eq!(
+            commands(paths),
+            vec![
+                vec![Command::new("d")],
+                vec![Command::new("a"), Command::new("e")]
+            ]
+        );
+
+        let paths = query_protocols(
+            protos,
+            &query(r#"{ "target": { "type": "state", "state": "3" }, "limit": 1 }"#),
+            DEFAULT_MAX_STATES,
+        )
+        .unwrap();
+        assert_eq!(commands(paths), vec![vec![Command::new("d")]]);
+    }
+}
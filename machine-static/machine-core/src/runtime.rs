@@ -0,0 +1,335 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use petgraph::{Direction::Outgoing, visit::EdgeRef};
+use serde::{Deserialize, Serialize};
+
+use crate::machine::{util, validation};
+use crate::types::{
+    projection::Graph,
+    proto_graph::NodeId,
+    typescript_types::{
+        BranchMap, Command, EventLabel, EventType, MachineLabel, MachineType, ProjectionInfo, State,
+    },
+};
+
+// An event as delivered to a running machine. Events are expected in the
+// order of the event stream. `lbj` is the id of the last branching or joining
+// event the emitter of the event had seen for the event's type (None if no such event).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+    pub event_id: String,
+    pub event_type: EventType,
+    pub lbj: Option<String>,
+}
+
+// Outcome of delivering an event to a machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PushResult {
+    // The machine has no reaction to the event in its current state
+    // or the event does not point to the expected branching or joining event.
+    Discard,
+    React { from: State, to: State },
+}
+
+// Mirrors the branch tracker of the branch-tracking machine runner.
+// For each event type, the id of the last branching or joining event
+// that determines whether an event of that type is accepted.
+#[derive(Debug, Clone)]
+struct BranchTracker {
+    jb_last: BTreeMap<EventType, Option<String>>,
+    special_event_types: BTreeSet<EventType>,
+    branches: BranchMap,
+}
+
+impl BranchTracker {
+    fn new(
+        event_types: BTreeSet<EventType>,
+        special_event_types: BTreeSet<EventType>,
+        branches: BranchMap,
+    ) -> Self {
+        Self {
+            jb_last: event_types.into_iter().map(|t| (t, None)).collect(),
+            special_event_types,
+            branches,
+        }
+    }
+
+    fn accepts(&self, event: &Event) -> bool {
+        self.jb_last.get(&event.event_type) == Some(&event.lbj)
+    }
+
+    // if event is branching or joining update jb_last accordingly
+    fn update(&mut self, event: &Event) {
+        if !self.special_event_types.contains(&event.event_type) {
+            return;
+        }
+        for t in self.branches.get(&event.event_type).into_iter().flatten() {
+            self.jb_last.insert(t.clone(), Some(event.event_id.clone()));
+        }
+    }
+
+    fn reset(&mut self) {
+        for pointer in self.jb_last.values_mut() {
+            *pointer = None;
+        }
+    }
+}
+
+// Reference interpreter for machines. Executes a machine against an
+// ordered event stream, either plainly or with branch-tracking like
+// the branch-tracking machine runner when created from a ProjectionInfo.
+#[derive(Debug, Clone)]
+pub struct MachineRunner {
+    machine: Graph,
+    initial: NodeId,
+    current: NodeId,
+    branch_tracker: Option<BranchTracker>,
+}
+
+impl MachineRunner {
+    pub fn new(machine: MachineType) -> Result<Self, Vec<String>> {
+        let _span = tracing::info_span!("MachineRunner::new").entered();
+        let mut errors: Vec<String> = validation::validate_machine_structure(&machine)
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        let (machine, initial, m_errors) = util::from_json(machine);
+        errors.extend(m_errors);
+        let machine: Graph = machine.map(|_, n| n.clone().unwrap(), |_, l| l.clone());
+        let Some(initial) = initial else {
+            errors.push("initial machine state has no transitions".to_string());
+            return Err(errors);
+        };
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(Self {
+            machine,
+            initial,
+            current: initial,
+            branch_tracker: None,
+        })
+    }
+
+    // Run an adapted machine with branch-tracking.
+    pub fn new_bt(projection_info: ProjectionInfo) -> Result<Self, Vec<String>> {
+        let mut runner = Self::new(projection_info.projection)?;
+        let event_types: BTreeSet<EventType> = runner
+            .machine
+            .edge_weights()
+            .map(|label| label.get_event_type())
+            .chain(projection_info.branches.values().flatten().cloned())
+            .chain(projection_info.special_event_types.iter().cloned())
+            .collect();
+        runner.branch_tracker = Some(BranchTracker::new(
+            event_types,
+            projection_info.special_event_types,
+            projection_info.branches,
+        ));
+
+        Ok(runner)
+    }
+
+    pub fn state(&self) -> &State {
+        &self.machine[self.current]
+    }
+
+    // The commands enabled in the current state and the event types they emit.
+    pub fn enabled_commands(&self) -> Vec<(Command, Vec<EventType>)> {
+        let mut commands: Vec<_> = self
+            .machine
+            .edges_directed(self.current, Outgoing)
+            .filter_map(|edge| match edge.weight() {
+                MachineLabel::Execute { cmd, log_type, .. } => {
+                    Some((cmd.clone(), log_type.clone()))
+                }
+                MachineLabel::Input { .. } => None,
+            })
+            .collect();
+        commands.sort();
+        commands
+    }
+
+    // The lbj pointer that an event of event_type emitted now must carry.
+    // Always None when not branch-tracking.
+    pub fn lbj(&self, event_type: &EventType) -> Option<String> {
+        self.branch_tracker
+            .as_ref()
+            .and_then(|tracker| tracker.jb_last.get(event_type).cloned().flatten())
+    }
+
+    pub fn push(&mut self, event: &Event) -> PushResult {
+        let target = self
+            .machine
+            .edges_directed(self.current, Outgoing)
+            .find(|edge| {
+                matches!(edge.weight(), MachineLabel::Input { event_type } if *event_type == event.event_type)
+            })
+            .map(|edge| edge.target());
+        let Some(target) = target else {
+            return PushResult::Discard;
+        };
+        if let Some(tracker) = self.branch_tracker.as_mut() {
+            if !tracker.accepts(event) {
+                return PushResult::Discard;
+            }
+            tracker.update(event);
+        }
+
+        let from = self.machine[self.current].clone();
+        self.current = target;
+        tracing::debug!(%from, to = %self.machine[target], event_type = %event.event_type, "react");
+        PushResult::React {
+            from,
+            to: self.machine[target].clone(),
+        }
+    }
+
+    // Deliver a sequence of events in order.
+    pub fn run<'a>(&mut self, events: impl IntoIterator<Item = &'a Event>) -> Vec<PushResult> {
+        events.into_iter().map(|event| self.push(event)).collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.current = self.initial;
+        if let Some(tracker) = self.branch_tracker.as_mut() {
+            tracker.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn event(event_id: &str, event_type: &str, lbj: Option<&str>) -> Event {
+        Event {
+            event_id: event_id.to_string(),
+            event_type: EventType::new(event_type),
+            lbj: lbj.map(|s| s.to_string()),
+        }
+    }
+
+    fn get_machine() -> MachineType {
+        serde_json::from_str::<MachineType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "label": { "tag": "Execute", "cmd": "request", "logType": ["partID"] }, "source": "0", "target": "0" },
+                    { "label": { "tag": "Input", "eventType": "partID" }, "source": "0", "target": "1" },
+                    { "label": { "tag": "Input", "eventType": "pos" }, "source": "1", "target": "2" },
+                    { "label": { "tag": "Execute", "cmd": "deliver", "logType": ["part"] }, "source": "2", "target": "2" },
+                    { "label": { "tag": "Input", "eventType": "part" }, "source": "2", "target": "0" },
+                    { "label": { "tag": "Input", "eventType": "time" }, "source": "0", "target": "3" }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_runner() {
+        test_utils::setup_logger();
+        let mut runner = MachineRunner::new(get_machine()).unwrap();
+        assert_eq!(
+            runner.enabled_commands(),
+            vec![(Command::new("request"), vec![EventType::new("partID")])]
+        );
+
+        let results = runner.run(&[
+            event("e1", "partID", None),
+            event("e2", "part", None),
+            event("e3", "pos", None),
+        ]);
+        assert_eq!(
+            results,
+            vec![
+                PushResult::React {
+                    from: State::new("0"),
+                    to: State::new("1")
+                },
+                PushResult::Discard,
+                PushResult::React {
+                    from: State::new("1"),
+                    to: State::new("2")
+                },
+            ]
+        );
+        assert_eq!(runner.state(), &State::new("2"));
+        assert_eq!(
+            runner.enabled_commands(),
+            vec![(Command::new("deliver"), vec![EventType::new("part")])]
+        );
+        assert_eq!(runner.lbj(&EventType::new("part")), None);
+
+        runner.reset();
+        assert_eq!(runner.state(), &State::new("0"));
+    }
+
+    #[test]
+    fn test_runner_bt() {
+        test_utils::setup_logger();
+        let projection_info = ProjectionInfo {
+            projection: get_machine(),
+            branches: BTreeMap::from([
+                (
+                    EventType::new("partID"),
+                    vec![EventType::new("pos"), EventType::new("part")],
+                ),
+                (
+                    EventType::new("part"),
+                    vec![EventType::new("partID"), EventType::new("time")],
+                ),
+            ]),
+            special_event_types: BTreeSet::from([EventType::new("partID"), EventType::new("part")]),
+            proj_to_machine_states: BTreeMap::new(),
+        };
+        let mut runner = MachineRunner::new_bt(projection_info).unwrap();
+
+        let results = runner.run(&[
+            event("e1", "partID", None),
+            // points to the wrong branching event
+            event("e2", "pos", None),
+            event("e3", "pos", Some("e1")),
+        ]);
+        assert_eq!(
+            results,
+            vec![
+                PushResult::React {
+                    from: State::new("0"),
+                    to: State::new("1")
+                },
+                PushResult::Discard,
+                PushResult::React {
+                    from: State::new("1"),
+                    to: State::new("2")
+                },
+            ]
+        );
+        assert_eq!(runner.lbj(&EventType::new("part")), Some("e1".to_string()));
+        assert_eq!(runner.lbj(&EventType::new("time")), None);
+
+        runner.push(&event("e4", "part", Some("e1")));
+        assert_eq!(runner.state(), &State::new("0"));
+        assert_eq!(runner.lbj(&EventType::new("time")), Some("e4".to_string()));
+        assert_eq!(runner.push(&event("e5", "time", None)), PushResult::Discard);
+
+        runner.reset();
+        assert_eq!(runner.lbj(&EventType::new("part")), None);
+    }
+
+    #[test]
+    fn test_runner_invalid() {
+        test_utils::setup_logger();
+        let mut machine = get_machine();
+        machine.transitions.push(machine.transitions[1].clone());
+        machine.transitions[6].target = State::new("3");
+        assert_eq!(
+            MachineRunner::new(machine).unwrap_err(),
+            vec!["non-deterministic reaction to event type partID in state 0".to_string()]
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use crate::composability_check;
+use crate::parallel;
+use crate::subscription::{exact, overapproximation};
+use crate::types::{
+    proto_info::{self, ProtoInfo},
+    typescript_types::{
+        Granularity, InterfacingProtocols, Subscriptions, SwarmLabel, SwarmProtocolType, Transition,
+    },
+};
+
+// How a subscription was computed. Part of the key of the subscription cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Method {
+    Exact,
+    Overapproximated(Granularity),
+}
+
+// An analysis session over a composition of protocols that are edited over time.
+// Keeps the ProtoInfo of each protocol and the combined ProtoInfo of every prefix of the composition.
+// An edit to protocol i only recomputes protocol i and the combinations of the prefixes containing it.
+// The combined ProtoInfo, the explicit composition and subscriptions are computed when requested and
+// cached until the next edit. Results are the same as those of swarms_to_proto_info,
+// exact_well_formed_sub (with max_states) and overapprox_well_formed_sub on the current protocols.
+pub struct Session {
+    protocols: Vec<SwarmProtocolType>,
+    // limit on the number of states of the explicit composition.
+    max_states: usize,
+    // confusion_free_proto_info(prepare_proto_info(p)) for each protocol p.
+    prepared: Vec<ProtoInfo>,
+    // combined[i] is the combination of prepared[0..=i], without joining events.
+    // May be shorter than prepared, the remaining prefixes are combined when needed.
+    combined: Vec<ProtoInfo>,
+    proto_info: Option<ProtoInfo>,
+    composition: Option<ProtoInfo>,
+    subscriptions: Vec<(Method, Subscriptions, Subscriptions)>,
+}
+
+impl Session {
+    // Exact subscriptions fail with Error::StateSpaceLimitExceeded if the composition has more than max_states states.
+    pub fn new(protos: InterfacingProtocols, max_states: usize) -> Self {
+        let _span = tracing::info_span!("session_new").entered();
+        let prepared = parallel::map(protos.0.clone(), prepare);
+        Self {
+            protocols: protos.0,
+            max_states,
+            prepared,
+            combined: vec![],
+            proto_info: None,
+            composition: None,
+            subscriptions: vec![],
+        }
+    }
+
+    pub fn protocols(&self) -> InterfacingProtocols {
+        InterfacingProtocols(self.protocols.clone())
+    }
+
+    // Add a protocol to the composition. The combinations of the existing protocols are kept.
+    pub fn add_protocol(&mut self, proto: SwarmProtocolType) {
+        self.prepared.push(prepare(proto.clone()));
+        self.protocols.push(proto);
+        self.invalidate();
+    }
+
+    // Add a transition to the protocol at index proto_pointer.
+    pub fn add_transition(
+        &mut self,
+        proto_pointer: usize,
+        transition: Transition<SwarmLabel>,
+    ) -> Result<(), String> {
+        let proto = self.get_protocol_mut(proto_pointer)?;
+        proto.transitions.push(transition);
+        self.update_protocol(proto_pointer);
+        Ok(())
+    }
+
+    // Remove a transition from the protocol at index proto_pointer.
+    pub fn remove_transition(
+        &mut self,
+        proto_pointer: usize,
+        transition: &Transition<SwarmLabel>,
+    ) -> Result<(), String> {
+        let proto = self.get_protocol_mut(proto_pointer)?;
+        let Some(position) = proto.transitions.iter().position(|t| t == transition) else {
+            return Err(format!(
+                "transition {} --({})--> {} not in protocol {proto_pointer}",
+                transition.source, transition.label.cmd, transition.target
+            ));
+        };
+        proto.transitions.remove(position);
+        self.update_protocol(proto_pointer);
+        Ok(())
+    }
+
+    // The combined ProtoInfo of all protocols, as returned by swarms_to_proto_info.
+    pub fn proto_info(&mut self) -> &ProtoInfo {
+        if self.proto_info.is_none() {
+            let _span = tracing::info_span!("session_proto_info").entered();
+            for i in self.combined.len()..self.prepared.len() {
+                let next = match self.combined.last() {
+                    Some(combined) => proto_info::combine_two_proto_infos(
+                        combined.clone(),
+                        self.prepared[i].clone(),
+                    ),
+                    None => self.prepared[i].clone(),
+                };
+                self.combined.push(next);
+            }
+            let mut combined = self
+                .combined
+                .last()
+                .cloned()
+                .unwrap_or_else(|| ProtoInfo::new_only_proto(vec![]));
+            combined.joining_events = proto_info::joining_event_types_map(&combined);
+            self.proto_info = Some(combined);
+        }
+        self.proto_info.as_ref().unwrap()
+    }
+
+    // Same as exact_well_formed_sub on the current protocols.
+    pub fn exact_well_formed_sub(
+        &mut self,
+        subs: &Subscriptions,
+    ) -> Result<Subscriptions, Vec<String>> {
+        self.well_formed_sub(Method::Exact, subs)
+    }
+
+    // Same as overapprox_well_formed_sub on the current protocols.
+    pub fn overapprox_well_formed_sub(
+        &mut self,
+        subs: &Subscriptions,
+        granularity: Granularity,
+    ) -> Result<Subscriptions, Vec<String>> {
+        self.well_formed_sub(Method::Overapproximated(granularity), subs)
+    }
+
+    fn well_formed_sub(
+        &mut self,
+        method: Method,
+        subs: &Subscriptions,
+    ) -> Result<Subscriptions, Vec<String>> {
+        if let Some((_, _, result)) = self
+            .subscriptions
+            .iter()
+            .find(|(m, s, _)| *m == method && s == subs)
+        {
+            return Ok(result.clone());
+        }
+        let proto_info = self.proto_info().clone();
+        if !proto_info.no_errors() {
+            return Err(proto_info.to_error_report().to_strings());
+        }
+        let result = match &method {
+            Method::Exact => {
+                if self.composition.is_none() {
+                    self.composition = Some(
+                        proto_info::explicit_composition_proto_info_bounded(
+                            proto_info,
+                            self.max_states,
+                        )
+                        .map_err(|errors| errors.to_strings())?,
+                    );
+                }
+                exact::exact_wf_sub(self.composition.clone().unwrap(), 0, subs)
+            }
+            Method::Overapproximated(granularity) => overapproximation::overapprox_wf_sub(
+                &mut proto_info.clone(),
+                subs,
+                granularity.clone(),
+            ),
+        };
+        self.subscriptions
+            .push((method, subs.clone(), result.clone()));
+        Ok(result)
+    }
+
+    fn get_protocol_mut(&mut self, proto_pointer: usize) -> Result<&mut SwarmProtocolType, String> {
+        let len = self.protocols.len();
+        self.protocols
+            .get_mut(proto_pointer)
+            .ok_or_else(|| format!("no protocol at index {proto_pointer}, session has {len}"))
+    }
+
+    // Recompute the protocol at proto_pointer and drop the combinations that contain it.
+    fn update_protocol(&mut self, proto_pointer: usize) {
+        self.prepared[proto_pointer] = prepare(self.protocols[proto_pointer].clone());
+        self.combined.truncate(proto_pointer);
+        self.invalidate();
+    }
+
+    fn invalidate(&mut self) {
+        self.proto_info = None;
+        self.composition = None;
+        self.subscriptions.clear();
+    }
+}
+
+fn prepare(proto: SwarmProtocolType) -> ProtoInfo {
+    composability_check::confusion_free_proto_info(proto_info::prepare_proto_info(proto))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::types::proto_info::DEFAULT_MAX_STATES;
+    use crate::types::typescript_types::{Command, EventType, Role, State};
+
+    // The fields of a proto info, for comparing a session against the from-scratch analysis.
+    fn fields(proto_info: &ProtoInfo) -> Vec<String> {
+        vec![
+            format!("{:?}", proto_info.role_event_map),
+            format!("{:?}", proto_info.concurrent_events),
+            format!("{:?}", proto_info.branching_events),
+            format!("{:?}", proto_info.joining_events),
+            format!("{:?}", proto_info.immediately_pre),
+            format!("{:?}", proto_info.succeeding_events),
+            format!("{:?}", proto_info.interfacing_events),
+            format!("{:?}", proto_info.infinitely_looping_events),
+            format!("{:?}", proto_info.clone().to_error_report().to_strings()),
+        ]
+    }
+
+    fn assert_same_as_from_scratch(session: &mut Session, subs: &Subscriptions) {
+        let expected = proto_info::swarms_to_proto_info(session.protocols());
+        assert_eq!(fields(session.proto_info()), fields(&expected));
+        let exact = exact::exact_well_formed_sub(session.protocols(), subs, session.max_states)
+            .map_err(|e| e.to_strings());
+        assert_eq!(session.exact_well_formed_sub(subs), exact);
+        for granularity in [Granularity::Fine, Granularity::Coarse, Granularity::TwoStep] {
+            let overapproximated = overapproximation::overapprox_well_formed_sub(
+                session.protocols(),
+                subs,
+                granularity.clone(),
+            )
+            .map_err(|e| e.to_strings());
+            assert_eq!(
+                session.overapprox_well_formed_sub(subs, granularity),
+                overapproximated
+            );
+        }
+    }
+
+    fn transition(
+        source: &str,
+        target: &str,
+        cmd: &str,
+        event_type: &str,
+        role: &str,
+    ) -> Transition<SwarmLabel> {
+        Transition {
+            label: SwarmLabel {
+                cmd: Command::new(cmd),
+                log_type: vec![EventType::new(event_type)],
+                role: Role::new(role),
+                guard: None,
+                timeout: None,
+            },
+            source: State::new(source),
+            target: State::new(target),
+        }
+    }
+
+    #[test]
+    fn test_session_edits() {
+        test_utils::setup_logger();
+        let subs = Subscriptions::new();
+        let mut session = Session::new(
+            InterfacingProtocols(vec![test_utils::get_proto1()]),
+            DEFAULT_MAX_STATES,
+        );
+        assert_same_as_from_scratch(&mut session, &subs);
+
+        session.add_protocol(test_utils::get_proto2());
+        assert_same_as_from_scratch(&mut session, &subs);
+
+        session.add_protocol(test_utils::get_proto3());
+        assert_same_as_from_scratch(&mut session, &subs);
+
+        // Make the forklift check the part before delivering it. Adds a branch to the first protocol.
+        let check = transition("1", "2", "check", "checked", "FL");
+        session.add_transition(0, check.clone()).unwrap();
+        assert_same_as_from_scratch(&mut session, &subs);
+
+        session.remove_transition(0, &check).unwrap();
+        assert_same_as_from_scratch(&mut session, &subs);
+        assert_eq!(
+            session.protocols().0,
+            test_utils::get_interfacing_swarms_2().0
+        );
+    }
+
+    #[test]
+    fn test_session_errors() {
+        test_utils::setup_logger();
+        let subs = Subscriptions::new();
+        let mut session = Session::new(test_utils::get_interfacing_swarms_1(), DEFAULT_MAX_STATES);
+        let missing = transition("0", "1", "missing", "missing", "T");
+        assert!(session.remove_transition(0, &missing).is_err());
+        assert!(session.add_transition(2, missing.clone()).is_err());
+
+        // A second command emitting partID makes the first protocol confusionful.
+        session
+            .add_transition(0, transition("0", "4", "request2", "partID", "T"))
+            .unwrap();
+        assert!(!session.proto_info().no_errors());
+        assert!(session.exact_well_formed_sub(&subs).is_err());
+        assert_same_as_from_scratch(&mut session, &subs);
+    }
+
+    #[test]
+    fn test_session_cache() {
+        test_utils::setup_logger();
+        let subs: Subscriptions =
+            serde_json::from_str(r#"{ "T": ["partID", "part"], "F": ["car"] }"#).unwrap();
+        let mut session = Session::new(test_utils::get_interfacing_swarms_2(), DEFAULT_MAX_STATES);
+        let first = session.exact_well_formed_sub(&subs).unwrap();
+        assert_eq!(session.subscriptions.len(), 1);
+        assert_eq!(session.exact_well_formed_sub(&subs).unwrap(), first);
+        assert_eq!(session.subscriptions.len(), 1);
+        assert_eq!(session.combined.len(), 3);
+
+        // Editing the second protocol keeps the combination of the first.
+        session
+            .add_transition(1, transition("3", "4", "paint", "painted", "F"))
+            .unwrap();
+        assert!(session.subscriptions.is_empty());
+        assert_eq!(session.combined.len(), 1);
+        assert_same_as_from_scratch(&mut session, &subs);
+    }
+
+    #[test]
+    fn test_session_state_limit() {
+        test_utils::setup_logger();
+        let subs = Subscriptions::new();
+        let mut session = Session::new(test_utils::get_interfacing_swarms_2(), 2);
+        assert_eq!(
+            session.exact_well_formed_sub(&subs),
+            Err(vec![
+                "composition exceeds the limit of 2 states".to_string()
+            ])
+        );
+        assert!(
+            session
+                .overapprox_well_formed_sub(&subs, Granularity::TwoStep)
+                .is_ok()
+        );
+        assert_same_as_from_scratch(&mut session, &subs);
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use petgraph::{Direction::Outgoing, visit::EdgeRef};
+
+use crate::machine::{projection, util};
+use crate::runtime::{Event, MachineRunner};
+use crate::types::{
+    proto_graph::{Graph, NodeId},
+    proto_info,
+    typescript_types::{
+        EventLabel, EventType, InterfacingProtocols, Role, State, StateName, Subscriptions,
+        SwarmProtocolType,
+    },
+};
+
+// Parameters of a simulation run. A run is fully determined by its parameters.
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    pub seed: u64,
+    // number of ticks during which peers may emit events
+    pub steps: usize,
+    // probability that a peer with enabled commands emits in a tick
+    pub emit_probability: f64,
+    // events reach other peers after between 0 and max_delay ticks
+    pub max_delay: u64,
+    // probability that the network splits in two in a tick
+    pub partition_probability: f64,
+    // number of ticks before a partition is merged again
+    pub partition_duration: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            steps: 50,
+            emit_probability: 0.3,
+            max_delay: 5,
+            partition_probability: 0.05,
+            partition_duration: 10,
+        }
+    }
+}
+
+// An event in the log of a peer. Logs are kept in Lamport order,
+// ties broken by emitting peer and the peer's sequence number.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SimEvent {
+    pub lamport: u64,
+    pub peer: usize,
+    pub seq: u64,
+    pub event_type: EventType,
+    pub role: Role,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerReport {
+    pub role: Role,
+    pub machine_state: State,
+    // the state of the protocol after the valid events of the peer's log
+    pub protocol_state: State,
+    pub log_len: usize,
+    // whether machine_state is a projection of protocol_state
+    pub consistent: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulationReport {
+    pub converged: bool,
+    pub peers: Vec<PeerReport>,
+    // the log all peers agree on once every event has been delivered
+    pub log: Vec<SimEvent>,
+    // events of the log that are not valid in the protocol and hence ignored
+    pub invalid_events: usize,
+    pub diagnostics: Vec<String>,
+}
+
+// SplitMix64. Small, seedable and good enough for picking delays and peers.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        if n == 0 { 0 } else { self.next_u64() % n }
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+}
+
+struct Peer {
+    role: Role,
+    runner: MachineRunner,
+    // projection state to the protocol states it represents
+    represents: BTreeMap<State, BTreeSet<State>>,
+    log: BTreeSet<SimEvent>,
+    lamport: u64,
+    seq: u64,
+}
+
+impl Peer {
+    fn insert(&mut self, event: SimEvent) {
+        self.lamport = self.lamport.max(event.lamport);
+        if self.log.insert(event) {
+            self.replay();
+        }
+    }
+
+    // events may arrive out of order, so the machine is run on the whole log again
+    fn replay(&mut self) {
+        self.runner.reset();
+        for e in &self.log {
+            self.runner.push(&Event {
+                event_id: format!("{}-{}", e.peer, e.seq),
+                event_type: e.event_type.clone(),
+                lbj: None,
+            });
+        }
+    }
+}
+
+struct InFlight {
+    deliver_at: u64,
+    from: usize,
+    to: usize,
+    event: SimEvent,
+}
+
+// Simulate a swarm with one peer per role, each running the projection of proto
+// over its role w.r.t. subs. Peers emit events through enabled commands and
+// exchange them with random delays and network partitions. Once all events are
+// delivered, checks that all peers agree on the protocol state and that the
+// state of each machine corresponds to it.
+pub fn simulate(
+    proto: SwarmProtocolType,
+    subs: &Subscriptions,
+    config: &SimulationConfig,
+) -> Result<SimulationReport, Vec<String>> {
+    let _span = tracing::info_span!("simulate", seed = config.seed).entered();
+    let proto_info = proto_info::swarms_to_proto_info(InterfacingProtocols(vec![proto]));
+    if !proto_info.no_errors() {
+        return Err(proto_info.to_error_report().to_strings());
+    }
+    let (graph, initial) = match proto_info.protocols[0].initial {
+        Some(initial) => (proto_info.protocols[0].graph.clone(), initial),
+        None => {
+            return Err(vec![
+                "initial swarm protocol state has no transitions".to_string(),
+            ]);
+        }
+    };
+
+    let mut peers = vec![];
+    for role in proto_info.protocols[0].roles.iter() {
+        let (proj, proj_initial) =
+            projection::project_with_provenance(&graph, initial, subs, role.clone(), true);
+        let represents = proj
+            .node_weights()
+            .map(|n| (n.state.clone(), n.proto_states[0].clone()))
+            .collect();
+        let machine = util::to_json_machine(projection::from_provenance_graph(&proj), proj_initial);
+        peers.push(Peer {
+            role: role.clone(),
+            runner: MachineRunner::new(machine)?,
+            represents,
+            log: BTreeSet::new(),
+            lamport: 0,
+            seq: 0,
+        });
+    }
+
+    let mut rng = Rng(config.seed);
+    let mut in_flight: Vec<InFlight> = vec![];
+    // peers on the side of the partition given by the mask, and the tick at which it is merged
+    let mut partition: Option<(Vec<bool>, u64)> = None;
+    let mut now = 0;
+    while now < config.steps as u64 || !in_flight.is_empty() {
+        let emitting = now < config.steps as u64;
+        if partition
+            .as_ref()
+            .is_some_and(|(_, until)| *until <= now || !emitting)
+        {
+            tracing::debug!(now, "merging partition");
+            partition = None;
+        }
+        if emitting && partition.is_none() && rng.chance(config.partition_probability) {
+            let mask: Vec<bool> = (0..peers.len()).map(|_| rng.chance(0.5)).collect();
+            tracing::debug!(now, ?mask, "partitioning");
+            partition = Some((mask, now + config.partition_duration));
+        }
+        let separated = |from: usize, to: usize| {
+            partition
+                .as_ref()
+                .is_some_and(|(mask, _)| mask[from] != mask[to])
+        };
+
+        let (deliver, keep): (Vec<_>, Vec<_>) = in_flight
+            .into_iter()
+            .partition(|m| m.deliver_at <= now && !separated(m.from, m.to));
+        in_flight = keep;
+        for m in deliver {
+            peers[m.to].insert(m.event);
+        }
+
+        if emitting {
+            for i in 0..peers.len() {
+                let commands = peers[i].runner.enabled_commands();
+                if commands.is_empty() || !rng.chance(config.emit_probability) {
+                    continue;
+                }
+                let (cmd, log_type) = &commands[rng.below(commands.len() as u64) as usize];
+                tracing::debug!(now, peer = i, %cmd, "emitting");
+                for event_type in log_type {
+                    let event = SimEvent {
+                        lamport: peers[i].lamport + 1,
+                        peer: i,
+                        seq: peers[i].seq,
+                        event_type: event_type.clone(),
+                        role: peers[i].role.clone(),
+                    };
+                    peers[i].seq += 1;
+                    for to in (0..peers.len()).filter(|to| *to != i) {
+                        in_flight.push(InFlight {
+                            deliver_at: now + rng.below(config.max_delay + 1),
+                            from: i,
+                            to,
+                            event: event.clone(),
+                        });
+                    }
+                    peers[i].insert(event);
+                }
+            }
+        }
+        now += 1;
+    }
+
+    let mut diagnostics = vec![];
+    let mut reports = vec![];
+    for (i, peer) in peers.iter().enumerate() {
+        let (protocol_state, _) = protocol_state(&graph, initial, &peer.log);
+        let machine_state = peer.runner.state().clone();
+        let consistent = peer
+            .represents
+            .get(&machine_state)
+            .is_some_and(|states| states.contains(&protocol_state));
+        if !consistent {
+            diagnostics.push(format!(
+                "peer {i} (role {}) is in machine state {machine_state}, which does not correspond to protocol state {protocol_state}",
+                peer.role
+            ));
+        }
+        reports.push(PeerReport {
+            role: peer.role.clone(),
+            machine_state,
+            protocol_state,
+            log_len: peer.log.len(),
+            consistent,
+        });
+    }
+    if peers.iter().any(|p| p.log != peers[0].log) {
+        diagnostics.push("peers did not receive the same events".to_string());
+    }
+    if reports
+        .iter()
+        .any(|r| r.protocol_state != reports[0].protocol_state)
+    {
+        diagnostics.push("peers disagree on the protocol state".to_string());
+    }
+
+    let log = peers
+        .first()
+        .map(|p| p.log.iter().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    let invalid_events = peers
+        .first()
+        .map(|p| protocol_state(&graph, initial, &p.log).1)
+        .unwrap_or(0);
+
+    Ok(SimulationReport {
+        converged: diagnostics.is_empty(),
+        peers: reports,
+        log,
+        invalid_events,
+        diagnostics,
+    })
+}
+
+// Interpret a log in a swarm protocol. Events that are not valid in the state
+// reached so far are ignored. Returns the resulting state and the number of ignored events.
+fn protocol_state(graph: &Graph, initial: NodeId, log: &BTreeSet<SimEvent>) -> (State, usize) {
+    let mut current = initial;
+    let mut invalid = 0;
+    for event in log {
+        let next = graph.edges_directed(current, Outgoing).find(|edge| {
+            edge.weight().get_event_type() == event.event_type && edge.weight().role == event.role
+        });
+        match next {
+            Some(edge) => current = edge.target(),
+            None => invalid += 1,
+        }
+    }
+    (graph[current].state_name().clone(), invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subscription::overapproximation;
+    use crate::test_utils;
+    use crate::types::typescript_types::Granularity;
+
+    // the role choosing a branch is not the one that finishes it
+    fn get_branching_proto() -> SwarmProtocolType {
+        serde_json::from_str::<SwarmProtocolType>(
+            r#"{
+                "initial": "0",
+                "transitions": [
+                    { "source": "0", "target": "1", "label": { "cmd": "a", "logType": ["a"], "role": "R1" } },
+                    { "source": "1", "target": "2", "label": { "cmd": "c", "logType": ["c"], "role": "R2" } },
+                    { "source": "0", "target": "3", "label": { "cmd": "b", "logType": ["b"], "role": "R1" } },
+                    { "source": "3", "target": "4", "label": { "cmd": "d", "logType": ["d"], "role": "R2" } }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_simulate_well_formed() {
+        test_utils::setup_logger();
+        let proto = test_utils::get_proto1();
+        let subs = overapproximation::overapprox_well_formed_sub(
+            InterfacingProtocols(vec![proto.clone()]),
+            &BTreeMap::new(),
+            Granularity::TwoStep,
+        )
+        .unwrap();
+        for seed in 0..20 {
+            let config = SimulationConfig {
+                seed,
+                ..SimulationConfig::default()
+            };
+            let report = simulate(proto.clone(), &subs, &config).unwrap();
+            assert!(report.converged, "seed {seed}: {:?}", report.diagnostics);
+            assert!(!report.log.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_simulate_deterministic() {
+        test_utils::setup_logger();
+        let proto = test_utils::get_proto1();
+        let subs = overapproximation::overapprox_well_formed_sub(
+            InterfacingProtocols(vec![proto.clone()]),
+            &BTreeMap::new(),
+            Granularity::TwoStep,
+        )
+        .unwrap();
+        let config = SimulationConfig {
+            seed: 42,
+            ..SimulationConfig::default()
+        };
+        assert_eq!(
+            simulate(proto.clone(), &subs, &config).unwrap(),
+            simulate(proto, &subs, &config).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_simulate_bad_subscription() {
+        test_utils::setup_logger();
+        let proto = get_branching_proto();
+        let good_subs = overapproximation::overapprox_well_formed_sub(
+            InterfacingProtocols(vec![proto.clone()]),
+            &BTreeMap::new(),
+            Granularity::TwoStep,
+        )
+        .unwrap();
+        // R2 does not learn which branch R1 chose
+        let bad_subs = BTreeMap::from([
+            (
+                Role::new("R1"),
+                BTreeSet::from([
+                    EventType::new("a"),
+                    EventType::new("b"),
+                    EventType::new("c"),
+                    EventType::new("d"),
+                ]),
+            ),
+            (
+                Role::new("R2"),
+                BTreeSet::from([EventType::new("c"), EventType::new("d")]),
+            ),
+        ]);
+
+        let mut diverged = false;
+        for seed in 0..20 {
+            let config = SimulationConfig {
+                seed,
+                ..SimulationConfig::default()
+            };
+            let report = simulate(proto.clone(), &good_subs, &config).unwrap();
+            assert!(report.converged, "seed {seed}: {:?}", report.diagnostics);
+            let report = simulate(proto.clone(), &bad_subs, &config).unwrap();
+            diverged = diverged || !report.converged;
+        }
+        assert!(diverged);
+    }
+}
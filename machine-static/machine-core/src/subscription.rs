@@ -1,9 +1,13 @@
 use std::collections::BTreeSet;
 
-use crate::types::proto_info;
+use petgraph::visit::{EdgeRef, IntoEdges, IntoNodeIdentifiers};
+
+use crate::types::indexed::{Indexed, IndexedSubscriptions};
 use crate::types::{
+    proto_graph,
     proto_info::ProtoInfo,
-    typescript_types::{EventType, Role, Subscriptions},
+    typescript_types::{Subscriptions, SwarmLabel},
+    unordered_event_pair::UnordEventPair,
 };
 
 pub mod exact;
@@ -13,45 +17,73 @@ pub mod overapproximation;
 // For each event type t that does not lead to a terminal state, check looping condition from determinacy:
 // if t is not in subscriptions, add it to all roles in roles(t, G).
 // Awkwardly placed here because it is used by exact and overapproximation.
-fn add_looping_event_types(proto_info: &ProtoInfo, subscriptions: &mut Subscriptions) {
+fn add_looping_event_types(
+    proto_info: &ProtoInfo,
+    indexed: &Indexed,
+    subscriptions: &mut IndexedSubscriptions,
+) {
     let _span = tracing::info_span!("add_looping_event_types").entered();
 
     // For each event type t in the set of event types that can not reach a terminal state, check predicate adding t to subs of all involved roles if false.
     // iter() for BTreeSet, gets an iterator that visits the elements in the BTreeSet in ascending order.
     // https://doc.rust-lang.org/std/collections/struct.BTreeSet.html#method.iter
     for t in proto_info.infinitely_looping_events.iter() {
-        let t_and_after_t: BTreeSet<EventType> = [t.clone()]
-            .into_iter()
-            .chain(proto_info.get_succeeding(t))
-            .collect();
-        let involved_roles = proto_info::roles_on_path(t.clone(), proto_info, subscriptions);
-
-        // If there is not an event type among t_and_after_t such that all roles subscribe to this event type, add t to the subscription of all involved roles.
-        if !all_roles_sub_to_same(t_and_after_t, &involved_roles, &subscriptions) {
-            for r in involved_roles.iter() {
-                subscriptions
-                    .entry(r.clone())
-                    .and_modify(|set| {
-                        set.insert(t.clone());
-                    })
-                    .or_insert_with(|| BTreeSet::from([t.clone()]));
+        let involved_roles = indexed.roles_on_path(indexed.event(t), subscriptions);
+
+        // If there is not an event type among t and the event types after it such that all roles subscribe to this event type, add t to the subscription of all involved roles.
+        if !indexed.all_subscribe_on_path(indexed.event(t), &involved_roles, subscriptions) {
+            let t = indexed.event_set([t]);
+            for r in involved_roles {
+                subscriptions.add(r, &t);
             }
         }
     }
 }
 
-// True if there exists an event type in event_types such that all roles in involved_roles subscribe to it.
-fn all_roles_sub_to_same(
-    event_types: BTreeSet<EventType>,
-    involved_roles: &BTreeSet<Role>,
-    subscriptions: &Subscriptions,
-) -> bool {
-    let _span = tracing::info_span!("all_roles_sub_to_same").entered();
-    event_types.into_iter().any(|t_| {
-        involved_roles.iter().all(|r| {
+// Add the event types read by the data guard of a transition to the subscription of the role executing it.
+// Done before the fixpoints, so that the rest of the subscription accounts for these event types.
+fn add_guard_event_types(proto_info: &ProtoInfo, subscriptions: &mut Subscriptions) {
+    let _span = tracing::info_span!("add_guard_event_types").entered();
+    for label in proto_info.role_event_map.values().flatten() {
+        if let Some(guard) = &label.guard {
             subscriptions
-                .get(r)
-                .is_some_and(|event_types_r| event_types_r.contains(&t_))
-        })
-    })
+                .entry(label.role.clone())
+                .or_default()
+                .extend(guard.event_types());
+        }
+    }
+}
+
+// Add the event types competing with a timeout transition to the subscription of the role taking it.
+// The role has to observe that its source state was not left before the timer expired.
+fn add_timeout_event_types(proto_info: &ProtoInfo, subscriptions: &mut Subscriptions) {
+    let _span = tracing::info_span!("add_timeout_event_types").entered();
+    for proto in &proto_info.protocols {
+        add_timeout_event_types_in(&proto.graph, &proto_info.concurrent_events, subscriptions);
+    }
+}
+
+// add_timeout_event_types for the timeout transitions of graph.
+fn add_timeout_event_types_in<G>(
+    graph: G,
+    concurrent_events: &BTreeSet<UnordEventPair>,
+    subscriptions: &mut Subscriptions,
+) where
+    G: IntoEdges<EdgeWeight = SwarmLabel> + IntoNodeIdentifiers,
+{
+    for node in graph.node_identifiers() {
+        for edge in graph.edges(node) {
+            let label = edge.weight();
+            if label.timeout.is_some() {
+                subscriptions.entry(label.role.clone()).or_default().extend(
+                    proto_graph::competing_event_types_at(
+                        graph,
+                        node,
+                        edge.id(),
+                        concurrent_events,
+                    ),
+                );
+            }
+        }
+    }
 }
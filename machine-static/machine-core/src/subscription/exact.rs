@@ -2,23 +2,29 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use petgraph::{
     Direction::{Incoming, Outgoing},
-    visit::{Dfs, EdgeRef, Walker},
+    visit::{Dfs, EdgeRef, IntoEdgesDirected, IntoNodeIdentifiers, Visitable, Walker},
 };
 
+use crate::lazy_composition::LazyComposition;
+use crate::types::indexed::{Indexed, IndexedSubscriptions};
 use crate::types::{proto_graph, proto_info, unordered_event_pair::UnordEventPair};
 use crate::{
     errors::ErrorReport,
     types::{
-        proto_graph::{Graph, NodeId},
+        proto_graph::NodeId,
         proto_info::{ProtoInfo, ProtoStruct},
-        typescript_types::{EventLabel, EventType, InterfacingProtocols, Role, Subscriptions},
+        typescript_types::{
+            EventLabel, EventType, InterfacingProtocols, Subscriptions, SwarmLabel,
+        },
     },
 };
 
 // Construct a wf-subscription by constructing the composition of all protocols in protos and analyzing the result
+// Fails with Error::StateSpaceLimitExceeded if the composition of protos has more than max_states states.
 pub fn exact_well_formed_sub(
     protos: InterfacingProtocols,
     subs: &Subscriptions,
+    max_states: usize,
 ) -> Result<Subscriptions, ErrorReport> {
     let _span = tracing::info_span!("exact_well_formed_sub").entered();
     let combined_proto_info = proto_info::swarms_to_proto_info(protos);
@@ -30,16 +36,57 @@ pub fn exact_well_formed_sub(
     // We construct a ProtoInfo with the composition as the only protocol and all the
     // information about branches etc. from combined_proto_info
     // and the succeeding_events field updated using the expanded composition.
-    let composition = proto_info::explicit_composition_proto_info(combined_proto_info);
+    let composition =
+        proto_info::explicit_composition_proto_info_bounded(combined_proto_info, max_states)?;
     let sub = exact_wf_sub(composition, 0, subs);
 
     Ok(sub)
 }
 
+// Like exact_well_formed_sub, but analyzing a LazyComposition of protos instead of building the composition.
+// Fails with Error::StateSpaceLimitExceeded if the composition of protos has more than max_states states.
+pub fn lazy_well_formed_sub(
+    protos: InterfacingProtocols,
+    subs: &Subscriptions,
+    max_states: usize,
+) -> Result<Subscriptions, ErrorReport> {
+    let _span = tracing::info_span!("lazy_well_formed_sub").entered();
+    let proto_info = proto_info::swarms_to_proto_info(protos);
+    if !proto_info.no_errors() {
+        return Err(proto_info.to_error_report());
+    }
+    let Some(view) = LazyComposition::new(&proto_info, max_states) else {
+        return Ok(BTreeMap::new());
+    };
+    let composition = view.composition_proto_info()?;
+    Ok(exact_wf_sub_in(&composition, &view, view.initial(), subs))
+}
+
+// The event types each role has to subscribe to in addition to subs for subs to be well-formed,
+// i.e. what lazy_well_formed_sub adds to subs. Empty if subs is well-formed.
+// Fails with Error::StateSpaceLimitExceeded if the composition of protos has more than max_states states.
+pub fn lazy_missing_subscriptions(
+    protos: InterfacingProtocols,
+    subs: &Subscriptions,
+    max_states: usize,
+) -> Result<Subscriptions, ErrorReport> {
+    let _span = tracing::info_span!("lazy_missing_subscriptions").entered();
+    let empty = BTreeSet::new();
+    Ok(lazy_well_formed_sub(protos, subs, max_states)?
+        .into_iter()
+        .map(|(role, events)| {
+            let given = subs.get(&role).unwrap_or(&empty);
+            let missing: BTreeSet<EventType> = events.difference(given).cloned().collect();
+            (role, missing)
+        })
+        .filter(|(_, missing)| !missing.is_empty())
+        .collect())
+}
+
 // Given a swarm protocol return the smallest wf-subscription.
 // Expand composition and apply rules from definition of wf until subscription stabilizes.
 // Uses new, compositional definition of wf.
-fn exact_wf_sub(
+pub(crate) fn exact_wf_sub(
     proto_info: ProtoInfo,
     proto_pointer: usize,
     subscriptions: &Subscriptions,
@@ -54,43 +101,53 @@ fn exact_wf_sub(
         }) => (g, i),
         _ => return BTreeMap::new(),
     };
+    if graph.node_count() == 0 || initial == NodeId::end() {
+        return subscriptions.clone();
+    }
+    exact_wf_sub_in(&proto_info, &graph, initial, subscriptions)
+}
+
+// exact_wf_sub for the composition graph, e.g. a LazyComposition, starting in initial.
+// proto_info holds the succeeding and infinitely looping event types of graph.
+fn exact_wf_sub_in<G>(
+    proto_info: &ProtoInfo,
+    graph: G,
+    initial: G::NodeId,
+    subscriptions: &Subscriptions,
+) -> Subscriptions
+where
+    G: IntoEdgesDirected<EdgeWeight = SwarmLabel> + IntoNodeIdentifiers + Visitable,
+{
     let mut subscriptions = subscriptions.clone();
-    let mut is_stable = exact_wf_sub_step(&proto_info, &graph, initial, &mut subscriptions);
+    super::add_guard_event_types(proto_info, &mut subscriptions);
+    super::add_timeout_event_types_in(graph, &proto_info.concurrent_events, &mut subscriptions);
+    let indexed = Indexed::new(proto_info, &subscriptions);
+    let mut indexed_subs = indexed.subscriptions(&subscriptions);
+    let mut is_stable = exact_wf_sub_step(proto_info, &indexed, graph, initial, &mut indexed_subs);
     while !is_stable {
-        is_stable = exact_wf_sub_step(&proto_info, &graph, initial, &mut subscriptions);
+        is_stable = exact_wf_sub_step(proto_info, &indexed, graph, initial, &mut indexed_subs);
     }
 
     // Handle looping event types
-    super::add_looping_event_types(&proto_info, &mut subscriptions);
+    super::add_looping_event_types(proto_info, &indexed, &mut indexed_subs);
 
-    subscriptions
+    indexed.to_subscriptions(&indexed_subs)
 }
 
 // Apply rules from WF defintion to add event types to subscription.
-fn exact_wf_sub_step(
+fn exact_wf_sub_step<G>(
     proto_info: &ProtoInfo,
-    graph: &Graph,
-    initial: NodeId,
-    subscriptions: &mut Subscriptions,
-) -> bool {
+    indexed: &Indexed,
+    graph: G,
+    initial: G::NodeId,
+    subscriptions: &mut IndexedSubscriptions,
+) -> bool
+where
+    G: IntoEdgesDirected<EdgeWeight = SwarmLabel> + Visitable,
+{
     let _span = tracing::info_span!("exact_wf_sub_step").entered();
-    if graph.node_count() == 0 || initial == NodeId::end() {
-        return true;
-    }
     let mut is_stable = true;
-    let add_to_sub =
-        |role: Role, mut event_types: BTreeSet<EventType>, subs: &mut Subscriptions| -> bool {
-            if subs.contains_key(&role) && event_types.iter().all(|e| subs[&role].contains(e)) {
-                return true;
-            }
-            subs.entry(role)
-                .and_modify(|curr| {
-                    curr.append(&mut event_types);
-                })
-                .or_insert(event_types);
-            false
-        };
-    for node in Dfs::new(&graph, initial).iter(&graph) {
+    for node in Dfs::new(graph, initial).iter(graph) {
         // For each edge going out of node:
         //  Extend subscriptions to satisfy conditions for causal consistency
         //  Make role performing the command subscribe to the emitted event type
@@ -98,31 +155,25 @@ fn exact_wf_sub_step(
         //  Make an overapproximation of the roles in roles(e.G) subscribe to branching events.
         for edge in graph.edges_directed(node, Outgoing) {
             let event_type = edge.weight().get_event_type();
+            let event_type_set = indexed.event_set([&event_type]);
 
             // Causal consistency 1: roles subscribe to the event types they emit
-            is_stable = add_to_sub(
-                edge.weight().role.clone(),
-                BTreeSet::from([event_type.clone()]),
-                subscriptions,
-            ) && is_stable;
+            is_stable =
+                subscriptions.add(indexed.role(&edge.weight().role), &event_type_set) && is_stable;
 
             // Causal consistency 2: roles subscribe to the event types that immediately precede their own commands
             for active in proto_graph::active_transitions_not_conc(
                 edge.target(),
-                &graph,
+                graph,
                 &event_type,
                 &proto_info.concurrent_events,
             ) {
-                is_stable = add_to_sub(
-                    active.role,
-                    BTreeSet::from([event_type.clone()]),
-                    subscriptions,
-                ) && is_stable;
+                is_stable =
+                    subscriptions.add(indexed.role(&active.role), &event_type_set) && is_stable;
             }
 
             // Find all, if any, roles that subscribe to event types emitted later in the protocol.
-            let involved_roles =
-                proto_info::roles_on_path(event_type.clone(), &proto_info, &subscriptions);
+            let involved_roles = indexed.roles_on_path(indexed.event(&event_type), subscriptions);
 
             // Determinacy 1: roles subscribe to branching events.
             // Events that are branching with event_type.
@@ -144,9 +195,9 @@ fn exact_wf_sub_step(
             // If only one event labeled as branching at this node, do not add it to subscriptions.
             // This could happen due to concurrency and loss of behavior on composition.
             if branching_this_node.len() > 1 {
+                let branching_this_node = indexed.event_set(&branching_this_node);
                 for r in involved_roles.iter() {
-                    is_stable = add_to_sub(r.clone(), branching_this_node.clone(), subscriptions)
-                        && is_stable;
+                    is_stable = subscriptions.add(*r, &branching_this_node) && is_stable;
                 }
             }
 
@@ -155,14 +206,14 @@ fn exact_wf_sub_step(
             // So check if there are two or more incoming concurrent not concurrent with event type
             if proto_info.interfacing_events.contains(&event_type) {
                 let incoming_pairs_concurrent: Vec<UnordEventPair> =
-                    proto_graph::event_pairs_from_node(node, &graph, Incoming)
+                    proto_graph::event_pairs_from_node(node, graph, Incoming)
                         .into_iter()
                         .filter(|pair| proto_info.concurrent_events.contains(pair))
                         .filter(|pair| {
                             pair.iter().all(|e| {
-                                !proto_info.concurrent_events.contains(
-                                    &UnordEventPair::new(e.clone(), event_type.clone()),
-                                )
+                                !proto_info
+                                    .concurrent_events
+                                    .contains(&UnordEventPair::new(e.clone(), event_type.clone()))
                             })
                         })
                         .collect();
@@ -170,9 +221,9 @@ fn exact_wf_sub_step(
                     .into_iter()
                     .flat_map(|pair| pair.into_iter().chain([event_type.clone()]))
                     .collect();
+                let events_to_add = indexed.event_set(&events_to_add);
                 for r in involved_roles.iter() {
-                    is_stable =
-                        add_to_sub(r.clone(), events_to_add.clone(), subscriptions) && is_stable;
+                    is_stable = subscriptions.add(*r, &events_to_add) && is_stable;
                 }
             }
         }
@@ -184,15 +235,20 @@ fn exact_wf_sub_step(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::subscription::overapproximation;
     use crate::test_utils;
+    use crate::types::typescript_types::{Granularity, Role};
 
     #[test]
     fn test_well_formed_sub() {
         test_utils::setup_logger();
 
         // Test interfacing_swarms_1
-        let result_1 =
-            exact_well_formed_sub(test_utils::get_interfacing_swarms_1(), &BTreeMap::new());
+        let result_1 = exact_well_formed_sub(
+            test_utils::get_interfacing_swarms_1(),
+            &BTreeMap::new(),
+            usize::MAX,
+        );
         assert!(result_1.is_ok());
         let subs_1 = result_1.unwrap();
         let expected_subs_1: Subscriptions = BTreeMap::from([
@@ -234,8 +290,11 @@ mod tests {
         assert_eq!(subs_1, expected_subs_1);
 
         // Test interfacing_swarms_2
-        let result_2 =
-            exact_well_formed_sub(test_utils::get_interfacing_swarms_2(), &BTreeMap::new());
+        let result_2 = exact_well_formed_sub(
+            test_utils::get_interfacing_swarms_2(),
+            &BTreeMap::new(),
+            usize::MAX,
+        );
         assert!(result_2.is_ok());
         let subs_2 = result_2.unwrap();
         let expected_subs_2: Subscriptions = BTreeMap::from([
@@ -302,8 +361,11 @@ mod tests {
         assert_eq!(subs_2, expected_subs_2);
 
         // Test interfacing_swarms_3
-        let result_3 =
-            exact_well_formed_sub(test_utils::get_interfacing_swarms_3(), &BTreeMap::new());
+        let result_3 = exact_well_formed_sub(
+            test_utils::get_interfacing_swarms_3(),
+            &BTreeMap::new(),
+            usize::MAX,
+        );
         assert!(result_3.is_ok());
         let subs_3 = result_3.unwrap();
         let expected_subs_3: Subscriptions = BTreeMap::from([
@@ -363,13 +425,17 @@ mod tests {
         test_utils::setup_logger();
 
         // Test empty set of input protocols
-        let empty = exact_well_formed_sub(InterfacingProtocols(vec![]), &BTreeMap::new());
+        let empty =
+            exact_well_formed_sub(InterfacingProtocols(vec![]), &BTreeMap::new(), usize::MAX);
         assert!(empty.is_ok());
         assert_eq!(empty.unwrap(), BTreeMap::new());
 
         // Test interfacing_swarms_4
-        let result_4 =
-            exact_well_formed_sub(test_utils::get_interfacing_swarms_4(), &BTreeMap::new());
+        let result_4 = exact_well_formed_sub(
+            test_utils::get_interfacing_swarms_4(),
+            &BTreeMap::new(),
+            usize::MAX,
+        );
         assert!(result_4.is_ok());
         let subs_4 = result_4.unwrap();
         let expected_subs_4: Subscriptions = BTreeMap::from([
@@ -423,8 +489,11 @@ mod tests {
         assert_eq!(subs_4, expected_subs_4);
 
         // Test interfacing_swarms_5
-        let result_5 =
-            exact_well_formed_sub(test_utils::get_interfacing_swarms_5(), &BTreeMap::new());
+        let result_5 = exact_well_formed_sub(
+            test_utils::get_interfacing_swarms_5(),
+            &BTreeMap::new(),
+            usize::MAX,
+        );
         assert!(result_5.is_ok());
         let subs_5 = result_5.unwrap();
         let expected_subs_5: Subscriptions = BTreeMap::from([
@@ -457,7 +526,11 @@ mod tests {
     #[test]
     fn test_refinement_pattern() {
         test_utils::setup_logger();
-        let result = exact_well_formed_sub(test_utils::get_ref_pat_protos(), &BTreeMap::new());
+        let result = exact_well_formed_sub(
+            test_utils::get_ref_pat_protos(),
+            &BTreeMap::new(),
+            usize::MAX,
+        );
         assert!(result.is_ok());
         let subs = result.unwrap();
         let expected_subs: Subscriptions = BTreeMap::from([
@@ -510,6 +583,19 @@ mod tests {
         assert_eq!(subs, expected_subs);
     }
 
+    #[test]
+    fn test_well_formed_sub_state_space_limit() {
+        test_utils::setup_logger();
+        let protos = test_utils::get_interfacing_swarms_1();
+        assert!(exact_well_formed_sub(protos.clone(), &BTreeMap::new(), 16).is_ok());
+        assert_eq!(
+            exact_well_formed_sub(protos, &BTreeMap::new(), 4)
+                .unwrap_err()
+                .to_strings(),
+            vec!["composition exceeds the limit of 4 states".to_string()]
+        );
+    }
+
     #[test]
     fn test_extend_subs() {
         test_utils::setup_logger();
@@ -517,10 +603,67 @@ mod tests {
             (Role::new("D"), BTreeSet::from([EventType::new("pos")])),
             (Role::new("TR"), BTreeSet::from([EventType::new("ok")])),
         ]);
-        let result = exact_well_formed_sub(test_utils::get_interfacing_swarms_2(), &sub_to_extend);
+        let result = exact_well_formed_sub(
+            test_utils::get_interfacing_swarms_2(),
+            &sub_to_extend,
+            usize::MAX,
+        );
         assert!(result.is_ok());
         let subs = result.unwrap();
         assert!(subs[&Role::new("D")].contains(&EventType::new("pos")));
         assert!(subs[&Role::new("TR")].contains(&EventType::new("ok")));
     }
+
+    // Check that each role subscribes to the given event type in the exact subscription
+    // and in the overapproximations of every granularity.
+    fn assert_subscribes(protos: InterfacingProtocols, expected: &[(&str, &str)]) {
+        let exact = exact_well_formed_sub(protos.clone(), &BTreeMap::new(), usize::MAX).unwrap();
+        let overapproximations = [Granularity::Fine, Granularity::Coarse, Granularity::TwoStep]
+            .map(|granularity| {
+                overapproximation::overapprox_well_formed_sub(
+                    protos.clone(),
+                    &BTreeMap::new(),
+                    granularity,
+                )
+                .unwrap()
+            });
+        for sub in [exact].iter().chain(overapproximations.iter()) {
+            for (role, event_type) in expected {
+                assert!(
+                    sub[&Role::new(role)].contains(&EventType::new(event_type)),
+                    "{role} does not subscribe to {event_type}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_data_guard_sub() {
+        test_utils::setup_logger();
+        let guarded = test_utils::get_guarded_proto();
+        let mut unguarded = guarded.clone();
+        unguarded.transitions[2].label.guard = None;
+
+        // S reads the limit in the guard of select, but is not required to subscribe to it otherwise.
+        assert_subscribes(InterfacingProtocols(vec![guarded]), &[("S", "limit")]);
+        let sub = exact_well_formed_sub(
+            InterfacingProtocols(vec![unguarded]),
+            &BTreeMap::new(),
+            usize::MAX,
+        )
+        .unwrap();
+        assert!(!sub[&Role::new("S")].contains(&EventType::new("limit")));
+    }
+
+    #[test]
+    fn test_timeout_sub() {
+        test_utils::setup_logger();
+
+        // W only takes cancel if part was not delivered before, so it subscribes to part.
+        // T is involved after the branch and learns about a cancellation.
+        assert_subscribes(
+            InterfacingProtocols(vec![test_utils::get_timed_proto()]),
+            &[("W", "part"), ("T", "cancelled")],
+        );
+    }
 }
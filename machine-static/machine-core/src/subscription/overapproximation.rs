@@ -1,15 +1,14 @@
 use std::collections::BTreeSet;
 
+use crate::types::indexed::{EventSet, Indexed, IndexedSubscriptions};
+use crate::types::proto_info;
 use crate::{
     errors::ErrorReport,
     types::{
         proto_info::ProtoInfo,
-        typescript_types::{
-            EventLabel, EventType, Granularity, InterfacingProtocols, Role, Subscriptions,
-        },
+        typescript_types::{EventLabel, Granularity, InterfacingProtocols, Subscriptions},
     },
 };
-use crate::types::proto_info;
 
 // Construct wf-subscription compositionally.
 // Overapproximates the subscription one would obtain from exact_well_formed_sub().
@@ -31,16 +30,20 @@ pub fn overapprox_well_formed_sub(
     Ok(sub)
 }
 
-fn overapprox_wf_sub(
+// Like overapprox_well_formed_sub, for a combined proto_info without errors.
+pub(crate) fn overapprox_wf_sub(
     proto_info: &mut ProtoInfo,
     subscription: &Subscriptions,
     granularity: Granularity,
 ) -> Subscriptions {
     let _span = tracing::info_span!("overapprox_wf_sub").entered();
+    let mut subscription = subscription.clone();
+    super::add_guard_event_types(proto_info, &mut subscription);
+    super::add_timeout_event_types(proto_info, &mut subscription);
     match granularity {
-        Granularity::Fine => finer_overapprox_wf_sub(proto_info, subscription, false),
-        Granularity::Coarse => finer_overapprox_wf_sub(proto_info, subscription, true),
-        Granularity::TwoStep => two_step_overapprox_wf_sub(proto_info, &mut subscription.clone()),
+        Granularity::Fine => finer_overapprox_wf_sub(proto_info, &subscription, false),
+        Granularity::Coarse => finer_overapprox_wf_sub(proto_info, &subscription, true),
+        Granularity::TwoStep => two_step_overapprox_wf_sub(proto_info, &mut subscription),
     }
 }
 
@@ -80,45 +83,36 @@ fn finer_overapprox_wf_sub(
         }
     }
 
+    let indexed = Indexed::new(proto_info, &subscription);
+    let mut subs = indexed.subscriptions(&subscription);
+
     // Determinacy
-    finer_approx_add_branches_and_joins(proto_info, &mut subscription);
+    finer_approx_add_branches_and_joins(proto_info, &indexed, &mut subs);
 
     // Add looping event types to the subscription.
-    super::add_looping_event_types(proto_info, &mut subscription);
+    super::add_looping_event_types(proto_info, &indexed, &mut subs);
 
-    subscription
+    indexed.to_subscriptions(&subs)
 }
 
-fn finer_approx_add_branches_and_joins(proto_info: &ProtoInfo, subscription: &mut Subscriptions) {
+fn finer_approx_add_branches_and_joins(
+    proto_info: &ProtoInfo,
+    indexed: &Indexed,
+    subs: &mut IndexedSubscriptions,
+) {
     let _span = tracing::info_span!("finer_approx_add_branches_and_joins").entered();
+    let joins = indexed_joins(proto_info, indexed);
+    let branches = indexed_branches(proto_info, indexed);
     let mut is_stable = false;
 
     while !is_stable {
         is_stable = true;
 
         // Determinacy: joins
-        for (joining_event, pre_joining_event) in &proto_info.joining_events {
-            let interested_roles =
-                proto_info::roles_on_path(joining_event.clone(), proto_info, &subscription);
-            let join_and_prejoin: BTreeSet<EventType> = [joining_event.clone()]
-                .into_iter()
-                .chain(pre_joining_event.clone().into_iter())
-                .collect();
-            for role in interested_roles {
-                is_stable = add_to_sub(role, join_and_prejoin.clone(), subscription) && is_stable;
-            }
-        }
+        is_stable = add_joins(indexed, &joins, subs) && is_stable;
 
         // Determinacy: branches
-        for branching_events in &proto_info.branching_events {
-            let interested_roles = branching_events
-                .iter()
-                .flat_map(|e| proto_info::roles_on_path(e.clone(), proto_info, &subscription))
-                .collect::<BTreeSet<_>>();
-            for role in interested_roles {
-                is_stable = add_to_sub(role, branching_events.clone(), subscription) && is_stable;
-            }
-        }
+        is_stable = add_branches(indexed, &branches, subs) && is_stable;
     }
 }
 
@@ -147,65 +141,99 @@ fn two_step_overapprox_wf_sub(
             .or_insert_with(|| events_to_add);
     }
 
+    let indexed = Indexed::new(proto_info, subscription);
+    let mut subs = indexed.subscriptions(subscription);
+    let joins = indexed_joins(proto_info, &indexed);
+    let branches = indexed_branches(proto_info, &indexed);
+    let interfacing: Vec<(usize, EventSet)> = proto_info
+        .interfacing_events
+        .iter()
+        .map(|e| (indexed.event(e), indexed.event_set([e])))
+        .collect();
     let mut is_stable = false;
     while !is_stable {
         is_stable = true;
         // Determinacy: branches
-        for branching_events in &proto_info.branching_events {
-            let interested_roles = branching_events
-                .iter()
-                .flat_map(|e| proto_info::roles_on_path(e.clone(), proto_info, &subscription))
-                .collect::<BTreeSet<_>>();
-            for role in interested_roles {
-                is_stable = add_to_sub(role, branching_events.clone(), subscription) && is_stable;
-            }
-        }
+        is_stable = add_branches(&indexed, &branches, &mut subs) && is_stable;
 
         // Determinacy: joins.
-        for (joining_event, pre_joining_event) in &proto_info.joining_events {
-            let interested_roles =
-                proto_info::roles_on_path(joining_event.clone(), proto_info, &subscription);
-            let join_and_prejoin: BTreeSet<EventType> = [joining_event.clone()]
-                .into_iter()
-                .chain(pre_joining_event.clone().into_iter())
-                .collect();
-            for role in interested_roles {
-                is_stable = add_to_sub(role, join_and_prejoin.clone(), subscription) && is_stable;
-            }
-        }
+        is_stable = add_joins(&indexed, &joins, &mut subs) && is_stable;
 
         // Interfacing rule from algorithm in paper
-        for interfacing_event in &proto_info.interfacing_events {
-            let interested_roles =
-                proto_info::roles_on_path(interfacing_event.clone(), proto_info, &subscription);
-            for role in interested_roles {
-                is_stable = add_to_sub(
-                    role,
-                    BTreeSet::from([interfacing_event.clone()]),
-                    subscription,
-                ) && is_stable;
+        for (interfacing_event, event_set) in &interfacing {
+            for role in indexed.roles_on_path(*interfacing_event, &subs) {
+                is_stable = subs.add(role, event_set) && is_stable;
             }
         }
     }
 
     // Add looping event types to the subscription.
-    super::add_looping_event_types(proto_info, subscription);
+    super::add_looping_event_types(proto_info, &indexed, &mut subs);
 
-    subscription.clone()
+    indexed.to_subscriptions(&subs)
 }
 
-// Add events to a subscription, return true of they were already in the subscription and false otherwise
-// Mutates subs.
-fn add_to_sub(role: Role, mut event_types: BTreeSet<EventType>, subs: &mut Subscriptions) -> bool {
-    if subs.contains_key(&role) && event_types.iter().all(|e| subs[&role].contains(e)) {
-        return true;
-    }
-    subs.entry(role)
-        .and_modify(|curr| {
-            curr.append(&mut event_types);
+// Each joining event type with the set of it and the event types immediately preceding it.
+fn indexed_joins(proto_info: &ProtoInfo, indexed: &Indexed) -> Vec<(usize, EventSet)> {
+    proto_info
+        .joining_events
+        .iter()
+        .map(|(join, pre_join)| {
+            (
+                indexed.event(join),
+                indexed.event_set([join].into_iter().chain(pre_join)),
+            )
         })
-        .or_insert(event_types);
-    false
+        .collect()
+}
+
+fn indexed_branches(proto_info: &ProtoInfo, indexed: &Indexed) -> Vec<(Vec<usize>, EventSet)> {
+    proto_info
+        .branching_events
+        .iter()
+        .map(|branch| {
+            (
+                branch.iter().map(|e| indexed.event(e)).collect(),
+                indexed.event_set(branch),
+            )
+        })
+        .collect()
+}
+
+// Make roles subscribing to a joining event type or later subscribe to the join and prejoin.
+// True if subs did not change.
+fn add_joins(
+    indexed: &Indexed,
+    joins: &[(usize, EventSet)],
+    subs: &mut IndexedSubscriptions,
+) -> bool {
+    let mut is_stable = true;
+    for (join, join_and_prejoin) in joins {
+        for role in indexed.roles_on_path(*join, subs) {
+            is_stable = subs.add(role, join_and_prejoin) && is_stable;
+        }
+    }
+    is_stable
+}
+
+// Make roles subscribing to a branching event type or later subscribe to all the branches.
+// True if subs did not change.
+fn add_branches(
+    indexed: &Indexed,
+    branches: &[(Vec<usize>, EventSet)],
+    subs: &mut IndexedSubscriptions,
+) -> bool {
+    let mut is_stable = true;
+    for (branch, event_set) in branches {
+        let interested_roles: BTreeSet<usize> = branch
+            .iter()
+            .flat_map(|e| indexed.roles_on_path(*e, subs))
+            .collect();
+        for role in interested_roles {
+            is_stable = subs.add(role, event_set) && is_stable;
+        }
+    }
+    is_stable
 }
 
 #[cfg(test)]
@@ -214,6 +242,7 @@ mod tests {
 
     use super::*;
     use crate::test_utils;
+    use crate::types::typescript_types::{EventType, Role};
 
     #[test]
     fn test_well_formed_sub() {
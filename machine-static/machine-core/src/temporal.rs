@@ -0,0 +1,625 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use petgraph::{Direction::Outgoing, visit::EdgeRef};
+
+use crate::types::{
+    proto_graph::{EdgeId, Graph, NodeId},
+    proto_info,
+    typescript_types::{
+        Counterexample, EventLabel, Formula, InterfacingProtocols, StateName, Transition,
+    },
+};
+
+// Check a formula against the composition of protos. The formula has to hold at
+// every transition leaving the initial state. Returns a counterexample if it does not.
+// Fails if the composition has more than max_states states.
+pub fn check_property(
+    protos: InterfacingProtocols,
+    formula: &Formula,
+    max_states: usize,
+) -> Result<Option<Counterexample>, Vec<String>> {
+    let proto_info = proto_info::swarms_to_proto_info(protos);
+    if !proto_info.no_errors() {
+        return Err(proto_info.to_error_report().to_strings());
+    }
+    let explicit = proto_info::explicit_composition_proto_info_bounded(proto_info, max_states)
+        .map_err(|errors| errors.to_strings())?;
+    match explicit.protocols[0].initial {
+        Some(initial) => Ok(check_graph(&explicit.protocols[0].graph, initial, formula)),
+        None => Err(vec![
+            "initial swarm protocol state has no transitions".to_string(),
+        ]),
+    }
+}
+
+pub fn check_graph(graph: &Graph, initial: NodeId, formula: &Formula) -> Option<Counterexample> {
+    let _span = tracing::info_span!("check_graph").entered();
+    let mut checker = Checker {
+        graph,
+        sat: BTreeMap::new(),
+    };
+    let violating = graph
+        .edges_directed(initial, Outgoing)
+        .map(|edge| edge.id())
+        .find(|edge| !checker.holds(*edge, formula))?;
+    let path = checker.counterexample(violating, formula);
+    Some(to_counterexample(graph, path))
+}
+
+fn to_counterexample(graph: &Graph, path: Path) -> Counterexample {
+    Counterexample {
+        path: path
+            .edges
+            .iter()
+            .map(|edge| {
+                let (source, target) = graph.edge_endpoints(*edge).unwrap();
+                Transition {
+                    label: graph[*edge].clone(),
+                    source: graph[source].state_name().clone(),
+                    target: graph[target].state_name().clone(),
+                }
+            })
+            .collect(),
+        loop_start: path.loop_start,
+        branches: path
+            .branches
+            .into_iter()
+            .map(|branch| to_counterexample(graph, branch))
+            .collect(),
+    }
+}
+
+// A path of transitions, possibly ending in a loop back to loop_start.
+// branches are further paths with the same first edge, they have no branches themselves.
+struct Path {
+    edges: Vec<EdgeId>,
+    loop_start: Option<usize>,
+    branches: Vec<Path>,
+}
+
+impl Path {
+    fn single(edge: EdgeId) -> Self {
+        Self {
+            edges: vec![edge],
+            loop_start: None,
+            branches: vec![],
+        }
+    }
+
+    // prefix followed by rest. rest starts where prefix leaves off.
+    fn concat(prefix: Vec<EdgeId>, rest: Path) -> Self {
+        let offset = prefix.len();
+        Self {
+            edges: prefix.iter().cloned().chain(rest.edges).collect(),
+            loop_start: rest.loop_start.map(|i| i + offset),
+            branches: rest
+                .branches
+                .into_iter()
+                .map(|branch| Path::concat(prefix.clone(), branch))
+                .collect(),
+        }
+    }
+
+    // The paths of self and other, which start with the same edge. A path that the other
+    // continues is dropped, paths going separate ways are kept as branches.
+    fn merge(self, other: Path) -> Self {
+        let (mut longer, mut shorter) = if other.edges.len() > self.edges.len() {
+            (other, self)
+        } else {
+            (self, other)
+        };
+        let mut branches = std::mem::take(&mut longer.branches);
+        branches.append(&mut shorter.branches);
+        let continued = if shorter.loop_start.is_some() {
+            shorter.edges == longer.edges && shorter.loop_start == longer.loop_start
+        } else {
+            longer.edges.starts_with(&shorter.edges)
+        };
+        if !continued {
+            branches.push(shorter);
+        }
+        Self { branches, ..longer }
+    }
+}
+
+// Computes the transitions satisfying (sub)formulas by fixpoint iteration.
+struct Checker<'a> {
+    graph: &'a Graph,
+    sat: BTreeMap<Formula, Vec<bool>>,
+}
+
+impl Checker<'_> {
+    fn holds(&mut self, edge: EdgeId, formula: &Formula) -> bool {
+        self.sat(formula)[edge.index()]
+    }
+
+    fn successors(&self, edge: EdgeId) -> Vec<EdgeId> {
+        let (_, target) = self.graph.edge_endpoints(edge).unwrap();
+        self.graph
+            .edges_directed(target, Outgoing)
+            .map(|e| e.id())
+            .collect()
+    }
+
+    fn sat(&mut self, formula: &Formula) -> Vec<bool> {
+        if let Some(sat) = self.sat.get(formula) {
+            return sat.clone();
+        }
+        let edges: Vec<EdgeId> = self.graph.edge_indices().collect();
+        let map =
+            |f: &dyn Fn(EdgeId) -> bool| -> Vec<bool> { edges.iter().map(|e| f(*e)).collect() };
+        let sat = match formula {
+            Formula::True => vec![true; edges.len()],
+            Formula::EventType { event_type } => {
+                map(&|e| self.graph[e].get_event_type() == *event_type)
+            }
+            Formula::Command { cmd } => map(&|e| self.graph[e].cmd == *cmd),
+            Formula::Role { role } => map(&|e| self.graph[e].role == *role),
+            Formula::Not { arg } => self.sat(arg).into_iter().map(|b| !b).collect(),
+            Formula::And { left, right } => zip(self.sat(left), self.sat(right), |l, r| l && r),
+            Formula::Or { left, right } => zip(self.sat(left), self.sat(right), |l, r| l || r),
+            Formula::Implies { left, right } => {
+                zip(self.sat(left), self.sat(right), |l, r| !l || r)
+            }
+            Formula::Ax { arg } => {
+                let arg = self.sat(arg);
+                self.all_successors(&arg, false)
+            }
+            Formula::Ex { arg } => {
+                let arg = self.sat(arg);
+                self.some_successor(&arg, false)
+            }
+            Formula::Af { arg } => {
+                let arg = self.sat(arg);
+                self.fixpoint(vec![false; edges.len()], |checker, z| {
+                    zip(arg.clone(), checker.all_successors(z, false), |a, b| a || b)
+                })
+            }
+            Formula::Ef { arg } => {
+                let arg = self.sat(arg);
+                self.fixpoint(vec![false; edges.len()], |checker, z| {
+                    zip(arg.clone(), checker.some_successor(z, false), |a, b| a || b)
+                })
+            }
+            Formula::Ag { arg } => {
+                let arg = self.sat(arg);
+                self.fixpoint(vec![true; edges.len()], |checker, z| {
+                    zip(arg.clone(), checker.all_successors(z, true), |a, b| a && b)
+                })
+            }
+            Formula::Eg { arg } => {
+                let arg = self.sat(arg);
+                self.fixpoint(vec![true; edges.len()], |checker, z| {
+                    zip(arg.clone(), checker.some_successor(z, true), |a, b| a && b)
+                })
+            }
+            Formula::Au { left, right } => {
+                let (left, right) = (self.sat(left), self.sat(right));
+                self.fixpoint(vec![false; edges.len()], |checker, z| {
+                    let step = zip(left.clone(), checker.all_successors(z, false), |a, b| {
+                        a && b
+                    });
+                    zip(right.clone(), step, |a, b| a || b)
+                })
+            }
+            Formula::Eu { left, right } => {
+                let (left, right) = (self.sat(left), self.sat(right));
+                self.fixpoint(vec![false; edges.len()], |checker, z| {
+                    let step = zip(left.clone(), checker.some_successor(z, false), |a, b| {
+                        a && b
+                    });
+                    zip(right.clone(), step, |a, b| a || b)
+                })
+            }
+        };
+        self.sat.insert(formula.clone(), sat.clone());
+        sat
+    }
+
+    // whether all successors are in set. terminal is the result for transitions without successors.
+    fn all_successors(&self, set: &[bool], terminal: bool) -> Vec<bool> {
+        self.graph
+            .edge_indices()
+            .map(|e| {
+                let succ = self.successors(e);
+                if succ.is_empty() {
+                    terminal
+                } else {
+                    succ.iter().all(|s| set[s.index()])
+                }
+            })
+            .collect()
+    }
+
+    fn some_successor(&self, set: &[bool], terminal: bool) -> Vec<bool> {
+        self.graph
+            .edge_indices()
+            .map(|e| {
+                let succ = self.successors(e);
+                if succ.is_empty() {
+                    terminal
+                } else {
+                    succ.iter().any(|s| set[s.index()])
+                }
+            })
+            .collect()
+    }
+
+    fn fixpoint(&self, mut z: Vec<bool>, step: impl Fn(&Self, &[bool]) -> Vec<bool>) -> Vec<bool> {
+        loop {
+            let next = step(self, &z);
+            if next == z {
+                return z;
+            }
+            z = next;
+        }
+    }
+
+    // Shortest path from edge to an edge in target moving only through edges in through.
+    // The path excludes the edge found, which is returned separately.
+    fn shortest_path(
+        &self,
+        edge: EdgeId,
+        through: &[bool],
+        target: &[bool],
+    ) -> Option<(Vec<EdgeId>, EdgeId)> {
+        let mut predecessor: BTreeMap<EdgeId, EdgeId> = BTreeMap::new();
+        let mut queue = VecDeque::from([edge]);
+        let mut visited = BTreeSet::from([edge]);
+        while let Some(e) = queue.pop_front() {
+            if target[e.index()] {
+                let mut path = vec![];
+                let mut current = e;
+                while let Some(p) = predecessor.get(&current) {
+                    path.push(*p);
+                    current = *p;
+                }
+                path.reverse();
+                return Some((path, e));
+            }
+            if !through[e.index()] {
+                continue;
+            }
+            for s in self.successors(e) {
+                if visited.insert(s) {
+                    predecessor.insert(s, e);
+                    queue.push_back(s);
+                }
+            }
+        }
+        None
+    }
+
+    // Follow edges in set until reaching an edge without successors in set or closing a loop.
+    // stop ends the walk early at an edge.
+    fn walk(&self, edge: EdgeId, set: &[bool], stop: impl Fn(EdgeId) -> bool) -> Path {
+        let mut edges = vec![edge];
+        let mut current = edge;
+        loop {
+            if stop(current) {
+                return Path {
+                    edges,
+                    loop_start: None,
+                    branches: vec![],
+                };
+            }
+            let Some(next) = self
+                .successors(current)
+                .into_iter()
+                .find(|s| set[s.index()])
+            else {
+                return Path {
+                    edges,
+                    loop_start: None,
+                    branches: vec![],
+                };
+            };
+            if let Some(i) = edges.iter().position(|e| *e == next) {
+                return Path {
+                    edges,
+                    loop_start: Some(i),
+                    branches: vec![],
+                };
+            }
+            edges.push(next);
+            current = next;
+        }
+    }
+
+    // A path starting with edge showing that formula does not hold at edge.
+    fn counterexample(&mut self, edge: EdgeId, formula: &Formula) -> Path {
+        match formula {
+            Formula::Not { arg } => self.witness(edge, arg),
+            Formula::And { left, right } => {
+                if !self.holds(edge, left) {
+                    self.counterexample(edge, left)
+                } else {
+                    self.counterexample(edge, right)
+                }
+            }
+            Formula::Or { left, right } => {
+                let left = self.counterexample(edge, left);
+                left.merge(self.counterexample(edge, right))
+            }
+            Formula::Implies { left, right } => {
+                let left = self.witness(edge, left);
+                left.merge(self.counterexample(edge, right))
+            }
+            Formula::Ax { arg } => {
+                let arg_sat = self.sat(arg);
+                match self
+                    .successors(edge)
+                    .into_iter()
+                    .find(|s| !arg_sat[s.index()])
+                {
+                    Some(s) => Path::concat(vec![edge], self.counterexample(s, arg)),
+                    None => Path::single(edge),
+                }
+            }
+            Formula::Ag { arg } => {
+                let violating: Vec<bool> = self.sat(arg).into_iter().map(|b| !b).collect();
+                let everywhere = vec![true; violating.len()];
+                let (prefix, e) = self.shortest_path(edge, &everywhere, &violating).unwrap();
+                Path::concat(prefix, self.counterexample(e, arg))
+            }
+            Formula::Af { .. } => {
+                let not_sat: Vec<bool> = self.sat(formula).into_iter().map(|b| !b).collect();
+                self.walk(edge, &not_sat, |_| false)
+            }
+            Formula::Au { left, .. } => {
+                let not_sat: Vec<bool> = self.sat(formula).into_iter().map(|b| !b).collect();
+                let left = self.sat(left);
+                self.walk(edge, &not_sat, |e| !left[e.index()])
+            }
+            _ => Path::single(edge),
+        }
+    }
+
+    // A path starting with edge showing that formula holds at edge.
+    fn witness(&mut self, edge: EdgeId, formula: &Formula) -> Path {
+        match formula {
+            Formula::Not { arg } => self.counterexample(edge, arg),
+            Formula::Or { left, right } => {
+                if self.holds(edge, left) {
+                    self.witness(edge, left)
+                } else {
+                    self.witness(edge, right)
+                }
+            }
+            Formula::And { left, right } => {
+                let left = self.witness(edge, left);
+                left.merge(self.witness(edge, right))
+            }
+            Formula::Implies { left, right } => {
+                if !self.holds(edge, left) {
+                    self.counterexample(edge, left)
+                } else {
+                    self.witness(edge, right)
+                }
+            }
+            Formula::Ex { arg } => {
+                let arg_sat = self.sat(arg);
+                let s = self
+                    .successors(edge)
+                    .into_iter()
+                    .find(|s| arg_sat[s.index()])
+                    .unwrap();
+                Path::concat(vec![edge], self.witness(s, arg))
+            }
+            Formula::Ef { arg } => {
+                let arg_sat = self.sat(arg);
+                let everywhere = vec![true; arg_sat.len()];
+                let (prefix, e) = self.shortest_path(edge, &everywhere, &arg_sat).unwrap();
+                Path::concat(prefix, self.witness(e, arg))
+            }
+            Formula::Eu { left, right } => {
+                let (left, right_sat) = (self.sat(left), self.sat(right));
+                let (prefix, e) = self.shortest_path(edge, &left, &right_sat).unwrap();
+                Path::concat(prefix, self.witness(e, right))
+            }
+            Formula::Eg { .. } => {
+                let sat = self.sat(formula);
+                self.walk(edge, &sat, |_| false)
+            }
+            _ => Path::single(edge),
+        }
+    }
+}
+
+fn zip(left: Vec<bool>, right: Vec<bool>, f: impl Fn(bool, bool) -> bool) -> Vec<bool> {
+    left.into_iter().zip(right).map(|(l, r)| f(l, r)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::types::proto_info::DEFAULT_MAX_STATES;
+    use crate::types::typescript_types::{EventType, State};
+
+    fn formula(json: &str) -> Formula {
+        serde_json::from_str::<Formula>(json).unwrap()
+    }
+
+    fn event_types(counterexample: &Counterexample) -> Vec<EventType> {
+        counterexample
+            .path
+            .iter()
+            .map(|t| t.label.get_event_type())
+            .collect()
+    }
+
+    #[test]
+    fn test_eventually_followed_by() {
+        test_utils::setup_logger();
+        let protos = InterfacingProtocols(vec![test_utils::get_proto1()]);
+        // every request is eventually followed by part
+        let property = formula(
+            r#"{ "op": "ag", "arg": { "op": "implies",
+                "left": { "op": "command", "cmd": "request" },
+                "right": { "op": "ax", "arg": { "op": "af", "arg": { "op": "eventType", "eventType": "part" } } } } }"#,
+        );
+        assert_eq!(
+            check_property(protos.clone(), &property, DEFAULT_MAX_STATES).unwrap(),
+            None
+        );
+
+        // every request is eventually followed by time
+        let property = formula(
+            r#"{ "op": "ag", "arg": { "op": "implies",
+                "left": { "op": "command", "cmd": "request" },
+                "right": { "op": "ax", "arg": { "op": "af", "arg": { "op": "eventType", "eventType": "time" } } } } }"#,
+        );
+        let counterexample = check_property(protos, &property, DEFAULT_MAX_STATES)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            event_types(&counterexample),
+            vec![
+                EventType::new("partID"),
+                EventType::new("pos"),
+                EventType::new("part"),
+                EventType::new("partID"),
+            ]
+        );
+        assert_eq!(counterexample.loop_start, Some(1));
+        assert_eq!(counterexample.path[0].source, State::new("0"));
+    }
+
+    #[test]
+    fn test_never_acts_until() {
+        test_utils::setup_logger();
+        let protos = InterfacingProtocols(vec![test_utils::get_proto1()]);
+        // FL never acts after partID until part
+        let property = formula(
+            r#"{ "op": "ag", "arg": { "op": "implies",
+                "left": { "op": "eventType", "eventType": "partID" },
+                "right": { "op": "not", "arg": { "op": "ex", "arg": { "op": "eu",
+                    "left": { "op": "not", "arg": { "op": "eventType", "eventType": "part" } },
+                    "right": { "op": "role", "role": "FL" } } } } } }"#,
+        );
+        let counterexample = check_property(protos.clone(), &property, DEFAULT_MAX_STATES)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            event_types(&counterexample),
+            vec![EventType::new("partID"), EventType::new("pos")]
+        );
+        assert_eq!(counterexample.loop_start, None);
+
+        // nobody acts after time
+        let property = formula(
+            r#"{ "op": "ag", "arg": { "op": "implies",
+                "left": { "op": "eventType", "eventType": "time" },
+                "right": { "op": "not", "arg": { "op": "ex", "arg": { "op": "true" } } } } }"#,
+        );
+        assert_eq!(
+            check_property(protos, &property, DEFAULT_MAX_STATES).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_composition() {
+        test_utils::setup_logger();
+        let protos = test_utils::get_interfacing_swarms_1();
+        // every part is eventually followed by car even though time is concurrent
+        let property = formula(
+            r#"{ "op": "ag", "arg": { "op": "implies",
+                "left": { "op": "eventType", "eventType": "part" },
+                "right": { "op": "ax", "arg": { "op": "af", "arg": { "op": "eventType", "eventType": "car" } } } } }"#,
+        );
+        assert_eq!(
+            check_property(protos.clone(), &property, DEFAULT_MAX_STATES).unwrap(),
+            None
+        );
+
+        // closing the warehouse first blocks the factory
+        let property =
+            formula(r#"{ "op": "af", "arg": { "op": "eventType", "eventType": "car" } }"#);
+        let counterexample = check_property(protos, &property, DEFAULT_MAX_STATES)
+            .unwrap()
+            .unwrap();
+        assert_eq!(event_types(&counterexample), vec![EventType::new("time")]);
+        assert_eq!(counterexample.loop_start, None);
+    }
+
+    // the event types of the path and of its branches.
+    fn all_event_types(counterexample: &Counterexample) -> BTreeSet<Vec<EventType>> {
+        counterexample
+            .branches
+            .iter()
+            .map(event_types)
+            .chain([event_types(counterexample)])
+            .collect()
+    }
+
+    #[test]
+    fn test_both_sides() {
+        test_utils::setup_logger();
+        let protos = InterfacingProtocols(vec![test_utils::get_proto1()]);
+        let paths = |after_part: Vec<EventType>| {
+            let prefix = vec![
+                EventType::new("partID"),
+                EventType::new("pos"),
+                EventType::new("part"),
+            ];
+            after_part
+                .into_iter()
+                .map(|e| prefix.iter().cloned().chain([e]).collect())
+                .collect::<BTreeSet<Vec<EventType>>>()
+        };
+
+        // part is always followed by partID or always followed by time. Each side is violated
+        // by the other continuation, so the counterexample needs both.
+        let property = formula(
+            r#"{ "op": "ag", "arg": { "op": "implies",
+                "left": { "op": "eventType", "eventType": "part" },
+                "right": { "op": "or",
+                    "left": { "op": "ax", "arg": { "op": "eventType", "eventType": "partID" } },
+                    "right": { "op": "ax", "arg": { "op": "eventType", "eventType": "time" } } } } }"#,
+        );
+        let counterexample = check_property(protos.clone(), &property, DEFAULT_MAX_STATES)
+            .unwrap()
+            .unwrap();
+        assert_eq!(counterexample.branches.len(), 1);
+        assert_eq!(
+            all_event_types(&counterexample),
+            paths(vec![EventType::new("partID"), EventType::new("time")])
+        );
+
+        // part is not followed by a choice between partID and time. Witnessing the
+        // conjunction needs both continuations.
+        let property = formula(
+            r#"{ "op": "ag", "arg": { "op": "implies",
+                "left": { "op": "eventType", "eventType": "part" },
+                "right": { "op": "not", "arg": { "op": "and",
+                    "left": { "op": "ex", "arg": { "op": "eventType", "eventType": "partID" } },
+                    "right": { "op": "ex", "arg": { "op": "eventType", "eventType": "time" } } } } } }"#,
+        );
+        let counterexample = check_property(protos.clone(), &property, DEFAULT_MAX_STATES)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            all_event_types(&counterexample),
+            paths(vec![EventType::new("partID"), EventType::new("time")])
+        );
+
+        // part never enables time. The implication is witnessed by the time following part.
+        let property = formula(
+            r#"{ "op": "ag", "arg": { "op": "implies",
+                "left": { "op": "eventType", "eventType": "part" },
+                "right": { "op": "not", "arg": { "op": "implies",
+                    "left": { "op": "eventType", "eventType": "part" },
+                    "right": { "op": "ex", "arg": { "op": "eventType", "eventType": "time" } } } } } }"#,
+        );
+        let counterexample = check_property(protos, &property, DEFAULT_MAX_STATES)
+            .unwrap()
+            .unwrap();
+        assert!(counterexample.branches.is_empty());
+        assert_eq!(
+            all_event_types(&counterexample),
+            paths(vec![EventType::new("time")])
+        );
+    }
+}
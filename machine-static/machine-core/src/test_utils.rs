@@ -451,3 +451,72 @@ pub fn get_looping_proto_6() -> SwarmProtocolType {
     )
     .unwrap()
 }
+
+// The seller selects a bid only if its price is below the limit set by the auctioneer.
+pub fn get_guarded_proto() -> SwarmProtocolType {
+    serde_json::from_str::<SwarmProtocolType>(
+        r#"{
+            "initial": "0",
+            "transitions": [
+                { "source": "0", "target": "1", "label": { "cmd": "setLimit", "logType": ["limit"], "role": "A" } },
+                { "source": "1", "target": "2", "label": { "cmd": "bid", "logType": ["bid"], "role": "B" } },
+                { "source": "2", "target": "3", "label": { "cmd": "select", "logType": ["selected"], "role": "S",
+                    "guard": { "predicate": "bid.price < limit.value", "fields": [
+                        { "eventType": "bid", "field": "price" },
+                        { "eventType": "limit", "field": "value" }
+                    ] } } },
+                { "source": "2", "target": "3", "label": { "cmd": "pass", "logType": ["passed"], "role": "S" } }
+            ]
+        }"#,
+    )
+    .unwrap()
+}
+
+// The warehouse W may cancel if the transport T does not deliver within five minutes.
+pub fn get_timed_proto() -> SwarmProtocolType {
+    serde_json::from_str::<SwarmProtocolType>(
+        r#"{
+            "initial": "0",
+            "transitions": [
+                { "source": "0", "target": "1", "label": { "cmd": "request", "logType": ["partID"], "role": "W" } },
+                { "source": "1", "target": "2", "label": { "cmd": "deliver", "logType": ["part"], "role": "T" } },
+                { "source": "1", "target": "3", "label": { "cmd": "cancel", "logType": ["cancelled"], "role": "W", "timeout": 300000 } },
+                { "source": "2", "target": "4", "label": { "cmd": "store", "logType": ["stored"], "role": "W" } }
+            ]
+        }"#,
+    )
+    .unwrap()
+}
+
+// An auction and a shipping protocol interfacing on selected, with payload schemas.
+pub fn get_payload_protos() -> InterfacingProtocols {
+    let auction = serde_json::from_str::<SwarmProtocolType>(
+        r#"{
+            "initial": "0",
+            "transitions": [
+                { "source": "0", "target": "1", "label": { "cmd": "bid", "logType": ["bid"], "role": "B" } },
+                { "source": "1", "target": "2", "label": { "cmd": "select", "logType": ["selected"], "role": "S" } }
+            ],
+            "payloads": {
+                "bid": { "type": "object", "properties": { "price": { "type": "number" }, "bidder": { "type": "string" } }, "required": ["price"] },
+                "selected": { "type": "object", "properties": { "items": { "type": "array", "items": { "type": "integer" } } }, "required": ["items"] }
+            }
+        }"#,
+    )
+    .unwrap();
+    let shipping = serde_json::from_str::<SwarmProtocolType>(
+        r#"{
+            "initial": "0",
+            "transitions": [
+                { "source": "0", "target": "1", "label": { "cmd": "select", "logType": ["selected"], "role": "S" } },
+                { "source": "1", "target": "2", "label": { "cmd": "ship", "logType": ["shipped"], "role": "W" } }
+            ],
+            "payloads": {
+                "selected": { "type": "object", "properties": { "items": { "type": "array", "items": { "type": "integer" } } }, "required": ["items"] },
+                "shipped": { "type": "null" }
+            }
+        }"#,
+    )
+    .unwrap();
+    InterfacingProtocols(vec![auction, shipping])
+}
@@ -1,3 +1,4 @@
+pub(crate) mod indexed;
 pub mod projection;
 pub mod proto_graph;
 pub mod proto_info;
@@ -0,0 +1,255 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bitvec::vec::BitVec;
+
+use crate::types::{
+    proto_info::ProtoInfo,
+    typescript_types::{EventLabel, EventType, Role, Subscriptions},
+};
+
+// A set of event types as a bitset over the indices of an Indexed.
+pub(crate) type EventSet = BitVec;
+
+// Dense indices for the event types and roles of a ProtoInfo and a subscription.
+// Used by the subscription fixpoints instead of maps keyed by interned strings.
+// Subscriptions are converted back to the string based types at the end.
+pub(crate) struct Indexed {
+    event_types: Vec<EventType>,
+    event_index: BTreeMap<EventType, usize>,
+    roles: Vec<Role>,
+    role_index: BTreeMap<Role, usize>,
+    // for each event type, itself and the event types succeeding it.
+    on_path: Vec<EventSet>,
+}
+
+// A subscription indexed by role. Roles not in the subscription have an empty set and are not present.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct IndexedSubscriptions {
+    subs: Vec<EventSet>,
+    present: BitVec,
+}
+
+impl Indexed {
+    pub(crate) fn new(proto_info: &ProtoInfo, subs: &Subscriptions) -> Self {
+        let event_types: BTreeSet<EventType> = proto_info
+            .protocols
+            .iter()
+            .flat_map(|p| p.graph.edge_weights().map(|label| label.get_event_type()))
+            .chain(
+                proto_info
+                    .role_event_map
+                    .values()
+                    .flatten()
+                    .map(|label| label.get_event_type()),
+            )
+            .chain(subs.values().flatten().cloned())
+            .chain(
+                proto_info
+                    .succeeding_events
+                    .iter()
+                    .chain(&proto_info.immediately_pre)
+                    .chain(&proto_info.joining_events)
+                    .flat_map(|(e, es)| [e].into_iter().chain(es))
+                    .cloned(),
+            )
+            .chain(proto_info.branching_events.iter().flatten().cloned())
+            .chain(proto_info.interfacing_events.iter().cloned())
+            .chain(proto_info.infinitely_looping_events.iter().cloned())
+            .collect();
+        let roles: BTreeSet<Role> = proto_info
+            .role_event_map
+            .keys()
+            .chain(subs.keys())
+            .chain(proto_info.protocols.iter().flat_map(|p| &p.roles))
+            .cloned()
+            .collect();
+        let event_types: Vec<EventType> = event_types.into_iter().collect();
+        let roles: Vec<Role> = roles.into_iter().collect();
+        let event_index = event_types
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (e.clone(), i))
+            .collect();
+        let role_index = roles
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (r.clone(), i))
+            .collect();
+        let mut indexed = Self {
+            event_types,
+            event_index,
+            roles,
+            role_index,
+            on_path: vec![],
+        };
+        indexed.on_path = indexed
+            .event_types
+            .iter()
+            .map(|e| indexed.event_set([e].into_iter().chain(&proto_info.get_succeeding(e))))
+            .collect();
+        indexed
+    }
+
+    pub(crate) fn event(&self, event_type: &EventType) -> usize {
+        self.event_index[event_type]
+    }
+
+    pub(crate) fn role(&self, role: &Role) -> usize {
+        self.role_index[role]
+    }
+
+    pub(crate) fn event_set<'a>(
+        &self,
+        event_types: impl IntoIterator<Item = &'a EventType>,
+    ) -> EventSet {
+        let mut set = BitVec::repeat(false, self.event_types.len());
+        for e in event_types {
+            set.set(self.event(e), true);
+        }
+        set
+    }
+
+    pub(crate) fn subscriptions(&self, subs: &Subscriptions) -> IndexedSubscriptions {
+        let mut indexed = IndexedSubscriptions {
+            subs: vec![BitVec::repeat(false, self.event_types.len()); self.roles.len()],
+            present: BitVec::repeat(false, self.roles.len()),
+        };
+        for (role, event_types) in subs {
+            indexed.add(self.role(role), &self.event_set(event_types));
+        }
+        indexed
+    }
+
+    pub(crate) fn to_subscriptions(&self, subs: &IndexedSubscriptions) -> Subscriptions {
+        subs.present
+            .iter_ones()
+            .map(|r| {
+                let event_types = subs.subs[r]
+                    .iter_ones()
+                    .map(|e| self.event_types[e].clone())
+                    .collect();
+                (self.roles[r].clone(), event_types)
+            })
+            .collect()
+    }
+
+    // The roles subscribing to event_type or an event type succeeding it, see proto_info::roles_on_path.
+    pub(crate) fn roles_on_path(
+        &self,
+        event_type: usize,
+        subs: &IndexedSubscriptions,
+    ) -> Vec<usize> {
+        let on_path = &self.on_path[event_type];
+        subs.present
+            .iter_ones()
+            .filter(|r| intersects(&subs.subs[*r], on_path))
+            .collect()
+    }
+
+    // True if all roles subscribe to a common event type among event_type and the event types succeeding it.
+    pub(crate) fn all_subscribe_on_path(
+        &self,
+        event_type: usize,
+        roles: &[usize],
+        subs: &IndexedSubscriptions,
+    ) -> bool {
+        let mut common = self.on_path[event_type].clone();
+        for role in roles {
+            common &= &subs.subs[*role];
+        }
+        common.any()
+    }
+}
+
+impl IndexedSubscriptions {
+    pub(crate) fn contains(&self, role: usize, event_type: usize) -> bool {
+        self.subs[role][event_type]
+    }
+
+    // Add event_types to the subscription of role. True if they were all subscribed to already.
+    pub(crate) fn add(&mut self, role: usize, event_types: &EventSet) -> bool {
+        let known = self.present[role] && is_subset(event_types, &self.subs[role]);
+        self.present.set(role, true);
+        self.subs[role] |= event_types;
+        known
+    }
+}
+
+fn intersects(a: &EventSet, b: &EventSet) -> bool {
+    a.as_raw_slice()
+        .iter()
+        .zip(b.as_raw_slice())
+        .any(|(x, y)| x & y != 0)
+}
+
+fn is_subset(a: &EventSet, b: &EventSet) -> bool {
+    a.as_raw_slice()
+        .iter()
+        .zip(b.as_raw_slice())
+        .all(|(x, y)| x & !y == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::types::{proto_info, proto_label::ProtoLabel};
+
+    #[test]
+    fn test_indexed_roles_on_path() {
+        test_utils::setup_logger();
+        let proto_info = proto_info::swarms_to_proto_info(test_utils::get_interfacing_swarms_1());
+        let subs: Subscriptions = serde_json::from_str(
+            r#"{ "T": ["partID", "part"], "D": ["time"], "FL": ["pos"], "F": ["car"] }"#,
+        )
+        .unwrap();
+        let indexed = Indexed::new(&proto_info, &subs);
+        let indexed_subs = indexed.subscriptions(&subs);
+        assert_eq!(indexed.to_subscriptions(&indexed_subs), subs);
+        for event_type in proto_info.get_event_types() {
+            let roles: BTreeSet<Role> = indexed
+                .roles_on_path(indexed.event(&event_type), &indexed_subs)
+                .into_iter()
+                .map(|r| indexed.roles[r].clone())
+                .collect();
+            assert_eq!(
+                roles,
+                proto_info::roles_on_path(event_type.clone(), &proto_info, &subs)
+            );
+            let roles: Vec<usize> = roles.iter().map(|r| indexed.role(r)).collect();
+            let common = [event_type.clone()]
+                .into_iter()
+                .chain(proto_info.get_succeeding(&event_type))
+                .any(|e| {
+                    roles
+                        .iter()
+                        .all(|r| indexed_subs.contains(*r, indexed.event(&e)))
+                });
+            assert_eq!(
+                indexed.all_subscribe_on_path(indexed.event(&event_type), &roles, &indexed_subs),
+                common
+            );
+        }
+    }
+
+    #[test]
+    fn test_add_to_indexed_subscriptions() {
+        test_utils::setup_logger();
+        let proto_info = proto_info::swarms_to_proto_info(test_utils::get_interfacing_swarms_1());
+        let subs: Subscriptions = serde_json::from_str(r#"{ "T": [] }"#).unwrap();
+        let indexed = Indexed::new(&proto_info, &subs);
+        let mut indexed_subs = indexed.subscriptions(&subs);
+        let time = indexed.event_set([&EventType::new("time")]);
+        let empty = indexed.event_set([]);
+        assert!(indexed_subs.add(indexed.role(&Role::new("T")), &empty));
+        assert!(!indexed_subs.add(indexed.role(&Role::new("D")), &time));
+        assert!(indexed_subs.add(indexed.role(&Role::new("D")), &time));
+        assert_eq!(
+            indexed.to_subscriptions(&indexed_subs),
+            BTreeMap::from([
+                (Role::new("T"), BTreeSet::new()),
+                (Role::new("D"), BTreeSet::from([EventType::new("time")])),
+            ])
+        );
+    }
+}
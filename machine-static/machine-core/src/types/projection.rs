@@ -7,6 +7,15 @@ use std::collections::BTreeSet;
 pub type Graph = petgraph::Graph<State, MachineLabel>;
 pub type OptionGraph = petgraph::Graph<Option<State>, MachineLabel>;
 
+// A state of a projection together with the protocol states it represents.
+// One set of protocol states for each projected protocol, in the order the protocols were composed.
+#[derive(Clone, PartialEq, PartialOrd, Ord, Eq, Hash, Debug)]
+pub struct ProvenanceNode {
+    pub state: State,
+    pub proto_states: Vec<BTreeSet<State>>,
+}
+pub type ProvenanceGraph = petgraph::Graph<ProvenanceNode, MachineLabel>;
+
 // Vec of triples of the form:
 //      (protocol_graph, initial_node, interfacing event types with vec[i-1])
 // Protocols linked together in a 'chain' by interfacing event types
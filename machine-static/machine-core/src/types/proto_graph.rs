@@ -1,7 +1,10 @@
 use itertools::Itertools;
 use petgraph::{
     Direction::{self, Outgoing},
-    visit::{Dfs, EdgeRef, GraphBase, Reversed, Walker},
+    visit::{
+        Dfs, EdgeRef, GraphBase, IntoEdges, IntoEdgesDirected, IntoNeighborsDirected,
+        IntoNodeIdentifiers, Reversed, Visitable, Walker,
+    },
 };
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -40,6 +43,8 @@ pub fn swarm_to_graph(proto: &SwarmProtocolType) -> (Graph, Option<NodeId>, Vec<
         }
     }
 
+    errors.append(&mut multiple_timeouts(&graph));
+
     let initial = if let Some(idx) = nodes.get(&proto.initial) {
         errors.append(&mut all_nodes_reachable(&graph, *idx));
         Some(*idx)
@@ -84,15 +89,75 @@ fn all_nodes_reachable(graph: &Graph, initial: NodeId) -> Vec<Error> {
         .collect()
 }
 
+// A state has a single timer, so at most one timeout transition can leave it.
+pub fn multiple_timeouts<N>(graph: &petgraph::Graph<N, SwarmLabel>) -> Vec<Error> {
+    graph
+        .node_indices()
+        .filter(|node| {
+            graph
+                .edges_directed(*node, Outgoing)
+                .filter(|edge| edge.weight().timeout.is_some())
+                .count()
+                > 1
+        })
+        .map(Error::TimeoutOnMultipleTransitions)
+        .collect()
+}
+
+// The event types of the transitions competing with the timeout transition edge, i.e. the other
+// transitions leaving its source that are not concurrent with it. The role of edge takes the
+// transition only if none of these happened, so it must subscribe to them.
+// Transitions without event types are skipped.
+pub fn competing_event_types<N>(
+    graph: &petgraph::Graph<N, SwarmLabel>,
+    edge: EdgeId,
+    concurrent_events: &BTreeSet<UnordEventPair>,
+) -> BTreeSet<EventType> {
+    let Some((source, _)) = graph.edge_endpoints(edge) else {
+        return BTreeSet::new();
+    };
+    competing_event_types_at(graph, source, edge, concurrent_events)
+}
+
+// competing_event_types for the transition edge leaving source in any graph, e.g. a LazyComposition.
+pub fn competing_event_types_at<G>(
+    graph: G,
+    source: G::NodeId,
+    edge: G::EdgeId,
+    concurrent_events: &BTreeSet<UnordEventPair>,
+) -> BTreeSet<EventType>
+where
+    G: IntoEdges<EdgeWeight = SwarmLabel>,
+{
+    let edges: Vec<_> = graph.edges(source).collect();
+    let event_type = edges
+        .iter()
+        .find(|e| e.id() == edge)
+        .and_then(|e| e.weight().log_type.first());
+    edges
+        .iter()
+        .filter(|e| e.id() != edge)
+        .filter_map(|e| e.weight().log_type.first().cloned())
+        .filter(|t| {
+            !event_type.is_some_and(|event_type| {
+                concurrent_events.contains(&UnordEventPair::new(event_type.clone(), t.clone()))
+            })
+        })
+        .collect()
+}
+
 // Given some node, return the swarmlabels going out of that node that are not concurrent with 'event_type'.
-pub fn active_transitions_not_conc(
-    node: NodeId,
-    graph: &Graph,
+pub fn active_transitions_not_conc<G>(
+    node: G::NodeId,
+    graph: G,
     event_type: &EventType,
     concurrent_events: &BTreeSet<UnordEventPair>,
-) -> Vec<SwarmLabel> {
+) -> Vec<SwarmLabel>
+where
+    G: IntoEdges<EdgeWeight = SwarmLabel>,
+{
     graph
-        .edges_directed(node, Outgoing)
+        .edges(node)
         .map(|e| e.weight().clone())
         .filter(|e| {
             !concurrent_events.contains(&UnordEventPair::new(event_type.clone(), e.get_event_type()))
@@ -101,38 +166,46 @@ pub fn active_transitions_not_conc(
 }
 
 // Return all event types that are part of an infinte loop in a graph (according to succ_map).
-pub fn infinitely_looping_event_types(
-    graph: &Graph,
+pub fn infinitely_looping_event_types<G>(
+    graph: G,
     succ_map: &BTreeMap<EventType, BTreeSet<EventType>>,
-) -> BTreeSet<EventType> {
+) -> BTreeSet<EventType>
+where
+    G: IntoEdges<EdgeWeight = SwarmLabel> + IntoNeighborsDirected + IntoNodeIdentifiers + Visitable,
+    G::NodeId: Ord,
+{
     let _span = tracing::info_span!("infinitely_looping_event_types").entered();
     let nodes = nodes_not_reaching_terminal(graph);
     nodes
         .into_iter()
         .flat_map(|n| {
             graph
-                .edges_directed(n, Outgoing)
+                .edges(n)
                 .map(|e| e.weight().get_event_type())
                 .filter(|t| succ_map.contains_key(t) && succ_map[t].contains(t))
         })
         .collect()
 }
 
-fn nodes_not_reaching_terminal(graph: &Graph) -> Vec<NodeId> {
+pub(crate) fn nodes_not_reaching_terminal<G>(graph: G) -> Vec<G::NodeId>
+where
+    G: IntoNeighborsDirected + IntoNodeIdentifiers + Visitable,
+    G::NodeId: Ord,
+{
     let _span = tracing::info_span!("nodes_not_reaching_terminal").entered();
     // All terminal nodes
     let terminal_nodes: Vec<_> = graph
-        .node_indices()
-        .filter(|node| graph.edges_directed(*node, Outgoing).count() == 0)
+        .node_identifiers()
+        .filter(|node| graph.neighbors_directed(*node, Outgoing).next().is_none())
         .collect();
     // Reversed adaptor -- all edges have the opposite direction.
-    let reversed = Reversed(&graph);
+    let reversed = Reversed(graph);
 
     // Collect all predecessors of from node using reversed adaptor.
-    let get_predecessors = |node: NodeId| -> BTreeSet<NodeId> {
+    let get_predecessors = |node: G::NodeId| -> BTreeSet<G::NodeId> {
         let mut predecessors = BTreeSet::new();
-        let mut dfs = Dfs::new(&reversed, node);
-        while let Some(predecessor) = dfs.next(&reversed) {
+        let mut dfs = Dfs::new(reversed, node);
+        while let Some(predecessor) = dfs.next(reversed) {
             predecessors.insert(predecessor);
         }
         predecessors
@@ -147,28 +220,25 @@ fn nodes_not_reaching_terminal(graph: &Graph) -> Vec<NodeId> {
 
     // Collect nodes that can not reach a terminal node and transform to a vec of errors.
     graph
-        .node_indices()
-        .into_iter()
+        .node_identifiers()
         .filter(|node| !can_reach_terminal_nodes.contains(node))
         .collect()
 }
 
 // all pairs of incoming/outgoing events from a node
-pub fn event_pairs_from_node(
-    node: NodeId,
-    graph: &Graph,
+pub fn event_pairs_from_node<G>(
+    node: G::NodeId,
+    graph: G,
     direction: Direction,
-) -> Vec<UnordEventPair> {
+) -> Vec<UnordEventPair>
+where
+    G: IntoEdgesDirected<EdgeWeight = SwarmLabel>,
+{
     graph
         .edges_directed(node, direction)
-        .map(|e| e.id())
+        .map(|e| e.weight().get_event_type())
         .combinations(2)
-        .map(|pair| {
-            UnordEventPair::new(
-                graph[pair[0]].get_event_type(),
-                graph[pair[1]].get_event_type(),
-            )
-        }) //BTreeSet::from([graph[pair[0]].get_event_type(), graph[pair[1]].get_event_type()]))
+        .map(|pair| UnordEventPair::new(pair[0].clone(), pair[1].clone()))
         .collect()
 }
 
@@ -1,28 +1,36 @@
 use crate::errors::{Error, ErrorReport};
+use crate::types::indexed::Indexed;
 use crate::types::proto_graph;
 use crate::types::proto_label::ProtoLabel;
 use crate::types::typescript_types::{
-    Command, EventLabel, InterfacingProtocols, Subscriptions, SwarmProtocolType,
+    Command, CompositionEstimate, EventLabel, InterfacingProtocols, ProtocolAnalysis,
+    Subscriptions, SwarmProtocolType,
 };
 use crate::types::unordered_event_pair::UnordEventPair;
 use crate::types::{
     proto_graph::{Graph, NodeId},
-    typescript_types::{EventType, Role, SwarmLabel},
+    typescript_types::{EventType, PayloadSchema, Role, State, SwarmLabel},
 };
-use crate::{composability_check, composition};
+use crate::{composability_check, composition, parallel};
 use itertools::Itertools;
 use petgraph::Directed;
 use petgraph::algo;
+use petgraph::unionfind::UnionFind;
 use petgraph::visit::DfsPostOrder;
 use petgraph::{
     Direction::{Incoming, Outgoing},
-    visit::{Dfs, EdgeRef},
+    visit::{Dfs, EdgeRef, IntoEdges, Visitable},
 };
+use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 
+// Limit on the number of states of explicit compositions used by compose_protocols
+// and by the wasm functions that are not given a limit.
+pub const DEFAULT_MAX_STATES: usize = 1_000_000;
+
 pub type RoleEventMap = BTreeMap<Role, BTreeSet<SwarmLabel>>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProtoStruct {
     pub graph: Graph,
     pub initial: Option<NodeId>,
@@ -58,7 +66,7 @@ impl ProtoStruct {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProtoInfo {
     pub protocols: Vec<ProtoStruct>,
     pub role_event_map: RoleEventMap,
@@ -69,6 +77,7 @@ pub struct ProtoInfo {
     pub succeeding_events: BTreeMap<EventType, BTreeSet<EventType>>,
     pub interfacing_events: BTreeSet<EventType>,
     pub infinitely_looping_events: BTreeSet<EventType>, // Event types that do not lead to a terminal state.
+    pub payloads: BTreeMap<EventType, PayloadSchema>,
     pub interface_errors: Vec<Error>,
 }
 
@@ -83,6 +92,7 @@ impl ProtoInfo {
         succeeding_events: BTreeMap<EventType, BTreeSet<EventType>>,
         interfacing_events: BTreeSet<EventType>,
         infinitely_looping_events: BTreeSet<EventType>,
+        payloads: BTreeMap<EventType, PayloadSchema>,
         interface_errors: Vec<Error>,
     ) -> Self {
         Self {
@@ -95,6 +105,7 @@ impl ProtoInfo {
             succeeding_events,
             interfacing_events,
             infinitely_looping_events,
+            payloads,
             interface_errors,
         }
     }
@@ -110,6 +121,7 @@ impl ProtoInfo {
             succeeding_events: BTreeMap::new(),
             interfacing_events: BTreeSet::new(),
             infinitely_looping_events: BTreeSet::new(),
+            payloads: BTreeMap::new(),
             interface_errors: Vec::new(),
         }
     }
@@ -260,19 +272,19 @@ fn infinitely_looping_event_types_in_sub(
         .collect();
 
     // event types in those loops that all involved roles subscribe to
+    let indexed = Indexed::new(proto_info, subscriptions);
+    let subs = indexed.subscriptions(subscriptions);
     let loops: BTreeSet<BTreeSet<EventType>> = loops
         .into_iter()
         .map(|a_loop| {
             a_loop
                 .into_iter()
                 .filter(|t| {
-                    roles_on_path(t.clone(), proto_info, subscriptions)
-                        .iter()
-                        .all(|r| {
-                            subscriptions
-                                .get(r)
-                                .is_some_and(|event_types_r| event_types_r.contains(t))
-                        })
+                    let t = indexed.event(t);
+                    indexed
+                        .roles_on_path(t, &subs)
+                        .into_iter()
+                        .all(|r| subs.contains(r, t))
                 })
                 .collect()
         })
@@ -328,7 +340,9 @@ fn get_interfacing_event_types(
 
 // Construct map from joining event types to concurrent events preceding joining event types.
 #[inline]
-fn joining_event_types_map(proto_info: &ProtoInfo) -> BTreeMap<EventType, BTreeSet<EventType>> {
+pub(crate) fn joining_event_types_map(
+    proto_info: &ProtoInfo,
+) -> BTreeMap<EventType, BTreeSet<EventType>> {
     let pre_joins = |e: &EventType| -> BTreeSet<EventType> {
         let pre = proto_info.get_preceding(e);
         let product = pre.clone().into_iter().cartesian_product(&pre);
@@ -363,7 +377,7 @@ pub fn flatten_joining_map(
 
 // Combine fields of two proto infos.
 // Do not compute transitive closure of happens after and do not compute joining event types.
-fn combine_two_proto_infos(proto_info1: ProtoInfo, proto_info2: ProtoInfo) -> ProtoInfo {
+pub(crate) fn combine_two_proto_infos(proto_info1: ProtoInfo, proto_info2: ProtoInfo) -> ProtoInfo {
     let _span = tracing::info_span!("combine_proto_infos").entered();
     let interface_errors = composability_check::check_interface(&proto_info1, &proto_info2);
     let interfacing_event_types = get_interfacing_event_types(&proto_info1, &proto_info2);
@@ -407,6 +421,13 @@ fn combine_two_proto_infos(proto_info1: ProtoInfo, proto_info2: ProtoInfo) -> Pr
         .chain(proto_info2.infinitely_looping_events.into_iter())
         .collect();
 
+    // Schemas of shared event types are equal or reported in interface_errors.
+    let payloads = proto_info2
+        .payloads
+        .into_iter()
+        .chain(proto_info1.payloads)
+        .collect();
+
     ProtoInfo::new(
         protocols,
         role_event_map,
@@ -417,6 +438,7 @@ fn combine_two_proto_infos(proto_info1: ProtoInfo, proto_info2: ProtoInfo) -> Pr
         happens_after,
         interfacing_event_types,
         infinitely_looping_events,
+        payloads,
         [
             proto_info1.interface_errors,
             proto_info2.interface_errors,
@@ -452,11 +474,7 @@ pub fn swarms_to_proto_info(protos: InterfacingProtocols) -> ProtoInfo {
 
 pub fn prepare_proto_infos(protos: InterfacingProtocols) -> Vec<ProtoInfo> {
     let _span = tracing::info_span!("prepare_proto_infos").entered();
-    protos
-        .0
-        .iter()
-        .map(|p| prepare_proto_info(p.clone()))
-        .collect()
+    parallel::map(protos.0, prepare_proto_info)
 }
 
 // Precondition: proto does not contain concurrency.
@@ -479,7 +497,7 @@ pub(crate) fn prepare_proto_info(proto: SwarmProtocolType) -> ProtoInfo {
 
     // Add to set of branching and joining.
     // Graph contains no concurrency, so:
-    //      Branching event types are all outgoing event types if more than one and if more than one distinct target.
+    //      Branching event types are all outgoing event types if more than one and if more than one distinct target or a timeout.
     //      Immediately preceding to each edge are all incoming event types
     while let Some(node_id) = walk.next(&graph) {
         let outgoing_labels: Vec<_> = graph
@@ -491,7 +509,11 @@ pub(crate) fn prepare_proto_info(proto: SwarmProtocolType) -> ProtoInfo {
             .map(|edge| edge.weight().get_event_type())
             .collect();
 
-        if outgoing_labels.len() > 1 && direct_successors(&graph, node_id).len() > 1 {
+        // A timeout is decided locally by its role, so it branches even if all transitions lead to the same state.
+        let has_timeout = outgoing_labels.iter().any(|label| label.timeout.is_some());
+        if outgoing_labels.len() > 1
+            && (has_timeout || direct_successors(&graph, node_id).len() > 1)
+        {
             branching_events.push(
                 outgoing_labels
                     .iter()
@@ -539,6 +561,7 @@ pub(crate) fn prepare_proto_info(proto: SwarmProtocolType) -> ProtoInfo {
         happens_after,
         BTreeSet::new(),
         infinitely_looping_events,
+        proto.payloads,
         vec![],
     )
 }
@@ -553,11 +576,14 @@ fn direct_successors(graph: &Graph, node: NodeId) -> BTreeSet<NodeId> {
 
 // Compute a map mapping event types to the set of event types that follow it.
 // I.e. for each event type t all those event types t' that can be emitted after t.
-fn after_not_concurrent(
-    graph: &Graph,
-    initial: NodeId,
+pub(crate) fn after_not_concurrent<G>(
+    graph: G,
+    initial: G::NodeId,
     concurrent_events: &BTreeSet<UnordEventPair>,
-) -> BTreeMap<EventType, BTreeSet<EventType>> {
+) -> BTreeMap<EventType, BTreeSet<EventType>>
+where
+    G: IntoEdges<EdgeWeight = SwarmLabel> + Visitable,
+{
     let _span = tracing::info_span!("after_not_concurrent").entered();
     let mut succ_map: BTreeMap<EventType, BTreeSet<EventType>> = BTreeMap::new();
     let mut is_stable = after_not_concurrent_step(graph, initial, concurrent_events, &mut succ_map);
@@ -573,19 +599,19 @@ fn after_not_concurrent(
 // that only contains event types that are immediately after t and not concurrent with t.
 // We then add each event type t' in active_in_successor and all the event types t''
 // that we already know are after t' to the set of event types succeeding t.
-fn after_not_concurrent_step(
-    graph: &Graph,
-    initial: NodeId,
+fn after_not_concurrent_step<G>(
+    graph: G,
+    initial: G::NodeId,
     concurrent_events: &BTreeSet<UnordEventPair>,
     succ_map: &mut BTreeMap<EventType, BTreeSet<EventType>>,
-) -> bool {
-    if graph.node_count() == 0 || initial == NodeId::end() {
-        return true;
-    }
+) -> bool
+where
+    G: IntoEdges<EdgeWeight = SwarmLabel> + Visitable,
+{
     let mut is_stable = true;
-    let mut walk = DfsPostOrder::new(&graph, initial);
-    while let Some(node) = walk.next(&graph) {
-        for edge in graph.edges_directed(node, Outgoing) {
+    let mut walk = DfsPostOrder::new(graph, initial);
+    while let Some(node) = walk.next(graph) {
+        for edge in graph.edges(node) {
             let event_type = edge.weight().get_event_type();
             let active_in_successor = proto_graph::active_transitions_not_conc(
                 edge.target(),
@@ -672,25 +698,44 @@ pub fn roles_on_path(
     proto_info: &ProtoInfo,
     subs: &Subscriptions,
 ) -> BTreeSet<Role> {
-    let succeeding_events: BTreeSet<EventType> = proto_info
-        .get_succeeding(&event_type)
-        .into_iter()
-        .chain([event_type])
-        .collect();
+    let empty = BTreeSet::new();
+    let succeeding_events = proto_info
+        .succeeding_events
+        .get(&event_type)
+        .unwrap_or(&empty);
     subs.iter()
-        .filter(|(_, events)| events.intersection(&succeeding_events).count() != 0)
+        .filter(|(_, events)| {
+            events
+                .iter()
+                .any(|e| *e == event_type || succeeding_events.contains(e))
+        })
         .map(|(r, _)| r.clone())
         .collect()
 }
 
-pub fn explicit_composition_proto_info(proto_info: ProtoInfo) -> ProtoInfo {
+// The proto_info with the explicit composition of the protocols of proto_info as its only protocol.
+// Fails with Error::StateSpaceLimitExceeded instead of building a composition with more than max_states states.
+pub fn explicit_composition_proto_info_bounded(
+    proto_info: ProtoInfo,
+    max_states: usize,
+) -> Result<ProtoInfo, ErrorReport> {
     let _span = tracing::info_span!("explicit_composition_proto_info").entered();
-    let (composed, composed_initial) = explicit_composition(&proto_info);
-    let succeeding_events =
-        after_not_concurrent(&composed, composed_initial, &proto_info.concurrent_events);
+    let (composed, composed_initial) = explicit_composition_bounded(
+        &proto_info,
+        |_, _, state| state.clone(),
+        composition::gen_state_name,
+        max_states,
+    )
+    .ok_or_else(|| state_space_limit_exceeded(max_states))?;
+    // the composition of no protocols has no states.
+    let succeeding_events = if composed.node_count() == 0 {
+        BTreeMap::new()
+    } else {
+        after_not_concurrent(&composed, composed_initial, &proto_info.concurrent_events)
+    };
     let infinitely_looping_events =
         proto_graph::infinitely_looping_event_types(&composed, &succeeding_events);
-    ProtoInfo {
+    Ok(ProtoInfo {
         protocols: vec![ProtoStruct::new(
             composed,
             Some(composed_initial),
@@ -700,66 +745,276 @@ pub fn explicit_composition_proto_info(proto_info: ProtoInfo) -> ProtoInfo {
         succeeding_events,
         infinitely_looping_events,
         ..proto_info
-    }
+    })
+}
+
+pub(crate) fn state_space_limit_exceeded(max_states: usize) -> ErrorReport {
+    ErrorReport(vec![(
+        Graph::new(),
+        vec![Error::StateSpaceLimitExceeded(max_states)],
+    )])
 }
 
-// precondition: the protocols can interface on the given interfaces
-fn explicit_composition(proto_info: &ProtoInfo) -> (Graph, NodeId) {
+// The composition of the protocols of a proto_info where each node
+// holds the corresponding node of every protocol.
+pub(crate) type TrackedGraph = petgraph::Graph<Vec<NodeId>, SwarmLabel>;
+
+// Fails with Error::StateSpaceLimitExceeded if the composition has more than max_states states.
+pub(crate) fn tracked_composition(
+    proto_info: &ProtoInfo,
+    max_states: usize,
+) -> Result<(TrackedGraph, NodeId), ErrorReport> {
+    explicit_composition_bounded(
+        proto_info,
+        |_, node, _| vec![node],
+        |n1, n2| n1.iter().chain(n2).cloned().collect(),
+        max_states,
+    )
+    .ok_or_else(|| state_space_limit_exceeded(max_states))
+}
+
+// Name of a composed state given by the node of every protocol.
+pub(crate) fn composed_state_name(proto_info: &ProtoInfo, nodes: &[NodeId]) -> State {
+    let name = nodes
+        .iter()
+        .enumerate()
+        .map(|(k, node)| proto_info.protocols[k].graph[*node].to_string())
+        .collect::<Vec<_>>()
+        .join(" || ");
+    State::new(&name)
+}
+
+// The composition of the protocols of a proto_info, with the nodes of the composition built from
+// the nodes of the protocols using to_node and gen_node. to_node gets the index of the protocol and the node.
+// Returns None as soon as some intermediate composition has more than max_states states.
+pub(crate) fn explicit_composition_bounded<N: Clone>(
+    proto_info: &ProtoInfo,
+    to_node: impl Fn(usize, NodeId, &State) -> N,
+    gen_node: fn(&N, &N) -> N,
+    max_states: usize,
+) -> Option<(petgraph::Graph<N, SwarmLabel>, NodeId)> {
     let _span = tracing::info_span!("explicit_composition").entered();
     if proto_info.protocols.is_empty() {
-        return (Graph::new(), NodeId::end());
+        return Some((petgraph::Graph::new(), NodeId::end()));
     }
 
+    let map_nodes = |k: usize, g: &Graph| -> petgraph::Graph<N, SwarmLabel> {
+        g.map(
+            |node, state| to_node(k, node, state),
+            |_, label| label.clone(),
+        )
+    };
     let (g, i, _) = proto_info.protocols[0].get_triple();
     let g_roles = g.get_roles();
-    let folder = |(acc_g, acc_i, acc_roles): (Graph, NodeId, BTreeSet<Role>),
-                  p: ProtoStruct|
-     -> (Graph, NodeId, BTreeSet<Role>) {
-        let empty = BTreeSet::new();
-        let interface = acc_roles
-            .intersection(&p.graph.get_roles())
-            .cloned()
-            .flat_map(|role| {
-                proto_info
-                    .role_event_map
-                    .get(&role)
-                    .unwrap_or(&empty)
-                    .iter()
-                    .map(|label| label.get_event_type())
-            })
-            .collect();
-        let acc_roles = acc_roles.into_iter().chain(p.graph.get_roles()).collect();
-        let (graph, initial) = composition::compose(
-            acc_g,
-            acc_i,
-            p.graph,
-            p.initial.unwrap(),
-            interface,
-            composition::gen_state_name,
-        );
-        (graph, initial, acc_roles)
-    };
+    let folder =
+        |(acc_g, acc_i, acc_roles): (petgraph::Graph<N, SwarmLabel>, NodeId, BTreeSet<Role>),
+         (k, p): (usize, ProtoStruct)|
+         -> Option<(petgraph::Graph<N, SwarmLabel>, NodeId, BTreeSet<Role>)> {
+            let interface = interface(proto_info, &acc_roles, &p.graph.get_roles());
+            let acc_roles = acc_roles.into_iter().chain(p.graph.get_roles()).collect();
+            let (graph, initial) = composition::compose_bounded(
+                acc_g,
+                acc_i,
+                map_nodes(k, &p.graph),
+                p.initial.unwrap(),
+                interface,
+                gen_node,
+                max_states,
+            )?;
+            Some((graph, initial, acc_roles))
+        };
     let (graph, initial, _) = proto_info.protocols[1..]
         .to_vec()
         .into_iter()
-        .fold((g, i.unwrap(), g_roles), folder);
-    (graph, initial)
+        .enumerate()
+        .map(|(k, p)| (k + 1, p))
+        .try_fold((map_nodes(0, &g), i.unwrap(), g_roles), folder)?;
+    Some((graph, initial))
+}
+
+// The event types two protocols with the given roles synchronize on when composed.
+pub(crate) fn interface(
+    proto_info: &ProtoInfo,
+    roles1: &BTreeSet<Role>,
+    roles2: &BTreeSet<Role>,
+) -> BTreeSet<EventType> {
+    let empty = BTreeSet::new();
+    roles1
+        .intersection(roles2)
+        .flat_map(|role| {
+            proto_info
+                .role_event_map
+                .get(role)
+                .unwrap_or(&empty)
+                .iter()
+                .map(|label| label.get_event_type())
+        })
+        .collect()
 }
 
 // Construct a graph that is the 'expanded' composition of protos.
+// Fails if the composition has more than DEFAULT_MAX_STATES states.
 pub fn compose_protocols(protos: InterfacingProtocols) -> Result<(Graph, NodeId), ErrorReport> {
-    let _span = tracing::info_span!("compose_protocols").entered();
+    compose_protocols_bounded(protos, DEFAULT_MAX_STATES)
+}
+
+// The payload schemas declared by protos. Assumes that the protocols interface correctly,
+// i.e. that they agree on the schemas of shared event types.
+pub fn payloads(protos: &InterfacingProtocols) -> BTreeMap<EventType, PayloadSchema> {
+    protos
+        .0
+        .iter()
+        .flat_map(|proto| proto.payloads.clone())
+        .collect()
+}
+
+// Like compose_protocols, but fails with Error::StateSpaceLimitExceeded
+// instead of building a composition with more than max_states states.
+pub fn compose_protocols_bounded(
+    protos: InterfacingProtocols,
+    max_states: usize,
+) -> Result<(Graph, NodeId), ErrorReport> {
+    let _span = tracing::info_span!("compose_protocols_bounded").entered();
     let combined_proto_info = swarms_to_proto_info(protos);
     if !combined_proto_info.no_errors() {
         return Err(combined_proto_info.to_error_report());
     }
 
-    let p = explicit_composition_proto_info(combined_proto_info)
+    let p = explicit_composition_proto_info_bounded(combined_proto_info, max_states)?
         .get_ith_proto(0)
         .unwrap();
     Ok((p.graph, p.initial.unwrap()))
 }
 
+// The analysis results of proto_info in a form that can be passed to TypeScript.
+pub fn analyze_protocols(proto_info: &ProtoInfo) -> ProtocolAnalysis {
+    ProtocolAnalysis {
+        concurrent_events: proto_info
+            .concurrent_events
+            .iter()
+            .map(|pair| pair.clone().into())
+            .collect(),
+        branching_events: proto_info.branching_events.clone(),
+        joining_events: proto_info.joining_events.clone(),
+        succeeding_events: proto_info.succeeding_events.clone(),
+        interfacing_events: proto_info.interfacing_events.clone(),
+        infinitely_looping_events: proto_info.infinitely_looping_events.clone(),
+    }
+}
+
+// Estimate the size of the explicit composition of the protocols of proto_info without building it.
+// The upper bounds are the sizes of the product of the protocols. The estimate assumes that the
+// interfacing event types cut each protocol into phases, the parts of it connected by other
+// transitions, and that two protocols only move independently within phases aligned by the interface.
+// Composing protocols with n1 and n2 states and r1 and r2 phases gives at most n1 * n2 / min(r1, r2)
+// states if all their other event types are concurrent and max(n1, n2) if none are. The estimate
+// lies between the two in proportion to the share of concurrent pairs of these event types.
+pub fn estimate_composition(proto_info: &ProtoInfo) -> CompositionEstimate {
+    let _span = tracing::info_span!("estimate_composition").entered();
+    let states: Vec<u64> = proto_info
+        .protocols
+        .iter()
+        .map(|p| p.graph.node_count() as u64)
+        .collect();
+    let transitions: Vec<u64> = proto_info
+        .protocols
+        .iter()
+        .map(|p| p.graph.edge_count() as u64)
+        .collect();
+    let upper_bound_states = states.iter().fold(1u64, |acc, n| acc.saturating_mul(*n));
+    let upper_bound_transitions = (0..states.len())
+        .map(|k| {
+            states
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != k)
+                .fold(transitions[k], |acc, (_, n)| acc.saturating_mul(*n))
+        })
+        .fold(0u64, |acc, n| acc.saturating_add(n));
+
+    let mut estimated_states = states.first().cloned().unwrap_or(0);
+    let mut acc_roles = proto_info
+        .protocols
+        .first()
+        .map(|p| p.graph.get_roles())
+        .unwrap_or_default();
+    for (k, p) in proto_info.protocols.iter().enumerate().skip(1) {
+        let roles = p.graph.get_roles();
+        let interface = interface(proto_info, &acc_roles, &roles);
+        let acc_phases = proto_info.protocols[..k]
+            .iter()
+            .map(|q| phases(&q.graph, &interface))
+            .max()
+            .unwrap_or(1);
+        let divisor = acc_phases.min(phases(&p.graph, &interface)).max(1);
+        let independent = estimated_states.saturating_mul(states[k]) / divisor;
+        let sequential = estimated_states.max(states[k]);
+        let acc_event_types: BTreeSet<EventType> = proto_info.protocols[..k]
+            .iter()
+            .flat_map(|q| q.graph.get_event_types())
+            .filter(|t| !interface.contains(t))
+            .collect();
+        let event_types: BTreeSet<EventType> = p
+            .graph
+            .get_event_types()
+            .into_iter()
+            .filter(|t| !interface.contains(t))
+            .collect();
+        let concurrent = acc_event_types
+            .iter()
+            .cartesian_product(&event_types)
+            .filter(|(t1, t2)| {
+                proto_info
+                    .concurrent_events
+                    .contains(&UnordEventPair::new((*t1).clone(), (*t2).clone()))
+            })
+            .count();
+        let pairs = acc_event_types.len() * event_types.len();
+        estimated_states = match pairs {
+            0 => sequential,
+            _ => {
+                sequential
+                    + (independent.saturating_sub(sequential) as f64 * concurrent as f64
+                        / pairs as f64) as u64
+            }
+        };
+        acc_roles.extend(roles);
+    }
+    // transitions per state add up over the protocols
+    let out_degree: f64 = states
+        .iter()
+        .zip(transitions.iter())
+        .filter(|(n, _)| **n > 0)
+        .map(|(n, t)| *t as f64 / *n as f64)
+        .sum();
+
+    CompositionEstimate {
+        states: states.iter().map(|n| *n as usize).collect(),
+        upper_bound_states,
+        upper_bound_transitions,
+        estimated_states: estimated_states.min(upper_bound_states),
+        estimated_transitions: ((estimated_states as f64 * out_degree) as u64)
+            .min(upper_bound_transitions),
+        interfacing_event_types: proto_info.interfacing_events.len(),
+        concurrent_event_pairs: proto_info.concurrent_events.len(),
+    }
+}
+
+// Number of parts of graph connected by transitions not in interface.
+fn phases(graph: &Graph, interface: &BTreeSet<EventType>) -> u64 {
+    let mut union_find = UnionFind::new(graph.node_count());
+    for edge in graph.edge_references() {
+        if !interface.contains(&edge.weight().get_event_type()) {
+            union_find.union(edge.source().index(), edge.target().index());
+        }
+    }
+    union_find
+        .into_labeling()
+        .into_iter()
+        .collect::<BTreeSet<_>>()
+        .len() as u64
+}
+
 // combine maps with sets as values
 fn combine_maps<K: Ord + Clone, V: Ord + Clone>(
     map1: BTreeMap<K, BTreeSet<V>>,
@@ -868,6 +1123,83 @@ mod tests {
         assert_eq!(expected_after, after_map);
     }
 
+    #[test]
+    fn test_estimate_composition() {
+        test_utils::setup_logger();
+        let proto_info = swarms_to_proto_info(test_utils::get_interfacing_swarms_1());
+        let estimate = estimate_composition(&proto_info);
+        let (composition, _) = compose_protocols(test_utils::get_interfacing_swarms_1()).unwrap();
+        assert_eq!(estimate.states, vec![4, 4]);
+        assert_eq!(estimate.upper_bound_states, 16);
+        assert_eq!(estimate.upper_bound_transitions, 28);
+        assert_eq!(estimate.estimated_states, composition.node_count() as u64);
+        assert!(estimate.estimated_transitions <= estimate.upper_bound_transitions);
+        // The estimate is not exact when the phases of the protocols do not align.
+        let proto_info = swarms_to_proto_info(test_utils::get_interfacing_swarms_2());
+        let estimate = estimate_composition(&proto_info);
+        let (composition, _) = compose_protocols(test_utils::get_interfacing_swarms_2()).unwrap();
+        assert_eq!(estimate.states, vec![4, 4, 5]);
+        assert_eq!(estimate.upper_bound_states, 80);
+        assert_eq!(estimate.estimated_states, 20);
+        assert_eq!(composition.node_count(), 18);
+        assert!(estimate.estimated_transitions <= estimate.upper_bound_transitions);
+    }
+
+    #[test]
+    fn test_analyze_protocols() {
+        test_utils::setup_logger();
+        let proto_info = swarms_to_proto_info(test_utils::get_interfacing_swarms_1());
+        let analysis = analyze_protocols(&proto_info);
+        assert_eq!(
+            analysis.concurrent_events,
+            vec![
+                (EventType::new("car"), EventType::new("pos")),
+                (EventType::new("car"), EventType::new("time"))
+            ]
+        );
+        assert_eq!(
+            analysis.branching_events,
+            vec![BTreeSet::from([
+                EventType::new("partID"),
+                EventType::new("time")
+            ])]
+        );
+        assert_eq!(
+            analysis.interfacing_events,
+            BTreeSet::from([EventType::new("part"), EventType::new("partID")])
+        );
+        assert_eq!(analysis.succeeding_events, proto_info.succeeding_events);
+        assert!(analysis.joining_events.is_empty());
+        assert!(analysis.infinitely_looping_events.is_empty());
+
+        let proto_info = swarms_to_proto_info(InterfacingProtocols(
+            test_utils::get_interfacing_swarms_pat_4().0[..2].to_vec(),
+        ));
+        assert_eq!(
+            analyze_protocols(&proto_info).joining_events,
+            BTreeMap::from([(
+                EventType::new("e_ir"),
+                BTreeSet::from([EventType::new("e_r0"), EventType::new("e_r1")])
+            )])
+        );
+    }
+
+    #[test]
+    fn test_compose_protocols_bounded() {
+        test_utils::setup_logger();
+        let protos = test_utils::get_interfacing_swarms_1();
+        let (composition, _) = compose_protocols(protos.clone()).unwrap();
+        let (bounded, _) =
+            compose_protocols_bounded(protos.clone(), composition.node_count()).unwrap();
+        assert_eq!(bounded.node_count(), composition.node_count());
+        assert_eq!(
+            compose_protocols_bounded(protos, 4)
+                .unwrap_err()
+                .to_strings(),
+            vec!["composition exceeds the limit of 4 states".to_string()]
+        );
+    }
+
     #[test]
     fn test_interface() {
         let proto1: SwarmProtocolType =
@@ -967,6 +1299,112 @@ mod tests {
         assert_eq!(expected_errors, errors);
     }
 
+    // The errors reported when composing the given protocols.
+    fn interface_errors(protos: &[SwarmProtocolType]) -> Vec<String> {
+        swarms_to_proto_info(InterfacingProtocols(protos.to_vec()))
+            .to_error_report()
+            .to_strings()
+    }
+
+    #[test]
+    fn test_interface_data_guards() {
+        test_utils::setup_logger();
+        let guarded = test_utils::get_guarded_proto();
+        let mut shipping = test_utils::get_payload_protos().0.remove(1);
+        assert_eq!(
+            interface_errors(&[guarded.clone(), shipping.clone()]),
+            vec!["Event type selected appears with different guards"]
+        );
+
+        shipping.transitions[0].label.guard = guarded.transitions[2].label.guard.clone();
+        assert!(interface_errors(&[guarded, shipping]).is_empty());
+    }
+
+    #[test]
+    fn test_timeouts() {
+        test_utils::setup_logger();
+        let mut timed = test_utils::get_timed_proto();
+        let proto_info = swarms_to_proto_info(InterfacingProtocols(vec![timed.clone()]));
+        assert!(proto_info.no_errors());
+        assert_eq!(
+            proto_info.branching_events,
+            vec![BTreeSet::from([
+                EventType::new("part"),
+                EventType::new("cancelled")
+            ])]
+        );
+
+        // A timeout branches even if it leads to the same state as the transition it competes with.
+        timed.transitions[2].target = State::new("2");
+        let proto_info = swarms_to_proto_info(InterfacingProtocols(vec![timed.clone()]));
+        assert_eq!(proto_info.branching_events.len(), 1);
+
+        let mut other = timed.clone();
+        other.transitions[2].label.timeout = Some(60000);
+        assert_eq!(
+            interface_errors(&[timed.clone(), other]),
+            vec!["Event type cancelled appears with different timeouts"]
+        );
+
+        timed.transitions[1].label.timeout = Some(60000);
+        assert_eq!(
+            interface_errors(&[timed]),
+            vec!["state 1 has more than one timeout transition"]
+        );
+    }
+
+    #[test]
+    fn test_interface_payload_schemas() {
+        test_utils::setup_logger();
+        let mut protos = test_utils::get_payload_protos();
+        let proto_info = swarms_to_proto_info(protos.clone());
+        assert!(proto_info.no_errors());
+        assert_eq!(proto_info.payloads, payloads(&protos));
+        assert_eq!(
+            proto_info.payloads.keys().cloned().collect::<Vec<_>>(),
+            vec![
+                EventType::new("bid"),
+                EventType::new("selected"),
+                EventType::new("shipped")
+            ]
+        );
+
+        // A protocol without a schema for selected does not conflict with one declaring it.
+        let declared = protos.0[1]
+            .payloads
+            .remove(&EventType::new("selected"))
+            .unwrap();
+        assert!(interface_errors(&protos.0).is_empty());
+
+        protos.0[1].payloads.insert(
+            EventType::new("selected"),
+            PayloadSchema::Array {
+                items: Box::new(PayloadSchema::Integer),
+            },
+        );
+        assert_eq!(
+            interface_errors(&protos.0),
+            vec![
+                "Event type selected appears with payload schema { items: integer[]; } and with payload schema integer[]"
+            ]
+        );
+
+        let selected = r#"{ "items": [1, 2] }"#;
+        assert!(
+            declared
+                .validate(&serde_json::from_str(selected).unwrap())
+                .is_empty()
+        );
+        assert_eq!(
+            declared.validate(&serde_json::from_str(r#"{ "items": [1, "2"] }"#).unwrap()),
+            vec!["$.items[1] is not of type integer"]
+        );
+        assert_eq!(
+            protos.0[0].payloads[&EventType::new("bid")]
+                .validate(&serde_json::from_str(r#"{ "bidder": 3 }"#).unwrap()),
+            vec!["$.price is missing", "$.bidder is not of type string"]
+        );
+    }
     #[test]
     fn test_joining_event_types() {
         // e_r0
@@ -1008,9 +1446,12 @@ mod tests {
         macro_rules! check_looping_event_types {
             ($protocol:expr, $expected_infinitely_looping_in_sub:expr) => {
                 let interfacing_protocols = InterfacingProtocols(vec![$protocol.clone()]);
-                let exact_subscriptions =
-                    exact::exact_well_formed_sub(interfacing_protocols.clone(), &BTreeMap::new())
-                        .unwrap();
+                let exact_subscriptions = exact::exact_well_formed_sub(
+                    interfacing_protocols.clone(),
+                    &BTreeMap::new(),
+                    usize::MAX,
+                )
+                .unwrap();
                 let overapproximated_subscriptions = overapproximation::overapprox_well_formed_sub(
                     interfacing_protocols.clone(),
                     &BTreeMap::new(),
@@ -76,6 +76,99 @@ decl_str!(EventType);
 pub struct ProtocolType<L> {
     pub initial: State,
     pub transitions: Vec<Transition<L>>,
+    // Payload schemas of the event types in the protocol. Event types without a schema are unchecked.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[tsify(optional)]
+    pub payloads: BTreeMap<EventType, PayloadSchema>,
+}
+
+// The subset of JSON Schema used to describe event payloads,
+// e.g. { "type": "object", "properties": { "price": { "type": "number" } }, "required": ["price"] }.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(tag = "type", rename_all = "lowercase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum PayloadSchema {
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    String,
+    Array {
+        items: Box<PayloadSchema>,
+    },
+    Object {
+        #[serde(default)]
+        properties: BTreeMap<String, PayloadSchema>,
+        #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+        #[tsify(optional)]
+        required: BTreeSet<String>,
+    },
+}
+
+impl PayloadSchema {
+    // Paths of the parts of value that do not match the schema. Empty if value is valid.
+    pub fn validate(&self, value: &serde_json::Value) -> Vec<String> {
+        let mut errors = vec![];
+        self.validate_at(value, "$", &mut errors);
+        errors
+    }
+
+    fn validate_at(&self, value: &serde_json::Value, path: &str, errors: &mut Vec<String>) {
+        use serde_json::Value;
+        match (self, value) {
+            (PayloadSchema::Null, Value::Null)
+            | (PayloadSchema::Boolean, Value::Bool(_))
+            | (PayloadSchema::Number, Value::Number(_))
+            | (PayloadSchema::String, Value::String(_)) => (),
+            (PayloadSchema::Integer, Value::Number(n)) if n.is_i64() || n.is_u64() => (),
+            (PayloadSchema::Array { items }, Value::Array(values)) => {
+                for (i, v) in values.iter().enumerate() {
+                    items.validate_at(v, &format!("{path}[{i}]"), errors);
+                }
+            }
+            (
+                PayloadSchema::Object {
+                    properties,
+                    required,
+                },
+                Value::Object(fields),
+            ) => {
+                for name in required.iter().filter(|name| !fields.contains_key(*name)) {
+                    errors.push(format!("{path}.{name} is missing"));
+                }
+                for (name, schema) in properties {
+                    if let Some(v) = fields.get(name) {
+                        schema.validate_at(v, &format!("{path}.{name}"), errors);
+                    }
+                }
+            }
+            _ => errors.push(format!("{path} is not of type {self}")),
+        }
+    }
+}
+
+impl fmt::Display for PayloadSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayloadSchema::Null => write!(f, "null"),
+            PayloadSchema::Boolean => write!(f, "boolean"),
+            PayloadSchema::Integer => write!(f, "integer"),
+            PayloadSchema::Number => write!(f, "number"),
+            PayloadSchema::String => write!(f, "string"),
+            PayloadSchema::Array { items } => write!(f, "{items}[]"),
+            PayloadSchema::Object {
+                properties,
+                required,
+            } => {
+                write!(f, "{{ ")?;
+                for (name, schema) in properties {
+                    let optional = if required.contains(name) { "" } else { "?" };
+                    write!(f, "{name}{optional}: {schema}; ")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
 }
 
 #[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -93,13 +186,49 @@ pub struct SwarmLabel {
     pub cmd: Command,
     pub log_type: Vec<EventType>,
     pub role: Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tsify(optional)]
+    pub guard: Option<DataGuard>,
+    // Marks a timeout transition. A timer is started when the source state is entered and
+    // role takes the transition once timeout milliseconds have passed without the state being left.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tsify(optional)]
+    pub timeout: Option<u64>,
 }
 
 impl fmt::Display for SwarmLabel {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}@{}<", self.cmd, self.role)?;
         print_log(&self.log_type, f)?;
-        write!(f, ">")
+        write!(f, ">")?;
+        print_guard(&self.guard, f)?;
+        print_timeout(&self.timeout, f)
+    }
+}
+
+// A payload field of the latest event of some event type.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PayloadField {
+    pub event_type: EventType,
+    pub field: String,
+}
+
+// A predicate that has to hold for a command to be enabled, like `bid.price < limit`.
+// The predicate is evaluated by the runner and not interpreted here. fields are the payload fields it reads,
+// the role executing the command has to subscribe to their event types.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct DataGuard {
+    pub predicate: String,
+    pub fields: Vec<PayloadField>,
+}
+
+impl DataGuard {
+    pub fn event_types(&self) -> BTreeSet<EventType> {
+        self.fields.iter().map(|f| f.event_type.clone()).collect()
     }
 }
 
@@ -111,6 +240,14 @@ pub enum MachineLabel {
     Execute {
         cmd: Command,
         log_type: Vec<EventType>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[tsify(optional)]
+        guard: Option<DataGuard>,
+        // To be executed timeout milliseconds after entering the state, see SwarmLabel.
+        // Runners offer it like any other command, the application starts the timer.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[tsify(optional)]
+        timeout: Option<u64>,
     },
     #[serde(rename_all = "camelCase")]
     Input { event_type: EventType },
@@ -119,9 +256,16 @@ pub enum MachineLabel {
 impl fmt::Display for MachineLabel {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            MachineLabel::Execute { cmd, log_type } => {
+            MachineLabel::Execute {
+                cmd,
+                log_type,
+                guard,
+                timeout,
+            } => {
                 write!(f, "{}/", cmd)?;
-                print_log(&log_type, f)
+                print_log(&log_type, f)?;
+                print_guard(guard, f)?;
+                print_timeout(timeout, f)
             }
             MachineLabel::Input { event_type } => write!(f, "{event_type}?"),
         }
@@ -138,6 +282,20 @@ fn print_log(log: &[EventType], f: &mut fmt::Formatter<'_>) -> fmt::Result {
     Ok(())
 }
 
+fn print_guard(guard: &Option<DataGuard>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match guard {
+        Some(guard) => write!(f, "[{}]", guard.predicate),
+        None => Ok(()),
+    }
+}
+
+fn print_timeout(timeout: &Option<u64>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match timeout {
+        Some(timeout) => write!(f, " after {timeout}ms"),
+        None => Ok(()),
+    }
+}
+
 pub trait StateName {
     fn state_name(&self) -> &State;
 }
@@ -178,6 +336,8 @@ pub struct SubscriptionsWrapped(pub BTreeMap<Role, BTreeSet<EventType>>);
 pub type SwarmProtocolType = ProtocolType<SwarmLabel>;
 #[declare]
 pub type MachineType = ProtocolType<MachineLabel>;
+#[declare]
+pub type SwarmTransition = Transition<SwarmLabel>;
 
 /* Used when combining machines and protocols */
 pub trait EventLabel: Clone + Ord {
@@ -203,7 +363,7 @@ impl EventLabel for MachineLabel {
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct InterfacingProtocols(pub Vec<SwarmProtocolType>);
 
-#[derive(Tsify, Serialize, Deserialize, Debug, Clone)]
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub enum Granularity {
     Fine,
@@ -216,7 +376,18 @@ pub type BranchMap = BTreeMap<EventType, Vec<EventType>>;
 #[declare]
 pub type SpecialEventTypes = BTreeSet<EventType>;
 #[declare]
-pub type ProjToMachineStates = BTreeMap<State, Vec<State>>;
+pub type ProjToMachineStates = BTreeMap<State, Vec<Option<State>>>;
+
+#[declare]
+pub type ProjToProtoStates = BTreeMap<State, Vec<BTreeSet<State>>>;
+
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ProjectionWithProvenance {
+    pub projection: MachineType,
+    pub proj_to_proto_states: ProjToProtoStates,
+}
 
 #[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "camelCase")]
@@ -228,6 +399,334 @@ pub struct ProjectionInfo {
     pub proj_to_machine_states: ProjToMachineStates,
 }
 
+// A machine implementing some role of the protocol at index k of a composition.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ProtocolMachine {
+    pub k: usize,
+    pub machine: MachineType,
+}
+
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ProtocolMachines(pub Vec<ProtocolMachine>);
+
+// A machine implementing a role of the protocol at index k of a composition.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct RoleMachine {
+    pub k: usize,
+    pub role: Role,
+    pub machine: MachineType,
+}
+
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct RoleMachines(pub Vec<RoleMachine>);
+
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct RoleProjectionInfo {
+    pub k: usize,
+    pub role: Role,
+    pub projection_info: ProjectionInfo,
+}
+
+// An entry of a recorded event log. Exports may call the role the source of the event.
+// The payload is checked against the schema of the event type if both are given.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct LogEntry {
+    pub event_type: EventType,
+    #[serde(alias = "source")]
+    pub role: Role,
+    pub timestamp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tsify(optional, type = "any")]
+    pub payload: Option<serde_json::Value>,
+}
+
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ExpectedEvent {
+    pub event_type: EventType,
+    pub role: Role,
+}
+
+// The first entry of a log that the protocol does not allow.
+// index is the position of the entry in the log as given.
+// payload_errors are set if the entry is allowed but its payload does not match the schema.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct NonConformance {
+    pub index: usize,
+    pub entry: LogEntry,
+    pub expected: Vec<ExpectedEvent>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[tsify(optional)]
+    pub payload_errors: Vec<String>,
+}
+
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ConformanceReport {
+    // state of the (composed) protocol after the conforming prefix of the log
+    pub protocol_state: State,
+    pub terminal: bool,
+    pub non_conformance: Option<NonConformance>,
+}
+
+// The first entry of a local log that the projection of the role does not allow.
+// missing_subscriptions are the event types that would have told the role
+// that the protocol went a way where the entry is possible.
+// payload_errors are set if the entry is allowed but its payload does not match the schema.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct LocalNonConformance {
+    pub index: usize,
+    pub entry: LogEntry,
+    pub expected: Vec<EventType>,
+    pub missing_subscriptions: Vec<EventType>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[tsify(optional)]
+    pub payload_errors: Vec<String>,
+}
+
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct LocalConformanceReport {
+    // state of the projection after the conforming prefix of the log
+    pub machine_state: State,
+    pub non_conformance: Option<LocalNonConformance>,
+}
+
+// Branching time temporal formulas over the transitions of a protocol.
+// A formula is evaluated at a transition and paths continue with the
+// transitions leaving its target. Paths end in terminal states.
+// Ax/Ex are strong: they do not hold at transitions into terminal states.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(tag = "op", rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum Formula {
+    True,
+    EventType {
+        #[serde(rename = "eventType")]
+        event_type: EventType,
+    },
+    Command {
+        cmd: Command,
+    },
+    Role {
+        role: Role,
+    },
+    Not {
+        arg: Box<Formula>,
+    },
+    And {
+        left: Box<Formula>,
+        right: Box<Formula>,
+    },
+    Or {
+        left: Box<Formula>,
+        right: Box<Formula>,
+    },
+    Implies {
+        left: Box<Formula>,
+        right: Box<Formula>,
+    },
+    Ax {
+        arg: Box<Formula>,
+    },
+    Ex {
+        arg: Box<Formula>,
+    },
+    Af {
+        arg: Box<Formula>,
+    },
+    Ef {
+        arg: Box<Formula>,
+    },
+    Ag {
+        arg: Box<Formula>,
+    },
+    Eg {
+        arg: Box<Formula>,
+    },
+    Au {
+        left: Box<Formula>,
+        right: Box<Formula>,
+    },
+    Eu {
+        left: Box<Formula>,
+        right: Box<Formula>,
+    },
+}
+
+// A path violating a formula. If loop_start is set the path
+// continues forever with the transitions from loop_start on.
+// branches are further paths from the same first transition that are needed together with path
+// to show the violation, e.g. for a disjunction whose sides are violated along different paths.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Counterexample {
+    pub path: Vec<Transition<SwarmLabel>>,
+    pub loop_start: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[tsify(optional)]
+    pub branches: Vec<Counterexample>,
+}
+
+// A state of a (composed) protocol together with a path leading to it from the initial state.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct StateWitness {
+    pub state: State,
+    pub path: Vec<Transition<SwarmLabel>>,
+}
+
+// A strongly connected component that can not be left. path leads to one of its states.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ClosedComponent {
+    pub states: Vec<State>,
+    pub path: Vec<Transition<SwarmLabel>>,
+}
+
+// The protocol at index k of a composition is in protocol_state waiting for
+// interfacing events that can not be emitted anymore in the composed state.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct InterfaceDeadlock {
+    pub k: usize,
+    pub protocol_state: State,
+    pub waiting_for: Vec<EventType>,
+    pub witness: StateWitness,
+}
+
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct DeadlockReport {
+    pub no_terminal_reachable: Vec<StateWitness>,
+    pub closed_components: Vec<ClosedComponent>,
+    pub interface_deadlocks: Vec<InterfaceDeadlock>,
+}
+
+// The protocol at index k of a composition in state.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ProtocolState {
+    pub k: usize,
+    pub state: State,
+}
+
+// A transition of the protocol at index k that does not occur in the composition.
+// Either its source state is never reached (interface_event is None) or it
+// needs to synchronize on interface_event with the protocols in blocked_by,
+// which never offer it at the same time.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct VanishedTransition {
+    pub k: usize,
+    pub transition: Transition<SwarmLabel>,
+    pub interface_event: Option<EventType>,
+    pub blocked_by: Vec<ProtocolState>,
+}
+
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct DeadCommand {
+    pub cmd: Command,
+    pub role: Role,
+}
+
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct LivenessReport {
+    pub vanished_transitions: Vec<VanishedTransition>,
+    pub dead_commands: Vec<DeadCommand>,
+    pub inactive_roles: Vec<Role>,
+}
+
+// Where a queried path ends: in a state or with a transition emitting an event type.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum PathTarget {
+    State {
+        state: State,
+    },
+    EventType {
+        #[serde(rename = "eventType")]
+        event_type: EventType,
+    },
+}
+
+// Paths from from (the initial state if not given) to target that do not use
+// transitions of the avoided roles, event types or commands. If limit is given,
+// the limit shortest paths without repeated states are returned, otherwise a shortest path.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PathQuery {
+    #[serde(default)]
+    pub from: Option<State>,
+    pub target: PathTarget,
+    #[serde(default)]
+    pub avoid_roles: BTreeSet<Role>,
+    #[serde(default)]
+    pub avoid_event_types: BTreeSet<EventType>,
+    #[serde(default)]
+    pub avoid_commands: BTreeSet<Command>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+// Predicted size of the explicit composition of some protocols.
+// states are the numbers of states of the protocols composed.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct CompositionEstimate {
+    pub states: Vec<usize>,
+    pub upper_bound_states: u64,
+    pub upper_bound_transitions: u64,
+    pub estimated_states: u64,
+    pub estimated_transitions: u64,
+    pub interfacing_event_types: usize,
+    pub concurrent_event_pairs: usize,
+}
+
+// Intermediate results of the analysis of some protocols, see ProtoInfo.
+// joining_events maps each joining event type to its pre-joins: the concurrent event types immediately preceding it.
+// succeeding_events is the union of the succeeding event types of each protocol, not of their composition.
+#[derive(Tsify, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ProtocolAnalysis {
+    pub concurrent_events: Vec<(EventType, EventType)>,
+    pub branching_events: Vec<BTreeSet<EventType>>,
+    pub joining_events: BTreeMap<EventType, BTreeSet<EventType>>,
+    pub succeeding_events: BTreeMap<EventType, BTreeSet<EventType>>,
+    pub interfacing_events: BTreeSet<EventType>,
+    pub infinitely_looping_events: BTreeSet<EventType>,
+}
+
 pub fn to_json_swarm(graph: Graph, initial: NodeId) -> SwarmProtocolType {
     let _span = tracing::info_span!("to_json_swarm").entered();
     let machine_label_mapper = |g: &Graph, eref: EdgeReference<'_, SwarmLabel>| {
@@ -249,5 +748,6 @@ pub fn to_json_swarm(graph: Graph, initial: NodeId) -> SwarmProtocolType {
     SwarmProtocolType {
         initial: graph[initial].state_name().clone(),
         transitions,
+        payloads: BTreeMap::new(),
     }
 }
@@ -1,8 +1,12 @@
+use serde::{Deserialize, Serialize};
+
 use crate::types::typescript_types::EventType;
 
 // Struct representation of an unordered pair of event types
 // Works by always assigning the smallest element to a.
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+// Serialized as a pair and normalized again when deserialized.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+#[serde(into = "(EventType, EventType)", from = "(EventType, EventType)")]
 pub struct UnordEventPair {
     a: EventType,
     b: EventType,
@@ -25,6 +29,18 @@ impl UnordEventPair {
     }
 }
 
+impl From<(EventType, EventType)> for UnordEventPair {
+    fn from((a, b): (EventType, EventType)) -> Self {
+        Self::new(a, b)
+    }
+}
+
+impl From<UnordEventPair> for (EventType, EventType) {
+    fn from(pair: UnordEventPair) -> Self {
+        (pair.a, pair.b)
+    }
+}
+
 // https://stackoverflow.com/questions/30218886/how-to-implement-iterator-and-intoiterator-for-a-simple-struct
 // https://dev.to/wrongbyte/implementing-iterator-and-intoiterator-in-rust-3nio
 pub struct UnordEventPairIterator<'a> {
@@ -208,4 +224,13 @@ mod tests {
         assert_eq!(iter.next(), Some(EventType::new("a")));
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn test_serde() {
+        let pair = UnordEventPair::new(EventType::new("b"), EventType::new("a"));
+        assert_eq!(serde_json::to_string(&pair).unwrap(), r#"["a","b"]"#);
+
+        let swapped: UnordEventPair = serde_json::from_str(r#"["b","a"]"#).unwrap();
+        assert_eq!(swapped, pair);
+    }
 }